@@ -1,277 +1,11248 @@
-use std::f32::consts::PI;
+use std::collections::VecDeque;
+use std::io::{self, Write};
 
 use engine::{
-    physics::EARTH_ACCELERATION_M_PER_S,
-    simulator::{Draw, Expire, Simulation, Tick, TickDrawExpire},
+    components::{Collider, Lifetime, Look, OverLifetime, Renderable, Transform, Velocity},
+    constraints::{
+        DistanceConstraint, HingeAnchor, HingeJoint, MouseJoint, ObjectAnchor, SoftRing,
+    },
+    gamepad::{self, Gamepad},
+    notify::Toasts,
+    persist::ColorDef,
+    physics::{
+        Aabb, Attractor, BodyState, Drag, ExplicitEuler, FluidRegion, ForceField, Material,
+        RigidBody, Rk4, SemiImplicitEuler, Shape, StaticCollider, VelocityVerlet,
+        EARTH_ACCELERATION_M_PER_S, WATER_DENSITY,
+    },
+    real::{consts, consts::PI, from_mq, to_f32, to_mq, vec2, Real, Vec2},
+    record::{self, RecordFormat, Recorder},
+    render::{
+        ArrowColor, ArrowStyle, Camera, DrawContext, Glow, Gradient, MacroquadRenderer, Renderer,
+        Trail,
+    },
+    scene::{LayerId, SceneStack},
+    simulator::{
+        Collide, Components, Draw, DrawLayer, EnergyReading, Entity, Event, Eviction, Exert,
+        Expire, ExpiryPolicy, Impact, Inputs, ObjectCap, ObjectHandle, Phase, Plugin, Resources,
+        Rng, Save, Simulation, SimulationBuilder, Spawn, Spawner, Stats, System, Tick, TickCtx,
+        TickDrawExpire,
+    },
 };
 
 use macroquad::prelude as mq;
+use macroquad::ui::{hash, root_ui, widgets};
+use serde::{Deserialize, Serialize};
 
 const BALL_EXPIRY_TIME: f64 = 2.;
-const FLOOR_Y: f32 = 500.;
+/// Longest a launched ball lasts, resting or not, once the next-ball window gives balls a
+/// lifetime. Until then they last until they come to rest.
+const BALL_LIFETIME: f64 = 30.;
+/// How far back the line behind each ball reaches
+const BALL_TRAIL_SECONDS: f64 = 0.75;
+/// How opaque a trail is at its head, relative to the ball
+const TRAIL_ALPHA: f32 = 0.4;
+/// Width and height of the pictures balls can wear, in texels
+const SPRITE_SIZE: u16 = 64;
+/// Balls faster than this, in metres per second, are drawn smeared out along their path
+const MOTION_BLUR_SPEED: f32 = 10.;
+/// How far back the smear reaches, as seconds of travel. About a frame at 30 FPS.
+const MOTION_BLUR_SECONDS: f32 = 1. / 30.;
+const MOTION_BLUR_GHOSTS: usize = 4;
+/// How many screen pixels a metre of world takes up before any zooming
+const PIXELS_PER_METER: f32 = 40.;
+const FLOOR_Y: Real = 12.5;
 const TICK_LEN_SECONDS: f64 = 0.0167 / 2.;
-const GRAVITY_MULTIPLIER: f64 = 40.;
-const DAMPENING_MULTIPLIER: f32 = 0.8;
 const ARROW_LEN_MULTIPLIER: f32 = 0.2;
+/// Pixels of dial arrow per metre per second squared
+const WIND_ARROW_LEN_MULTIPLIER: f32 = 28.;
+const GRAVITY_ARROW_LEN_MULTIPLIER: f32 = 2.8;
+/// How fast the arrow keys swing gravity around, in radians per second
+const GRAVITY_ROTATION_SPEED: f32 = std::f32::consts::FRAC_PI_2;
+const SPHERE_DRAG_COEFFICIENT: Real = 0.47;
+const BLOCK_DRAG_COEFFICIENT: Real = 1.05;
+const GRAVITY_WELL_STRENGTH: Real = 62.5;
+/// How long a fountain keeps spraying before it runs dry
+const EMITTER_LIFETIME: f64 = 5.;
+/// Held to make emitters aim at the pointer
+const AIM_KEY: mq::KeyCode = Action::Aim.key();
+/// Keys objects get to hear about through their tick context
+const OBJECT_KEYS: [mq::KeyCode; 1] = [AIM_KEY];
+const GRAVITY_WELL_CORE_RADIUS: Real = 0.5;
+const GRAVITY_WELL_LIFETIME: f64 = 8.;
+/// In coulombs
+const BALL_CHARGE: Real = 4.66e-5;
+const EXPLOSION_RADIUS: Real = 3.75;
+const EXPLOSION_STRENGTH: Real = 9.375;
+const EXPLOSION_FLASH_TIME: f64 = 0.3;
+/// Collisions harder than this throw off a spark
+const SPARK_IMPULSE_THRESHOLD: Real = 2.34;
+const SPARK_LIFETIME: f64 = 0.2;
+/// Balls hitting anything harder than this kick up a little burst of particles
+const BURST_IMPULSE_THRESHOLD: Real = 3.;
+const BURST_PARTICLES: usize = 5;
+/// Slowest and fastest a particle flies off, in metres per second
+const BURST_SPEED: (Real, Real) = (1., 3.);
+const PARTICLE_LIFETIME: f64 = 0.35;
+/// Particles a right-clicked ball bursts into, flung out at between these speeds in m/s
+const POP_PARTICLES: usize = 12;
+const POP_SPEED: (Real, Real) = (2., 4.);
+const PARTICLE_RADIUS: Real = 0.04;
+/// Negative, so balloons drift upwards
+const BALLOON_GRAVITY_SCALE: Real = -0.3;
+/// Enough for a busy scene, but few enough that holding the mouse down can't bog it down
+const MAX_OBJECTS: usize = 400;
+/// Comfortably above anything the demo throws around on purpose
+const MAX_SPEED: Real = 75.;
+/// The speeds each press of - or = steps time through, slowest first. Normal speed is one of
+/// them, so stepping back always lands on it exactly.
+const TIME_SCALES: [f64; 7] = [0.1, 0.25, 0.5, 1., 2., 4., 10.];
+/// How much history holding R can scrub back through
+const REWIND_SECONDS: f64 = 10.;
+/// What the window is called unless `--title` says otherwise
+const WINDOW_TITLE: &str = "Bouncing Balls";
+/// The window's size in pixels unless `--window-size WIDTHxHEIGHT` says otherwise, which the
+/// sandbox is laid out to fit
+const WINDOW_SIZE: (i32, i32) = (800, 600);
+/// Where F6 saves the sandbox and F9 loads it back from
+const SAVE_PATH: &str = "sandbox.save";
+/// Where screenshots and recordings go, unless `--screenshot-dir` says otherwise
+const SCREENSHOT_DIR: &str = "screenshots";
+/// A whole number of hundredths of a second per frame, which is all a GIF can do
+const RECORD_FPS: u32 = 20;
+/// Balls this fast or faster are drawn at the hot end of the gradient when colouring by speed,
+/// in metres per second
+const SPEED_COLORING_TOP_SPEED: f32 = 15.;
+/// Rough gap between force-field arrows on screen, in pixels
+const FIELD_ARROW_PIXELS: f32 = 40.;
+/// Closest force-field arrows get however far the view zooms in, in metres
+const FIELD_MIN_SPACING: f32 = 0.5;
+/// Pushes weaker than this get no arrow, in metres per second squared
+const FIELD_MIN_ACCEL: f32 = 1e-3;
+/// Push that draws a force-field arrow at half its longest
+const FIELD_HALF_LENGTH_ACCEL: f32 = 2.;
+/// Balls this fast or faster glow their brightest, in metres per second
+const GLOW_FULL_SPEED: f32 = 12.;
+/// How far past a ball's edge its light spreads before blurring, as a multiple of its radius
+const GLOW_RADIUS_SCALE: f32 = 1.4;
+const GLOW_STRENGTH: f32 = 1.5;
+/// The glow is drawn this many times smaller each way, which also widens the blur
+const GLOW_DOWNSCALE: f32 = 4.;
+/// Grid line spacings to pick from, in metres, finest first
+const GRID_SPACINGS: [f32; 9] = [0.5, 1., 2., 5., 10., 20., 50., 100., 200.];
+/// Closest grid lines may get on screen, in pixels, before a coarser spacing takes over
+const GRID_MIN_PIXELS: f32 = 20.;
+/// Every so many grid lines is brighter and labelled
+const GRID_LABEL_EVERY: i64 = 5;
+/// Recordings are shrunk by this much each way, to keep them a shareable size
+const RECORD_DOWNSCALE: usize = 2;
+/// Recording stops by itself after this long, before the frames eat all the memory
+const RECORD_MAX_SECONDS: u32 = 15;
+/// The background layer only drifts, so it can get away with much longer ticks
+const BACKGROUND_TICK_LEN_SECONDS: f64 = TICK_LEN_SECONDS * 4.;
+const BACKGROUND_MOTES: usize = 40;
+/// The most room the minimap takes up in the corner, in pixels
+const MINIMAP_SIZE: mq::Vec2 = mq::vec2(160., 120.);
+const MINIMAP_MARGIN: f32 = 10.;
+/// How much one notch of the scroll wheel zooms in or out
+const ZOOM_STEP: f32 = 1.1;
+/// How hard a dragged ball is pulled towards the cursor, per kilogram. Stiff enough to keep
+/// up with the mouse, and damped so it only just overshoots.
+const GRAB_STIFFNESS: Real = 400.;
+const GRAB_DAMPING: Real = 36.;
+/// The shortest gap between new balls, however fast they're asked for, unless
+/// `--spawn-cooldown` says otherwise
+const SPAWN_COOLDOWN_SECONDS: f64 = 0.05;
+/// Balls per second poured out when streaming, to start with and at the least and most that
+/// [ and ] can take it to
+const STREAM_RATE: f32 = 8.;
+const STREAM_RATE_RANGE: (f32, f32) = (1., 16.);
+/// Hand-drawn wall ends land on a grid this fine, in metres, unless there's another wall's end
+/// within snapping distance
+const WALL_GRID: Real = 0.25;
+const WALL_SNAP_DISTANCE: Real = 0.3;
+/// Anything shorter is taken for a slip of the mouse
+const WALL_MIN_LENGTH: Real = 0.1;
+const SETTINGS_SIZE: mq::Vec2 = mq::vec2(320., 244.);
+const BALL_DESIGN_SIZE: mq::Vec2 = mq::vec2(320., 452.);
+const QUICK_SLIDERS_SIZE: mq::Vec2 = mq::vec2(260., 50.);
+const PAUSE_MENU_SIZE: mq::Vec2 = mq::vec2(160., 150.);
+/// How far back the frame timing graph goes, in real seconds
+const TIMING_HISTORY_SECONDS: f64 = 5.;
+const TIMING_GRAPH_SIZE: mq::Vec2 = mq::vec2(200., 60.);
+/// How far back the object count and kinetic energy graphs go, in real seconds. Longer
+/// than the timing graph, since what they show builds up slowly.
+const STATS_HISTORY_SECONDS: f64 = 20.;
+const STATS_GRAPH_SIZE: mq::Vec2 = mq::vec2(160., 40.);
+/// The outline of the sandbox's box, left wall first. Closed off at the top so balloons have
+/// somewhere to come to rest.
+const SANDBOX_WALLS: [Vec2; 5] = [
+    vec2(5., 0.),
+    vec2(5., FLOOR_Y),
+    vec2(12.5, FLOOR_Y),
+    vec2(12.5, 0.),
+    vec2(5., 0.),
+];
+/// How many columns the help list is spread over
+const HELP_COLUMNS: usize = 3;
+/// The smallest the selection panel can shrink a ball to, in metres
+const INSPECTOR_MIN_RADIUS: Real = 0.05;
+/// How far a finger can wander, in pixels, and still be tapping rather than dragging
+const TAP_SLOP: f32 = 12.;
+/// How long a finger has to stay put to pop the ball under it
+const LONG_PRESS_SECONDS: f64 = 0.6;
+/// How fast a gamepad launches with the trigger all the way in, in m/s
+const GAMEPAD_LAUNCH_SPEED: f32 = 30.;
+/// How fast the right stick moves the pointer when pushed all the way, in pixels per second
+const GAMEPAD_POINTER_SPEED: f32 = 600.;
+/// Launch speed in m/s for each metre a ball's pulled back before letting go
+const LAUNCH_SPEED_PER_METER: f32 = 4.;
+/// How far the split screen's follow camera zooms in on its ball
+const FOLLOW_ZOOM: f32 = 2.5;
+const LASER_ORIGIN: Vec2 = vec2(5.125, 0.125);
+const LASER_RANGE: Real = 25.;
+const SOFT_BALL_NODES: usize = 16;
+const SOFT_BALL_STIFFNESS: Real = 125.;
+const SOFT_BALL_DAMPING: Real = 0.9375;
+/// Gas pressure inside a soft ball at its resting size
+const SOFT_BALL_PRESSURE: Real = 9.375;
+/// What the number keys drop at the cursor, 1 to 9 in order
+const SPAWN_PRESETS: [Preset; 9] = [
+    Preset::ball("pebble", 0.1, Material::STEEL, mq::LIGHTGRAY).speed(20.),
+    Preset::ball("boulder", 1.5, Material::STEEL, mq::DARKGRAY),
+    Preset::block("crate", 4, 0.6, mq::BROWN),
+    Preset::ball("cluster", 0.2, Material::RUBBER, mq::GOLD).count(10),
+    Preset::ball("balloon", 0.45, Material::RUBBER, mq::MAGENTA)
+        .gravity_scale(BALLOON_GRAVITY_SCALE),
+    Preset::ball("clay", 0.5, Material::CLAY, mq::BEIGE),
+    Preset::block("wedge", 3, 0.5, mq::ORANGE),
+    Preset::block("hexagon", 6, 0.5, mq::SKYBLUE),
+    Preset::ball("bunch", 0.3, Material::RUBBER, mq::PINK)
+        .gravity_scale(BALLOON_GRAVITY_SCALE)
+        .count(5),
+];
 
-fn draw_arrow(
-    x1: f32,
-    y1: f32,
-    x2: f32,
-    y2: f32,
-    thickness: f32,
-    color: mq::Color,
-    head_ratio: f32,
-) {
-    mq::draw_line(x1, y1, x2, y2, thickness, color);
-    // arrow head
-    let tip_theta: f32 = PI / 6. - PI;
-    let tail_pos = mq::vec2(x1, y1);
-    let tip_pos = mq::vec2(x2, y2);
-    let tip_from_origin = tip_pos - tail_pos;
-    let a_unit = mq::Vec2::from_angle(tip_theta);
-    let b_unit = mq::Vec2::from_angle(-tip_theta);
-
-    let a = a_unit.rotate(tip_from_origin) * head_ratio + tip_pos;
-    let b = b_unit.rotate(tip_from_origin) * head_ratio + tip_pos;
-
-    mq::draw_triangle(
-        mq::vec2(x2, y2),
-        mq::vec2(a.x, a.y),
-        mq::vec2(b.x, b.y),
-        color,
-    );
-}
 mod engine {
+    /// The numbers physics is done in. Normally `f32` like everything macroquad draws, or
+    /// `f64` with the `f64` feature, for runs that have to come out the same to the last bit
+    /// every time, like replays and lockstep multiplayer. Drawing stays in `f32` either way.
+    pub mod real {
+        use macroquad::prelude as mq;
+
+        #[cfg(not(feature = "f64"))]
+        mod precision {
+            pub use macroquad::prelude::{vec2, Mat2, Vec2};
+            pub use std::f32::consts;
+
+            pub type Real = f32;
+        }
+
+        #[cfg(feature = "f64")]
+        mod precision {
+            pub use glam::{dvec2 as vec2, DMat2 as Mat2, DVec2 as Vec2};
+            pub use std::f64::consts;
+
+            pub type Real = f64;
+        }
+
+        pub use precision::*;
+
+        /// A physics vector, in the `f32`s macroquad draws with
+        #[allow(clippy::unnecessary_cast)]
+        pub fn to_mq(v: Vec2) -> mq::Vec2 {
+            mq::vec2(v.x as f32, v.y as f32)
+        }
+
+        /// A point or vector from macroquad, like the cursor, for physics to use
+        pub fn from_mq(v: mq::Vec2) -> Vec2 {
+            vec2(v.x as Real, v.y as Real)
+        }
+
+        /// A physics number, in the `f32`s macroquad draws with
+        #[allow(clippy::unnecessary_cast)]
+        pub fn to_f32(x: Real) -> f32 {
+            x as f32
+        }
+    }
+
     pub mod simulator {
+        use std::any::{Any, TypeId};
+        use std::collections::{HashMap, HashSet, VecDeque};
+        use std::io;
+        use std::path::Path;
+
+        use macroquad::prelude as mq;
+        use serde::de::{self, DeserializeOwned};
+        use serde::{Deserialize, Serialize};
+
+        use super::broad_phase::{AllPairs, BroadPhase, Quadtree};
+        use super::components::{Collider, Lifetime, Renderable, Transform, Velocity};
+        use super::constraints::{
+            DistanceConstraint, HingeAnchor, HingeJoint, MouseJoint, ObjectAnchor, SoftRing,
+        };
+        use super::notify::Toasts;
+        use super::persist::{self, invalid, Loader};
+        use super::physics::{
+            self, Aabb, AirResistance, BodyState, ConstraintEnd, FluidRegion, ForceField, Gravity,
+            Integrator, RigidBody, SemiImplicitEuler, Shape, StaticCollider, Wind, AIR_DENSITY,
+        };
+        use super::real::{to_mq, vec2, Real, Vec2};
+        use super::render::{Camera, DrawContext, Renderer};
+
         pub trait Tick {
             /// Handle a tick
-            fn on_tick(&mut self, tick_len_seconds: f64);
+            fn on_tick(&mut self, ctx: &TickCtx);
+        }
+
+        /// What the player is up to as of the latest frame, for objects that react to it.
+        /// The main loop fills this in; headless runs leave it empty.
+        #[derive(Clone, Debug, Default)]
+        pub struct Inputs {
+            /// Where the pointer is, in world coordinates
+            pub cursor: Vec2,
+            pub keys_down: HashSet<mq::KeyCode>,
+            pub mouse_buttons_down: HashSet<mq::MouseButton>,
+        }
+
+        impl Inputs {
+            pub fn is_key_down(&self, key: mq::KeyCode) -> bool {
+                self.keys_down.contains(&key)
+            }
+
+            pub fn is_mouse_button_down(&self, button: mq::MouseButton) -> bool {
+                self.mouse_buttons_down.contains(&button)
+            }
+        }
+
+        /// State shared across a whole simulation, like a score or a setting, holding at most
+        /// one value of each type. Systems can change it as they go and objects can read it.
+        #[derive(Default)]
+        pub struct Resources {
+            by_type: HashMap<TypeId, Box<dyn Any>>,
+        }
+
+        impl Resources {
+            /// Add a resource, handing back whatever was there of the same type
+            pub fn insert<R: Any>(&mut self, resource: R) -> Option<R> {
+                self.by_type
+                    .insert(TypeId::of::<R>(), Box::new(resource))
+                    .and_then(|old| old.downcast().ok())
+                    .map(|old| *old)
+            }
+
+            pub fn get<R: Any>(&self) -> Option<&R> {
+                self.by_type.get(&TypeId::of::<R>())?.downcast_ref()
+            }
+
+            pub fn get_mut<R: Any>(&mut self) -> Option<&mut R> {
+                self.by_type.get_mut(&TypeId::of::<R>())?.downcast_mut()
+            }
+
+            pub fn remove<R: Any>(&mut self) -> Option<R> {
+                self.by_type
+                    .remove(&TypeId::of::<R>())
+                    .and_then(|old| old.downcast().ok())
+                    .map(|old| *old)
+            }
+        }
+
+        /// Plain data an object or entity can have attached to it, like where it is or how
+        /// it's drawn. Components are cloned along with everything else for rewinding.
+        pub trait Component: Any + Clone {}
+
+        impl<C: Any + Clone> Component for C {}
+
+        /// Every component of one kind, indexed by the slot of whatever it's attached to
+        #[derive(Clone)]
+        struct Column<C>(Vec<Option<(ObjectHandle, C)>>);
+
+        impl<C> Column<C> {
+            fn get(&self, handle: ObjectHandle) -> Option<&C> {
+                match self.0.get(handle.slot as usize)? {
+                    Some((owner, component)) if *owner == handle => Some(component),
+                    _ => None,
+                }
+            }
+
+            fn get_mut(&mut self, handle: ObjectHandle) -> Option<&mut C> {
+                match self.0.get_mut(handle.slot as usize)? {
+                    Some((owner, component)) if *owner == handle => Some(component),
+                    _ => None,
+                }
+            }
+        }
+
+        /// A column whose kind of component has been forgotten, so they can all be kept
+        /// together
+        trait AnyColumn {
+            /// Drop whatever's in `slot`
+            fn clear(&mut self, slot: usize);
+
+            fn clone_column(&self) -> Box<dyn AnyColumn>;
+
+            fn as_any(&self) -> &dyn Any;
+
+            fn as_any_mut(&mut self) -> &mut dyn Any;
+        }
+
+        impl<C: Component> AnyColumn for Column<C> {
+            fn clear(&mut self, slot: usize) {
+                if let Some(entry) = self.0.get_mut(slot) {
+                    *entry = None;
+                }
+            }
+
+            fn clone_column(&self) -> Box<dyn AnyColumn> {
+                Box::new(self.clone())
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+        }
+
+        /// The components attached to everything in a simulation, objects and entities alike.
+        /// Each kind is kept in a column of its own, so going through every one of a kind
+        /// skips everything without one, and always goes in the same order.
+        #[derive(Default)]
+        pub struct Components {
+            /// The generation of whatever's in each slot, if anything is
+            live: Vec<Option<u32>>,
+            columns: HashMap<TypeId, Box<dyn AnyColumn>>,
+        }
+
+        impl Clone for Components {
+            fn clone(&self) -> Self {
+                Self {
+                    live: self.live.clone(),
+                    columns: self
+                        .columns
+                        .iter()
+                        .map(|(&kind, column)| (kind, column.clone_column()))
+                        .collect(),
+                }
+            }
+        }
+
+        impl Components {
+            fn is_live(&self, handle: ObjectHandle) -> bool {
+                self.live.get(handle.slot as usize) == Some(&Some(handle.generation))
+            }
+
+            /// Start taking components for something that's just arrived
+            fn admit(&mut self, handle: ObjectHandle) {
+                let slot = handle.slot as usize;
+                if self.live.len() <= slot {
+                    self.live.resize(slot + 1, None);
+                }
+                self.live[slot] = Some(handle.generation);
+            }
+
+            /// Drop everything attached to something that's gone
+            fn dismiss(&mut self, handle: ObjectHandle) {
+                if !self.is_live(handle) {
+                    return;
+                }
+                let slot = handle.slot as usize;
+                self.live[slot] = None;
+                self.columns.values_mut().for_each(|c| c.clear(slot));
+            }
+
+            fn column<C: Component>(&self) -> Option<&Column<C>> {
+                self.columns
+                    .get(&TypeId::of::<C>())?
+                    .as_any()
+                    .downcast_ref()
+            }
+
+            fn column_mut<C: Component>(&mut self) -> Option<&mut Column<C>> {
+                self.columns
+                    .get_mut(&TypeId::of::<C>())?
+                    .as_any_mut()
+                    .downcast_mut()
+            }
+
+            /// Attach a component, handing back whatever of the same kind it replaces. Nothing
+            /// can be attached to something that's gone.
+            pub fn insert<C: Component>(
+                &mut self,
+                handle: ObjectHandle,
+                component: C,
+            ) -> Option<C> {
+                if !self.is_live(handle) {
+                    return None;
+                }
+                let column = self
+                    .columns
+                    .entry(TypeId::of::<C>())
+                    .or_insert_with(|| Box::new(Column::<C>(Vec::new())))
+                    .as_any_mut()
+                    .downcast_mut::<Column<C>>()
+                    .expect("columns are keyed by the type they hold");
+                let slot = handle.slot as usize;
+                if column.0.len() <= slot {
+                    column.0.resize_with(slot + 1, || None);
+                }
+                column.0[slot]
+                    .replace((handle, component))
+                    .map(|(_, old)| old)
+            }
+
+            pub fn get<C: Component>(&self, handle: ObjectHandle) -> Option<&C> {
+                self.column()?.get(handle)
+            }
+
+            #[allow(dead_code)]
+            pub fn get_mut<C: Component>(&mut self, handle: ObjectHandle) -> Option<&mut C> {
+                self.column_mut()?.get_mut(handle)
+            }
+
+            #[allow(dead_code)]
+            pub fn remove<C: Component>(&mut self, handle: ObjectHandle) -> Option<C> {
+                let entry = self.column_mut::<C>()?.0.get_mut(handle.slot as usize)?;
+                if entry.as_ref().is_some_and(|(owner, _)| *owner == handle) {
+                    entry.take().map(|(_, component)| component)
+                } else {
+                    None
+                }
+            }
+
+            /// Every component of a kind, with the handle of what it's attached to
+            pub fn iter<C: Component>(&self) -> impl Iterator<Item = (ObjectHandle, &C)> {
+                self.column::<C>()
+                    .into_iter()
+                    .flat_map(|column| column.0.iter().flatten())
+                    .map(|(handle, component)| (*handle, component))
+            }
+
+            pub fn iter_mut<C: Component>(
+                &mut self,
+            ) -> impl Iterator<Item = (ObjectHandle, &mut C)> {
+                self.column_mut::<C>()
+                    .into_iter()
+                    .flat_map(|column| column.0.iter_mut().flatten())
+                    .map(|(handle, component)| (*handle, component))
+            }
+
+            /// Call `f` on everything that has both an `A` and a `B`, which have to be
+            /// different kinds of component
+            pub fn for_each_pair<A: Component, B: Component>(
+                &mut self,
+                mut f: impl FnMut(ObjectHandle, &mut A, &mut B),
+            ) {
+                assert_ne!(TypeId::of::<A>(), TypeId::of::<B>(), "a pair of one kind");
+                // out of the map while `A` is borrowed from it, then back in
+                let Some(mut bs) = self.columns.remove(&TypeId::of::<B>()) else {
+                    return;
+                };
+                let column = bs.as_any_mut().downcast_mut::<Column<B>>();
+                if let (Some(b_column), Some(a_column)) = (column, self.column_mut::<A>()) {
+                    for (handle, a) in a_column.0.iter_mut().flatten() {
+                        if let Some(b) = b_column.get_mut(*handle) {
+                            f(*handle, a, b);
+                        }
+                    }
+                }
+                self.columns.insert(TypeId::of::<B>(), bs);
+            }
+        }
+
+        /// One component of an entity waiting to be spawned
+        trait Attach {
+            fn attach(self: Box<Self>, components: &mut Components, handle: ObjectHandle);
+        }
+
+        impl<C: Component> Attach for C {
+            fn attach(self: Box<Self>, components: &mut Components, handle: ObjectHandle) {
+                components.insert(handle, *self);
+            }
+        }
+
+        /// Something that's nothing but its components, like a puff of debris. Entities aren't
+        /// objects: the simulation moves, ages and draws them by their components alone, and
+        /// systems handle anything else. They don't count towards the object cap, aren't
+        /// saved, and come and go without events.
+        #[derive(Default)]
+        pub struct Entity {
+            components: Vec<Box<dyn Attach>>,
+        }
+
+        impl Entity {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            pub fn with<C: Component>(mut self, component: C) -> Self {
+                self.components.push(Box::new(component));
+                self
+            }
+        }
+
+        /// Everything an object is told about the tick it's handling
+        pub struct TickCtx<'a> {
+            /// Seconds the tick lasts, or the substep if the simulation splits ticks up
+            pub dt: f64,
+            /// Simulation time at the start of the tick
+            pub time: f64,
+            /// Which tick this is, counting from zero
+            pub tick: usize,
+            pub inputs: &'a Inputs,
+            pub resources: &'a Resources,
+        }
+
+        pub trait Draw {
+            /// Draw in world coordinates; the context takes care of the camera
+            fn on_draw(&self, ctx: &mut DrawContext);
+
+            /// Which layer to draw on, which is in with the bodies unless said otherwise
+            fn draw_layer(&self) -> DrawLayer {
+                DrawLayer::Bodies
+            }
+
+            /// Draw whatever this gives off light from, if anything, for the glow pass to
+            /// blur into a halo
+            fn on_draw_glow(&self, _ctx: &mut DrawContext) {}
+
+            /// Whether this is only for show, like a spark or debris. Effects don't count
+            /// towards the object cap and nothing is evicted to make room for one.
+            fn is_effect(&self) -> bool {
+                false
+            }
+        }
+
+        /// Where an object is drawn in the stack, bottom first. Objects on the same layer
+        /// are drawn in the order they were added.
+        #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+        pub enum DrawLayer {
+            /// Under the scenery, like scenery of its own
+            Background,
+            Bodies,
+            /// Over the bodies, like debris and flashes
+            Particles,
+            /// Over everything, water included, like markers and labels
+            Annotations,
+        }
+
+        pub trait Expire {
+            fn is_expired(&self) -> bool;
+        }
+
+        /// A rule for when an object should go. A policy keeps its own timers, so an object
+        /// just ticks it along with itself and asks it whether its time is up.
+        #[derive(Clone, Serialize, Deserialize)]
+        pub enum ExpiryPolicy {
+            Never,
+            /// Once the body has spent `seconds` resting on the ground, all told
+            OnFloor {
+                seconds: f64,
+                elapsed: f64,
+            },
+            /// Once `seconds` have gone by, whatever the body is doing
+            Lifetime {
+                seconds: f64,
+                elapsed: f64,
+            },
+            /// As soon as the body is entirely outside these bounds
+            OffScreen(Aabb),
+            /// When any one of these would expire
+            Any(Vec<ExpiryPolicy>),
+            /// Only once every one of these would
+            All(Vec<ExpiryPolicy>),
+        }
+
+        impl ExpiryPolicy {
+            pub fn on_floor(seconds: f64) -> Self {
+                ExpiryPolicy::OnFloor {
+                    seconds,
+                    elapsed: 0.,
+                }
+            }
+
+            pub fn lifetime(seconds: f64) -> Self {
+                ExpiryPolicy::Lifetime {
+                    seconds,
+                    elapsed: 0.,
+                }
+            }
+
+            #[allow(dead_code)]
+            pub fn off_screen(bounds: Aabb) -> Self {
+                ExpiryPolicy::OffScreen(bounds)
+            }
+
+            /// Expire when either this or `other` would
+            pub fn or(self, other: ExpiryPolicy) -> Self {
+                match self {
+                    ExpiryPolicy::Any(mut policies) => {
+                        policies.push(other);
+                        ExpiryPolicy::Any(policies)
+                    }
+                    policy => ExpiryPolicy::Any(vec![policy, other]),
+                }
+            }
+
+            /// Expire only once both this and `other` would
+            #[allow(dead_code)]
+            pub fn and(self, other: ExpiryPolicy) -> Self {
+                match self {
+                    ExpiryPolicy::All(mut policies) => {
+                        policies.push(other);
+                        ExpiryPolicy::All(policies)
+                    }
+                    policy => ExpiryPolicy::All(vec![policy, other]),
+                }
+            }
+
+            /// Run the timers on, given the body of the object the policy belongs to
+            pub fn on_tick(&mut self, body: Option<&RigidBody>, tick_len_seconds: f64) {
+                match self {
+                    ExpiryPolicy::OnFloor { elapsed, .. } => {
+                        if body.is_some_and(|b| b.grounded) {
+                            *elapsed += tick_len_seconds;
+                        }
+                    }
+                    ExpiryPolicy::Lifetime { elapsed, .. } => *elapsed += tick_len_seconds,
+                    ExpiryPolicy::Any(policies) | ExpiryPolicy::All(policies) => policies
+                        .iter_mut()
+                        .for_each(|p| p.on_tick(body, tick_len_seconds)),
+                    ExpiryPolicy::Never | ExpiryPolicy::OffScreen(_) => {}
+                }
+            }
+
+            pub fn is_expired(&self, body: Option<&RigidBody>) -> bool {
+                match self {
+                    ExpiryPolicy::Never => false,
+                    ExpiryPolicy::OnFloor { seconds, elapsed }
+                    | ExpiryPolicy::Lifetime { seconds, elapsed } => elapsed >= seconds,
+                    ExpiryPolicy::OffScreen(bounds) => {
+                        body.is_some_and(|b| !b.aabb().overlaps(bounds))
+                    }
+                    ExpiryPolicy::Any(policies) => policies.iter().any(|p| p.is_expired(body)),
+                    ExpiryPolicy::All(policies) => policies.iter().all(|p| p.is_expired(body)),
+                }
+            }
+
+            /// Seconds until the timers alone would run out, supposing every one of them
+            /// keeps running, or infinity if they never will
+            pub fn time_left(&self) -> f64 {
+                match self {
+                    ExpiryPolicy::Never | ExpiryPolicy::OffScreen(_) => f64::INFINITY,
+                    ExpiryPolicy::OnFloor { seconds, elapsed }
+                    | ExpiryPolicy::Lifetime { seconds, elapsed } => (seconds - elapsed).max(0.),
+                    ExpiryPolicy::Any(policies) => policies
+                        .iter()
+                        .map(ExpiryPolicy::time_left)
+                        .fold(f64::INFINITY, f64::min),
+                    ExpiryPolicy::All(policies) => policies
+                        .iter()
+                        .map(ExpiryPolicy::time_left)
+                        .fold(0., f64::max),
+                }
+            }
+        }
+
+        pub trait Collide {
+            /// The rigid body the simulation should collide against, if any
+            fn body(&self) -> Option<&RigidBody> {
+                None
+            }
+
+            fn body_mut(&mut self) -> Option<&mut RigidBody> {
+                None
+            }
+
+            /// Hear about something the body hit during the step, scenery included, with the
+            /// chance to spawn something there and then, like debris
+            fn on_impact(&mut self, _impact: &Impact, _spawner: &mut Spawner) {}
+        }
+
+        /// One hit an object's body took during a step
+        #[derive(Clone, Copy, Debug)]
+        pub struct Impact {
+            /// What it hit, or `None` for scenery
+            pub other: Option<ObjectHandle>,
+            pub point: Vec2,
+            /// Size of the impulse along the contact normal
+            pub impulse: Real,
+        }
+
+        pub trait Exert {
+            /// Force this object applies to another body, e.g. an attractor pulling it in
+            fn force_on(&self, _body: &RigidBody) -> Vec2 {
+                Vec2::ZERO
+            }
+        }
+
+        pub trait Spawn {
+            /// Add new objects to the simulation once this step is done
+            fn on_spawn(&mut self, _spawner: &mut Spawner) {}
+        }
+
+        pub trait Save {
+            /// What to write out for the object, along with the tag its loader is registered
+            /// under, or `None` to leave it out of saves
+            fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+                None
+            }
+        }
+
+        /// Most expired objects kept around for reuse
+        const MAX_POOLED_OBJECTS: usize = 256;
+
+        /// Most effects there can be at once in a simulation with an object cap. Past that,
+        /// new ones are dropped rather than anything else making way.
+        const MAX_EFFECTS: usize = 1000;
+
+        /// How an object adds others to the simulation, or tells the main loop about
+        /// something, from within a step
+        pub struct Spawner<'a> {
+            rng: &'a mut Rng,
+            pool: &'a mut Vec<Box<dyn TickDrawExpire>>,
+            spawned: &'a mut Vec<Box<dyn TickDrawExpire>>,
+            entities: &'a mut Vec<Entity>,
+            events: &'a mut Vec<Event>,
+        }
+
+        impl Spawner<'_> {
+            /// The simulation's own generator. Anything random about what's spawned should
+            /// come from here, so a seeded run spawns the same things.
+            pub fn rng(&mut self) -> &mut Rng {
+                self.rng
+            }
+
+            pub fn spawn<U: TickDrawExpire + Clone + 'static>(&mut self, object: U) {
+                self.spawned.push(recycle(self.pool, object));
+            }
+
+            pub fn spawn_entity(&mut self, entity: Entity) {
+                self.entities.push(entity);
+            }
+
+            /// Queue up an event for the main loop
+            pub fn emit(&mut self, event: Event) {
+                self.events.push(event);
+            }
+        }
+
+        /// Box up an object, reusing the allocation of an expired one of the same kind if
+        /// there's one in the pool
+        fn recycle<U: TickDrawExpire + Clone + 'static>(
+            pool: &mut Vec<Box<dyn TickDrawExpire>>,
+            object: U,
+        ) -> Box<dyn TickDrawExpire> {
+            let Some(at) = pool.iter().position(|o| (**o).as_any().is::<U>()) else {
+                return Box::new(object);
+            };
+            match pool.swap_remove(at).into_any().downcast::<U>() {
+                Ok(mut boxed) => {
+                    *boxed = object;
+                    boxed
+                }
+                Err(_) => Box::new(object),
+            }
+        }
+
+        pub trait TickDrawExpire:
+            Tick + Draw + Expire + Collide + Exert + Spawn + Save + CloneObject + Send + Sync
+        {
+        }
+
+        /// Copies an object behind a box, so a simulation of mixed objects can be rewound
+        pub trait CloneObject {
+            fn clone_object(&self) -> Box<dyn TickDrawExpire>;
+
+            fn as_any(&self) -> &dyn Any;
+
+            fn as_any_mut(&mut self) -> &mut dyn Any;
+
+            fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+            /// Name of the object's type, without its module path
+            fn kind(&self) -> &'static str;
+        }
+
+        impl<T: TickDrawExpire + Clone + 'static> CloneObject for T {
+            fn clone_object(&self) -> Box<dyn TickDrawExpire> {
+                Box::new(self.clone())
+            }
+
+            fn as_any(&self) -> &dyn Any {
+                self
+            }
+
+            fn as_any_mut(&mut self) -> &mut dyn Any {
+                self
+            }
+
+            fn into_any(self: Box<Self>) -> Box<dyn Any> {
+                self
+            }
+
+            fn kind(&self) -> &'static str {
+                let name = std::any::type_name::<T>();
+                name.rsplit("::").next().unwrap_or(name)
+            }
+        }
+
+        /// Turn a spawned object into whatever a simulation holds, if it can go in there at
+        /// all: as is for a simulation of boxes, or unboxed if it's the one kind of object
+        fn adopt<T: 'static>(object: Box<dyn TickDrawExpire>) -> Option<T> {
+            let mut object = Some(object);
+            if let Some(as_is) = (&mut object as &mut dyn Any).downcast_mut::<Option<T>>() {
+                return as_is.take();
+            }
+            object?
+                .into_any()
+                .downcast::<T>()
+                .ok()
+                .map(|object| *object)
+        }
+
+        /// The other way round from `adopt`: back into a box, if it was in one to begin with
+        fn boxed<T: 'static>(object: T) -> Option<Box<dyn TickDrawExpire>> {
+            let mut object = Some(object);
+            (&mut object as &mut dyn Any)
+                .downcast_mut::<Option<Box<dyn TickDrawExpire>>>()
+                .and_then(Option::take)
+        }
+
+        impl Clone for Box<dyn TickDrawExpire> {
+            fn clone(&self) -> Self {
+                (**self).clone_object()
+            }
+        }
+
+        impl<T: Tick + ?Sized> Tick for Box<T> {
+            fn on_tick(&mut self, ctx: &TickCtx) {
+                (**self).on_tick(ctx)
+            }
+        }
+
+        impl<T: Draw + ?Sized> Draw for Box<T> {
+            fn on_draw(&self, ctx: &mut DrawContext) {
+                (**self).on_draw(ctx)
+            }
+
+            fn draw_layer(&self) -> DrawLayer {
+                (**self).draw_layer()
+            }
+
+            fn on_draw_glow(&self, ctx: &mut DrawContext) {
+                (**self).on_draw_glow(ctx)
+            }
+
+            fn is_effect(&self) -> bool {
+                (**self).is_effect()
+            }
+        }
+
+        impl<T: Expire + ?Sized> Expire for Box<T> {
+            fn is_expired(&self) -> bool {
+                (**self).is_expired()
+            }
+        }
+
+        impl<T: Collide + ?Sized> Collide for Box<T> {
+            fn body(&self) -> Option<&RigidBody> {
+                (**self).body()
+            }
+
+            fn body_mut(&mut self) -> Option<&mut RigidBody> {
+                (**self).body_mut()
+            }
+
+            fn on_impact(&mut self, impact: &Impact, spawner: &mut Spawner) {
+                (**self).on_impact(impact, spawner)
+            }
+        }
+
+        impl<T: Exert + ?Sized> Exert for Box<T> {
+            fn force_on(&self, body: &RigidBody) -> Vec2 {
+                (**self).force_on(body)
+            }
+        }
+
+        impl<T: Spawn + ?Sized> Spawn for Box<T> {
+            fn on_spawn(&mut self, spawner: &mut Spawner) {
+                (**self).on_spawn(spawner)
+            }
+        }
+
+        impl<T: Save + ?Sized> Save for Box<T> {
+            fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+                (**self).save()
+            }
+        }
+
+        impl TickDrawExpire for Box<dyn TickDrawExpire> {}
+
+        /// Behaviour that runs across everything in the simulation rather than living in one
+        /// object type. A system sees every object's body, and every component attached to
+        /// objects and entities, so it can add to all of them at once by the components it
+        /// looks for, like a trail for anything with a `Transform`.
+        pub trait System {
+            /// Handle a step, given every object that has a body
+            fn on_step(
+                &mut self,
+                bodies: &mut [(ObjectHandle, &mut RigidBody)],
+                components: &mut Components,
+                resources: &mut Resources,
+                step_len: f64,
+            );
+
+            fn on_draw(&self, _components: &Components, _ctx: &mut DrawContext) {}
+
+            /// Forget anything kept for an object that's left the simulation
+            fn on_remove(&mut self, _handle: ObjectHandle) {}
         }
 
-        pub trait Draw {
-            fn on_draw(&self);
-        }
+        /// When in each step a system runs. Systems in the same phase run in the order they
+        /// were added.
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum Phase {
+            /// Before anything moves, e.g. to push bodies around from user input
+            PreTick,
+            /// Once bodies have moved and the objects have had their own tick, before
+            /// collisions and constraints are resolved
+            Tick,
+            /// Once the step is over and everything has settled, e.g. to gather statistics
+            PostTick,
+        }
+
+        /// Names one object for as long as it lives. Slots are reused once an object is
+        /// gone, so the generation tells a stale handle apart from whatever moved in after.
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+        pub struct ObjectHandle {
+            slot: u32,
+            generation: u32,
+        }
+
+        /// Whatever's in a slot: an object, an entity made only of components, or nothing
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum Occupant {
+            Vacant,
+            /// Sitting here in `objects`
+            Object(usize),
+            Entity,
+        }
+
+        #[derive(Clone)]
+        struct Slot {
+            generation: u32,
+            occupant: Occupant,
+        }
+
+        impl Slot {
+            /// Where its object sits in `objects`, if it has one
+            fn index(&self) -> Option<usize> {
+                match self.occupant {
+                    Occupant::Object(index) => Some(index),
+                    Occupant::Vacant | Occupant::Entity => None,
+                }
+            }
+        }
+
+        /// Two bodies touching during a step. Resting contacts keep reporting every step, with
+        /// an impulse of zero once nothing is closing any more.
+        #[derive(Clone, Copy, Debug)]
+        pub struct CollisionEvent {
+            pub a: ObjectHandle,
+            pub b: ObjectHandle,
+            pub point: Vec2,
+            /// Size of the impulse exchanged along the contact normal
+            pub impulse: Real,
+        }
+
+        pub type CollisionListener = Box<dyn FnMut(&CollisionEvent)>;
+
+        /// Something that happened in the simulation, queued up for the main loop
+        pub enum Event {
+            Spawned(ObjectHandle),
+            /// Expired, or evicted to make room for something else
+            Expired(ObjectHandle),
+            /// A contact that actually exchanged an impulse, unlike a body just resting
+            Collision(CollisionEvent),
+            /// Anything else worth passing on
+            Custom(Box<dyn Any>),
+        }
+
+        impl Event {
+            /// The payload of a custom event, if it's one of type `E`
+            pub fn custom<E: 'static>(&self) -> Option<&E> {
+                match self {
+                    Event::Custom(payload) => payload.downcast_ref(),
+                    _ => None,
+                }
+            }
+        }
+
+        /// Events kept for the main loop before the oldest start being dropped
+        const MAX_QUEUED_EVENTS: usize = 10_000;
+
+        /// The first body a ray runs into
+        #[derive(Clone, Copy, Debug)]
+        pub struct RayHit {
+            #[allow(dead_code)]
+            pub id: ObjectHandle,
+            pub point: Vec2,
+            pub normal: Vec2,
+            pub distance: Real,
+        }
+
+        /// How many ticks of energy readings to keep for diagnostics
+        const ENERGY_HISTORY_LEN: usize = 300;
+
+        /// Mechanical energy of every body at one moment. Potential energy only counts the
+        /// simulation's gravity, measured from the origin, so springs, fields and buoyancy
+        /// will show up as drift.
+        #[derive(Clone, Copy, Debug, Default)]
+        pub struct EnergyReading {
+            pub kinetic: Real,
+            pub potential: Real,
+        }
+
+        impl EnergyReading {
+            pub fn total(&self) -> Real {
+                self.kinetic + self.potential
+            }
+        }
+
+        /// Measures of the simulation as a whole at one moment
+        #[derive(Clone, Debug, Default)]
+        pub struct Stats {
+            pub kinetic_energy: Real,
+            /// Mean speed of every body
+            pub average_speed: Real,
+            /// Speed of the fastest body
+            pub top_speed: Real,
+            pub object_count: usize,
+            /// How many there are of each kind of object, most common first
+            pub counts_by_kind: Vec<(&'static str, usize)>,
+            /// Entities made only of components, which aren't objects of any kind
+            pub entity_count: usize,
+            /// Impacts per simulated second, over about the last second. Bodies resting on
+            /// each other don't count.
+            pub collisions_per_second: Real,
+        }
+
+        /// A small seeded random number generator (PCG), so a run can be replayed exactly
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub struct Rng {
+            state: u64,
+        }
+
+        impl Rng {
+            const MULTIPLIER: u64 = 6364136223846793005;
+            const INCREMENT: u64 = 1442695040888963407;
+
+            pub fn new(seed: u64) -> Self {
+                let mut rng = Self { state: 0 };
+                rng.next_u32();
+                rng.state = rng.state.wrapping_add(seed);
+                rng.next_u32();
+                rng
+            }
+
+            pub fn next_u32(&mut self) -> u32 {
+                let old = self.state;
+                self.state = old
+                    .wrapping_mul(Self::MULTIPLIER)
+                    .wrapping_add(Self::INCREMENT);
+                let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+                xorshifted.rotate_right((old >> 59) as u32)
+            }
+
+            /// A value from `low` up to but not including `high`
+            pub fn gen_range<T: RandomRange>(&mut self, low: T, high: T) -> T {
+                let fraction = self.next_u32() as f64 / (u32::MAX as f64 + 1.);
+                T::lerp(low, high, fraction)
+            }
+
+            pub fn rand_vec2(&mut self, xlow: Real, xhigh: Real, ylow: Real, yhigh: Real) -> Vec2 {
+                vec2(self.gen_range(xlow, xhigh), self.gen_range(ylow, yhigh))
+            }
+        }
+
+        pub trait RandomRange: Copy {
+            /// The value `fraction` of the way from `low` to `high`
+            fn lerp(low: Self, high: Self, fraction: f64) -> Self;
+        }
+
+        impl RandomRange for f32 {
+            fn lerp(low: Self, high: Self, fraction: f64) -> Self {
+                low + (high - low) * fraction as f32
+            }
+        }
+
+        impl RandomRange for f64 {
+            fn lerp(low: Self, high: Self, fraction: f64) -> Self {
+                low + (high - low) * fraction
+            }
+        }
+
+        macro_rules! integer_random_range {
+            ($($int:ty),*) => {
+                $(impl RandomRange for $int {
+                    fn lerp(low: Self, high: Self, fraction: f64) -> Self {
+                        (low as f64 + (high as f64 - low as f64) * fraction).floor() as Self
+                    }
+                })*
+            };
+        }
+
+        integer_random_range!(u8, u32, i32, usize);
+
+        /// Relaxation passes over the constraints each tick
+        const CONSTRAINT_ITERATIONS: usize = 8;
+
+        /// What to do when adding an object would take a simulation past its cap
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        #[allow(dead_code)]
+        pub enum Eviction {
+            /// Turn the new object away
+            Reject,
+            /// Make room by dropping whatever was added first
+            Oldest,
+            /// Make room by dropping whichever body is moving slowest
+            Slowest,
+        }
+
+        #[derive(Clone, Copy, Debug)]
+        pub struct ObjectCap {
+            pub max: usize,
+            pub eviction: Eviction,
+        }
+
+        /// Fewest items worth handing a thread of their own. Threads are started afresh each
+        /// time, tens of microseconds apiece, which is more than integrating a few hundred
+        /// bodies takes, so nothing short of a couple of thousand bodies gets split up.
+        /// `tests::threads_benchmark` times it.
+        const MIN_PARALLEL_CHUNK: usize = 1024;
+
+        /// Push a body back out of whatever scenery it's run into over the last `dt`, noting
+        /// every contact in `contacts`, and return where it was hit and how hard
+        fn hit_statics(
+            statics: &[StaticCollider],
+            static_bodies: &[RigidBody],
+            body: &mut RigidBody,
+            gravity: Vec2,
+            restitution_scale: Real,
+            dt: Real,
+            contacts: &mut Vec<Vec2>,
+        ) -> Vec<(Vec2, Real)> {
+            body.grounded = false;
+            // balloons and the like rest against ceilings instead of floors
+            let felt_gravity = gravity * body.gravity_scale;
+            let up = -felt_gravity.try_normalize().unwrap_or(Vec2::Y);
+            // decide up front, before any resolving moves the body
+            let blockers: Vec<_> = (0..statics.len())
+                .filter(|&k| statics[k].blocks(body, up))
+                .collect();
+            let mut hits = Vec::new();
+            if let Shape::Circle = body.shape {
+                let edges: Vec<_> = blockers
+                    .iter()
+                    .flat_map(|&k| {
+                        let surface = statics[k].surface_velocity();
+                        statics[k].edges().into_iter().map(move |e| (e, surface))
+                    })
+                    .collect();
+                hits = physics::sweep_against_edges(body, &edges, up, dt, restitution_scale);
+                contacts.extend(hits.iter().map(|&(point, _)| point));
+            }
+            for &k in &blockers {
+                if let Some(contact) = physics::collide(&static_bodies[k], body) {
+                    let surface = statics[k].surface_velocity();
+                    contacts.push(contact.point);
+                    let impulse = physics::resolve_static_contact(
+                        body,
+                        &contact,
+                        surface,
+                        up,
+                        dt,
+                        restitution_scale,
+                    );
+                    if impulse > 0. {
+                        hits.push((contact.point, impulse));
+                    }
+                }
+            }
+            hits
+        }
+
+        /// Run `f` on every item, split into runs across up to `threads` threads. Each item
+        /// is worked on alone, so the results don't depend on how they were split up.
+        fn for_each_parallel<I: Send>(items: &mut [I], threads: usize, f: impl Fn(&mut I) + Sync) {
+            let chunk = items.len().div_ceil(threads.max(1)).max(MIN_PARALLEL_CHUNK);
+            if chunk >= items.len() {
+                items.iter_mut().for_each(f);
+                return;
+            }
+            let f = &f;
+            std::thread::scope(|scope| {
+                for run in items.chunks_mut(chunk) {
+                    scope.spawn(move || run.iter_mut().for_each(f));
+                }
+            });
+        }
+
+        /// Smallest area a soft ring's gas is taken to fill, so a ring crushed flat doesn't
+        /// blow up, in square metres
+        const MIN_SOFT_RING_AREA: Real = 1e-3;
+
+        /// Ticks between the snapshots kept for rewinding
+        const SNAPSHOT_INTERVAL_TICKS: usize = 6;
+
+        /// A quarter of a second is more than any ordinary hitch, but short of what a
+        /// minimised window or a debugger break adds up to
+        const DEFAULT_MAX_CATCH_UP_SECONDS: f64 = 0.25;
+
+        /// Radius of the stand-in body `field_at` measures with, in metres
+        const FIELD_PROBE_RADIUS: Real = 0.01;
+
+        pub const MIN_TIME_SCALE: f64 = 0.1;
+        pub const MAX_TIME_SCALE: f64 = 10.;
+
+        /// Everything that changes as a simulation runs, as of one tick
+        struct Snapshot<T> {
+            tick_count: usize,
+            objects: Vec<T>,
+            handles: Vec<ObjectHandle>,
+            slots: Vec<Slot>,
+            constraints: Vec<DistanceConstraint>,
+            hinges: Vec<HingeJoint>,
+            soft_rings: Vec<SoftRing>,
+            components: Components,
+            rng: Rng,
+        }
+
+        /// Starts off every save, bumped whenever the format changes
+        const SAVE_VERSION: u32 = 4;
+
+        /// Everything in a save apart from the objects
+        #[derive(Serialize, Deserialize)]
+        struct SavedWorld {
+            tick: usize,
+            gravity: Vec2,
+            electrostatics: bool,
+            rng: Rng,
+            generation: u32,
+            slots: usize,
+            constraints: Vec<DistanceConstraint>,
+            hinges: Vec<HingeJoint>,
+            soft_rings: Vec<SoftRing>,
+        }
+
+        /// Runs a world of objects of type `T`. The default mixes any kinds of object behind
+        /// boxes; a scene made of just one kind can store them inline instead and skip the
+        /// allocation and dynamic dispatch per object.
+        pub struct Simulation<T = Box<dyn TickDrawExpire>> {
+            seconds_per_tick: f64,
+            objects: Vec<T>,
+            /// `handles[i]` names `objects[i]`
+            handles: Vec<ObjectHandle>,
+            slots: Vec<Slot>,
+            free_slots: Vec<u32>,
+            /// Handed to each slot as it's freed, so no two objects ever share a handle, even
+            /// across a rewind
+            next_generation: u32,
+            constraints: Vec<DistanceConstraint>,
+            hinges: Vec<HingeJoint>,
+            soft_rings: Vec<SoftRing>,
+            components: Components,
+            /// Whatever's being dragged about. It's let go of by hand, so it isn't saved or
+            /// rewound.
+            mouse_joint: Option<MouseJoint>,
+            /// Immovable scenery every body collides with
+            statics: Vec<StaticCollider>,
+            /// Pools of liquid that hold bodies up and slow them down
+            fluids: Vec<FluidRegion>,
+            tick_count: usize,
+            broad_phase: Box<dyn BroadPhase>,
+            /// Acceleration applied to every body, as a function of simulation time
+            wind: Option<fn(f64) -> Vec2>,
+            gravity: Vec2,
+            /// How quickly every body's speed bleeds away regardless of its surroundings, as
+            /// a rate per second, on top of drag
+            damping: Real,
+            /// Every impact's restitution is scaled by this, to make the whole world bouncier
+            /// or deader at once
+            restitution_scale: Real,
+            integrator: Box<dyn Integrator>,
+            /// Physics steps per tick. Objects see each one as a short tick, but nothing is
+            /// drawn in between.
+            substeps: usize,
+            /// Whether charged bodies push and pull on each other
+            electrostatics: bool,
+            collision_listeners: Vec<CollisionListener>,
+            /// Forces on every body besides gravity, wind and drag
+            force_fields: Vec<Box<dyn ForceField>>,
+            systems: Vec<(Phase, Box<dyn System>)>,
+            /// Speed limit for every body
+            max_speed: Option<Real>,
+            object_cap: Option<ObjectCap>,
+            /// Threads to spread integration over in big scenes
+            threads: usize,
+            /// Energy after each of the last few ticks, oldest first
+            energy_history: VecDeque<EnergyReading>,
+            /// Impacts during each tick of about the last second, oldest first
+            collision_history: VecDeque<usize>,
+            /// Where bodies touched each other or scenery during the last tick
+            contacts: Vec<Vec2>,
+            /// Simulated seconds per real second
+            time_scale: f64,
+            /// Real time as of the last call to `do_tick`
+            last_time: Option<f64>,
+            /// Simulated time that has fallen due but not been ticked through yet
+            accumulator: f64,
+            /// Most simulated time one `do_tick` will catch up on. Anything owed beyond
+            /// that is dropped, so a long stall slows the simulation down for a moment
+            /// rather than freezing it while it works through the backlog.
+            max_catch_up_seconds: f64,
+            /// Real time goes by without any falling due
+            paused: bool,
+            /// The most recent snapshots, oldest first
+            snapshots: VecDeque<Snapshot<T>>,
+            max_snapshots: usize,
+            /// Where anything random about the run should come from, so a seed replays it
+            rng: Rng,
+            /// Expired boxes waiting to be reused
+            pool: Vec<Box<dyn TickDrawExpire>>,
+            /// Events waiting for the main loop, oldest first
+            events: VecDeque<Event>,
+            /// How to read back each kind of saved object, by tag
+            loaders: Vec<(&'static str, Loader<T>)>,
+            inputs: Inputs,
+            /// Left alone by rewinding and loading, like the rest of the settings
+            resources: Resources,
+        }
+
+        impl Simulation {
+            /// The object behind `handle`, if it's still around and is a `U`
+            pub fn get_as<U: 'static>(&self, handle: ObjectHandle) -> Option<&U> {
+                (**self.get(handle)?).as_any().downcast_ref()
+            }
+
+            pub fn get_as_mut<U: 'static>(&mut self, handle: ObjectHandle) -> Option<&mut U> {
+                (**self.get_mut(handle)?).as_any_mut().downcast_mut()
+            }
+
+            /// Add an object in the allocation of an expired one of the same kind, if there
+            /// is one
+            pub fn add_pooled<U: TickDrawExpire + Clone + 'static>(
+                &mut self,
+                object: U,
+            ) -> ObjectHandle {
+                let boxed = recycle(&mut self.pool, object);
+                self.add_object(boxed)
+            }
+        }
+
+        impl<T: TickDrawExpire + Clone + 'static> Simulation<T> {
+            /// A simulation that holds only objects of type `T`
+            pub fn homogeneous(seconds_per_tick: f64, broad_phase: Box<dyn BroadPhase>) -> Self {
+                Self {
+                    seconds_per_tick,
+                    objects: Vec::new(),
+                    handles: Vec::new(),
+                    slots: Vec::new(),
+                    free_slots: Vec::new(),
+                    next_generation: 0,
+                    constraints: Vec::new(),
+                    hinges: Vec::new(),
+                    soft_rings: Vec::new(),
+                    components: Components::default(),
+                    mouse_joint: None,
+                    statics: Vec::new(),
+                    fluids: Vec::new(),
+                    tick_count: 0,
+                    broad_phase,
+                    wind: None,
+                    gravity: Vec2::ZERO,
+                    damping: 0.,
+                    restitution_scale: 1.,
+                    integrator: Box::new(SemiImplicitEuler),
+                    substeps: 1,
+                    electrostatics: false,
+                    collision_listeners: Vec::new(),
+                    force_fields: Vec::new(),
+                    systems: Vec::new(),
+                    max_speed: None,
+                    object_cap: None,
+                    threads: 1,
+                    energy_history: VecDeque::with_capacity(ENERGY_HISTORY_LEN),
+                    collision_history: VecDeque::new(),
+                    contacts: Vec::new(),
+                    time_scale: 1.,
+                    last_time: None,
+                    accumulator: 0.,
+                    max_catch_up_seconds: DEFAULT_MAX_CATCH_UP_SECONDS,
+                    paused: false,
+                    snapshots: VecDeque::new(),
+                    max_snapshots: 0,
+                    rng: Rng::new(0),
+                    pool: Vec::new(),
+                    events: VecDeque::new(),
+                    loaders: Vec::new(),
+                    inputs: Inputs::default(),
+                    resources: Resources::default(),
+                }
+            }
+
+            #[allow(dead_code)]
+            pub fn set_substeps(&mut self, substeps: usize) {
+                self.substeps = substeps.max(1);
+            }
+
+            pub fn set_electrostatics(&mut self, enabled: bool) {
+                self.electrostatics = enabled;
+            }
+
+            pub fn get_electrostatics(&self) -> bool {
+                self.electrostatics
+            }
+
+            pub fn set_gravity(&mut self, gravity: Vec2) {
+                self.gravity = gravity;
+            }
+
+            pub fn get_gravity(&self) -> Vec2 {
+                self.gravity
+            }
+
+            pub fn set_damping(&mut self, damping: Real) {
+                self.damping = damping.max(0.);
+            }
+
+            pub fn get_damping(&self) -> Real {
+                self.damping
+            }
+
+            pub fn set_restitution_scale(&mut self, scale: Real) {
+                self.restitution_scale = scale.max(0.);
+            }
+
+            pub fn get_restitution_scale(&self) -> Real {
+                self.restitution_scale
+            }
+
+            /// Change how long each tick lasts. The clock carries on from where it was and
+            /// rewinding still reaches as far back, but the snapshots taken so far are
+            /// dropped, being a different number of ticks apart.
+            pub fn set_tick_len(&mut self, seconds_per_tick: f64) {
+                let time = self.get_time();
+                let interval = SNAPSHOT_INTERVAL_TICKS as f64 * self.seconds_per_tick;
+                let rewind_seconds = self.max_snapshots as f64 * interval;
+                self.seconds_per_tick = seconds_per_tick;
+                self.tick_count = (time / seconds_per_tick).round() as usize;
+                self.snapshots.clear();
+                self.set_rewind_seconds(rewind_seconds);
+            }
+
+            pub fn get_tick_len(&self) -> f64 {
+                self.seconds_per_tick
+            }
+
+            /// Slow time down or speed it up. This changes how many ticks fall due and not
+            /// how long each one is, so the physics stays as stable as it was.
+            pub fn set_time_scale(&mut self, time_scale: f64) {
+                self.time_scale = time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE);
+            }
+
+            pub fn get_time_scale(&self) -> f64 {
+                self.time_scale
+            }
+
+            /// Stop time falling due, without losing track of the real time, so unpausing
+            /// carries on from where things stood
+            pub fn set_paused(&mut self, paused: bool) {
+                self.paused = paused;
+            }
+
+            pub fn is_paused(&self) -> bool {
+                self.paused
+            }
+
+            #[allow(dead_code)]
+            pub fn set_max_catch_up_seconds(&mut self, seconds: f64) {
+                self.max_catch_up_seconds = seconds;
+            }
+
+            /// Tell the objects what the player is up to, as of this frame
+            pub fn set_inputs(&mut self, inputs: Inputs) {
+                self.inputs = inputs;
+            }
+
+            #[allow(dead_code)]
+            pub fn get_inputs(&self) -> &Inputs {
+                &self.inputs
+            }
+
+            /// Share a value with every system and object, replacing any of the same type
+            pub fn insert_resource<R: Any>(&mut self, resource: R) -> Option<R> {
+                self.resources.insert(resource)
+            }
+
+            pub fn resource<R: Any>(&self) -> Option<&R> {
+                self.resources.get()
+            }
+
+            pub fn resource_mut<R: Any>(&mut self) -> Option<&mut R> {
+                self.resources.get_mut()
+            }
+
+            #[allow(dead_code)]
+            pub fn remove_resource<R: Any>(&mut self) -> Option<R> {
+                self.resources.remove()
+            }
+
+            /// Tell the player something, if there's a `Toasts` resource to show it on
+            pub fn notify(&self, text: &str) {
+                if let Some(toasts) = self.resource::<Toasts>() {
+                    toasts.push(text);
+                }
+            }
+
+            pub fn set_rng(&mut self, rng: Rng) {
+                self.rng = rng;
+            }
+
+            pub fn rng(&mut self) -> &mut Rng {
+                &mut self.rng
+            }
+
+            /// How far back `do_rewind` can go. Zero stops keeping snapshots.
+            pub fn set_rewind_seconds(&mut self, seconds: f64) {
+                let interval = SNAPSHOT_INTERVAL_TICKS as f64 * self.seconds_per_tick;
+                self.max_snapshots = (seconds / interval).ceil() as usize;
+                while self.snapshots.len() > self.max_snapshots {
+                    self.snapshots.pop_front();
+                }
+            }
+
+            /// Step back to the latest snapshot, returning false once there are none left.
+            /// `time` is the real time now, as passed to `do_tick`, so ticking picks up from
+            /// the snapshot without trying to catch up on the time spent rewinding.
+            pub fn do_rewind(&mut self, time: f64) -> bool {
+                self.last_time = Some(time);
+                let Some(snapshot) = self.snapshots.pop_back() else {
+                    return false;
+                };
+                let previous = self.live_handles();
+                self.handles = snapshot.handles;
+                self.objects = snapshot.objects;
+                self.constraints = snapshot.constraints;
+                self.hinges = snapshot.hinges;
+                self.soft_rings = snapshot.soft_rings;
+                self.components = snapshot.components;
+                self.rng = snapshot.rng;
+                self.tick_count = snapshot.tick_count;
+                self.accumulator = 0.;
+                // slots that were live keep their old handles; the rest may have been handed
+                // out since, so each gets a generation nobody has seen
+                self.free_slots.clear();
+                for k in 0..self.slots.len() {
+                    match snapshot.slots.get(k) {
+                        Some(slot) if slot.occupant != Occupant::Vacant => {
+                            self.slots[k] = slot.clone()
+                        }
+                        _ => {
+                            self.next_generation = self.next_generation.wrapping_add(1);
+                            self.slots[k] = Slot {
+                                generation: self.next_generation,
+                                occupant: Occupant::Vacant,
+                            };
+                            self.free_slots.push(k as u32);
+                        }
+                    }
+                }
+                for handle in previous {
+                    if !self.is_live(handle) {
+                        self.systems
+                            .iter_mut()
+                            .for_each(|(_, s)| s.on_remove(handle));
+                    }
+                }
+                true
+            }
+
+            /// Teach `load` to read back objects of type `U` saved under `tag`
+            pub fn register_loader<U: TickDrawExpire + Clone + DeserializeOwned + 'static>(
+                &mut self,
+                tag: &'static str,
+            ) {
+                let loader = move |input: &mut dyn erased_serde::Deserializer| {
+                    let object: U = erased_serde::deserialize(input)?;
+                    adopt(Box::new(object)).ok_or_else(|| {
+                        de::Error::custom(format!("a {tag} can't go in this simulation"))
+                    })
+                };
+                self.loaders.push((tag, Box::new(loader)));
+            }
+
+            /// Write out everything that changes as the simulation runs. Objects that don't
+            /// save themselves are left out, along with anything attached to them, and entities
+            /// and components aren't saved at all, any more than settings like the integrator
+            /// and force fields are.
+            pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+                let world = SavedWorld {
+                    tick: self.tick_count,
+                    gravity: self.gravity,
+                    electrostatics: self.electrostatics,
+                    rng: self.rng,
+                    generation: self.next_generation,
+                    slots: self.slots.len(),
+                    constraints: self.constraints.clone(),
+                    hinges: self.hinges.clone(),
+                    soft_rings: self.soft_rings.clone(),
+                };
+                let objects: Vec<_> = self
+                    .objects
+                    .iter()
+                    .zip(&self.handles)
+                    .filter_map(|(object, handle)| {
+                        let (tag, saved) = object.save()?;
+                        Some((*handle, tag, saved))
+                    })
+                    .collect();
+                std::fs::write(path, persist::write_save(SAVE_VERSION, &world, &objects)?)
+            }
+
+            /// Replace everything that changes as the simulation runs with what `save` wrote,
+            /// keeping the simulation's own settings. Every kind of object in the save needs a
+            /// loader registered. A save that can't be read leaves the simulation untouched.
+            pub fn load(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+                let text = std::fs::read_to_string(path)?;
+                let (world, saved): (SavedWorld, _) =
+                    persist::read_save(&text, SAVE_VERSION, &self.loaders)?;
+                let slot_count = world.slots;
+                let (handles, objects): (Vec<ObjectHandle>, Vec<T>) = saved.into_iter().unzip();
+                // saved objects keep their handles; every other slot gets a generation nobody
+                // has seen, as if its object had just gone
+                if handles.iter().any(|h| h.slot as usize >= slot_count) {
+                    return Err(invalid("object saved in a slot that doesn't exist"));
+                }
+                let mut slots: Vec<Option<Slot>> = vec![None; slot_count];
+                for (i, handle) in handles.iter().enumerate() {
+                    let slot = Slot {
+                        generation: handle.generation,
+                        occupant: Occupant::Object(i),
+                    };
+                    if slots[handle.slot as usize].replace(slot).is_some() {
+                        return Err(invalid("two objects saved in the same slot"));
+                    }
+                }
+                for handle in self.live_handles() {
+                    self.systems
+                        .iter_mut()
+                        .for_each(|(_, s)| s.on_remove(handle));
+                }
+                self.components = Components::default();
+                handles.iter().for_each(|&h| self.components.admit(h));
+                self.handles = handles;
+                self.free_slots.clear();
+                self.next_generation = world.generation;
+                self.slots = Vec::with_capacity(slots.len());
+                for (k, slot) in slots.into_iter().enumerate() {
+                    let slot = slot.unwrap_or_else(|| {
+                        self.next_generation = self.next_generation.wrapping_add(1);
+                        self.free_slots.push(k as u32);
+                        Slot {
+                            generation: self.next_generation,
+                            occupant: Occupant::Vacant,
+                        }
+                    });
+                    self.slots.push(slot);
+                }
+                self.objects = objects;
+                self.constraints = world.constraints;
+                self.hinges = world.hinges;
+                self.soft_rings = world.soft_rings;
+                self.tick_count = world.tick;
+                self.gravity = world.gravity;
+                self.electrostatics = world.electrostatics;
+                self.rng = world.rng;
+                self.accumulator = 0.;
+                self.snapshots.clear();
+                self.energy_history.clear();
+                self.do_reindex();
+                Ok(())
+            }
+
+            fn do_record_snapshot(&mut self) {
+                if self.max_snapshots == 0 {
+                    return;
+                }
+                if self.snapshots.len() == self.max_snapshots {
+                    self.snapshots.pop_front();
+                }
+                self.snapshots.push_back(Snapshot {
+                    tick_count: self.tick_count,
+                    objects: self.objects.clone(),
+                    handles: self.handles.clone(),
+                    slots: self.slots.clone(),
+                    constraints: self.constraints.clone(),
+                    hinges: self.hinges.clone(),
+                    soft_rings: self.soft_rings.clone(),
+                    components: self.components.clone(),
+                    rng: self.rng,
+                });
+            }
+
+            pub fn set_max_speed(&mut self, max_speed: Option<Real>) {
+                self.max_speed = max_speed;
+            }
+
+            /// Spread integration over up to this many threads once there are enough bodies to
+            /// make it worthwhile. Collisions and constraints are always resolved in order on
+            /// one thread.
+            pub fn set_threads(&mut self, threads: usize) {
+                self.threads = threads.max(1);
+            }
+
+            /// Limit how many objects there can be at once. Objects joined to others by a
+            /// constraint, hinge or soft ring are never evicted, and effects have a limit of
+            /// their own.
+            pub fn set_object_cap(&mut self, object_cap: Option<ObjectCap>) {
+                self.object_cap = object_cap;
+            }
+
+            #[allow(dead_code)]
+            pub fn add_force_field(&mut self, field: Box<dyn ForceField>) {
+                self.force_fields.push(field);
+            }
+
+            /// Run a system every step, at the given point in it
+            pub fn add_system(&mut self, phase: Phase, system: Box<dyn System>) {
+                self.systems.push((phase, system));
+            }
+
+            pub fn set_integrator(&mut self, integrator: Box<dyn Integrator>) {
+                self.integrator = integrator;
+            }
+
+            pub fn get_integrator_name(&self) -> &'static str {
+                self.integrator.name()
+            }
+
+            pub fn set_wind(&mut self, wind: fn(f64) -> Vec2) {
+                self.wind = Some(wind);
+            }
+
+            pub fn get_time(&self) -> f64 {
+                self.tick_count as f64 * self.seconds_per_tick
+            }
+
+            pub fn get_wind(&self) -> Vec2 {
+                self.wind_at(self.get_time())
+            }
+
+            fn wind_at(&self, time: f64) -> Vec2 {
+                self.wind.map_or(Vec2::ZERO, |wind| wind(time))
+            }
+
+            pub fn get_tick_count(&self) -> usize {
+                self.tick_count
+            }
+
+            #[allow(dead_code)]
+            pub fn get_object_count(&self) -> usize {
+                self.objects.len()
+            }
+
+            /// Run whatever ticks have fallen due by real time `time`. The first call only
+            /// starts the clock, and a clock that seems to go backwards counts as standing
+            /// still.
+            pub fn do_tick(&mut self, time: f64) {
+                let elapsed = self.last_time.map_or(0., |last| (time - last).max(0.));
+                self.last_time = Some(time);
+                if self.paused {
+                    return;
+                }
+                self.accumulator += elapsed * self.time_scale;
+                let due = (self.accumulator / self.seconds_per_tick).floor() as usize;
+                let budget = (self.max_catch_up_seconds / self.seconds_per_tick).ceil() as usize;
+                let ticks_to_perform = due.min(budget.max(1));
+                self.accumulator = if ticks_to_perform < due {
+                    0.
+                } else {
+                    self.accumulator - ticks_to_perform as f64 * self.seconds_per_tick
+                };
+                for tick in 0..ticks_to_perform {
+                    self.do_run_tick(self.tick_count + tick);
+                }
+                let previous_tick_count = self.tick_count;
+                self.tick_count += ticks_to_perform;
+                if self.tick_count / SNAPSHOT_INTERVAL_TICKS
+                    > previous_tick_count / SNAPSHOT_INTERVAL_TICKS
+                {
+                    self.do_record_snapshot();
+                }
+            }
+
+            /// How far real time has got into the tick after the last one run
+            pub fn tick_fraction(&self) -> f32 {
+                (self.accumulator / self.seconds_per_tick).clamp(0., 1.) as f32
+            }
+
+            /// Like `tick_fraction`, in seconds, for carrying things on from where the last
+            /// tick left them
+            pub fn lead_seconds(&self) -> f32 {
+                self.tick_fraction() * self.seconds_per_tick as f32
+            }
+
+            /// Run `ticks` whole ticks straight away, without waiting on the real time to fall
+            /// due, clearing out whatever expires after each. Nothing here needs a window, so
+            /// this is how tests and benchmarks drive a simulation.
+            #[allow(dead_code)]
+            pub fn run_for(&mut self, ticks: usize) {
+                for _ in 0..ticks {
+                    self.do_run_tick(self.tick_count);
+                    self.tick_count += 1;
+                    if self.tick_count.is_multiple_of(SNAPSHOT_INTERVAL_TICKS) {
+                        self.do_record_snapshot();
+                    }
+                    self.do_handle_expiry();
+                }
+                // so a later `do_tick` carries on from here rather than catching up
+                self.accumulator = 0.;
+            }
+
+            fn do_run_tick(&mut self, tick: usize) {
+                let tick_time = tick as f64 * self.seconds_per_tick;
+                let ticks_per_second = (1. / self.seconds_per_tick).round().max(1.) as usize;
+                while self.collision_history.len() >= ticks_per_second {
+                    self.collision_history.pop_front();
+                }
+                self.collision_history.push_back(0);
+                self.contacts.clear();
+                let step_len = self.seconds_per_tick / self.substeps as f64;
+                for step in 0..self.substeps {
+                    self.do_step(tick, tick_time + step as f64 * step_len, step_len);
+                }
+                self.do_record_energy();
+            }
+
+            fn do_record_energy(&mut self) {
+                if self.energy_history.len() == ENERGY_HISTORY_LEN {
+                    self.energy_history.pop_front();
+                }
+                self.energy_history.push_back(self.get_energy());
+            }
+
+            pub fn get_energy(&self) -> EnergyReading {
+                self.objects.iter().filter_map(|o| o.body()).fold(
+                    EnergyReading::default(),
+                    |acc, body| {
+                        let mass = body.mass();
+                        let inertia = mass * body.inertia_per_mass();
+                        // bodies that can't turn have no spin energy to count
+                        let spin = if inertia.is_finite() {
+                            0.5 * inertia * body.angular_velocity.powi(2)
+                        } else {
+                            0.
+                        };
+                        EnergyReading {
+                            kinetic: acc.kinetic
+                                + 0.5 * mass * body.velocity.length_squared()
+                                + spin,
+                            potential: acc.potential
+                                - mass * body.gravity_scale * self.gravity.dot(body.pos),
+                        }
+                    },
+                )
+            }
+
+            pub fn stats(&self) -> Stats {
+                let mut counts_by_kind: Vec<(&'static str, usize)> = Vec::new();
+                for object in &self.objects {
+                    // a box would only name itself, so look inside
+                    let kind = match (object as &dyn Any).downcast_ref::<Box<dyn TickDrawExpire>>()
+                    {
+                        Some(boxed) => (**boxed).kind(),
+                        None => object.kind(),
+                    };
+                    match counts_by_kind.iter_mut().find(|(k, _)| *k == kind) {
+                        Some((_, count)) => *count += 1,
+                        None => counts_by_kind.push((kind, 1)),
+                    }
+                }
+                counts_by_kind.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+                let (body_count, total_speed, top_speed): (usize, Real, Real) =
+                    self.bodies()
+                        .fold((0, 0., 0.), |(n, total, top), (_, body)| {
+                            let speed = body.velocity.length();
+                            (n + 1, total + speed, top.max(speed))
+                        });
+                let collisions: usize = self.collision_history.iter().sum();
+                let window = self.collision_history.len() as f64 * self.seconds_per_tick;
+                Stats {
+                    kinetic_energy: self.get_energy().kinetic,
+                    average_speed: if body_count == 0 {
+                        0.
+                    } else {
+                        total_speed / body_count as Real
+                    },
+                    top_speed,
+                    object_count: self.objects.len(),
+                    counts_by_kind,
+                    entity_count: self.get_entity_count(),
+                    collisions_per_second: if window > 0. {
+                        (collisions as f64 / window) as Real
+                    } else {
+                        0.
+                    },
+                }
+            }
+
+            pub fn get_energy_history(&self) -> &VecDeque<EnergyReading> {
+                &self.energy_history
+            }
+
+            /// One physics step, a whole tick long unless sub-stepping is on
+            fn do_step(&mut self, tick: usize, time: f64, step_len: f64) {
+                self.do_systems(Phase::PreTick, step_len);
+                self.do_integrate(time, step_len as Real);
+                self.do_static_collisions(step_len as Real);
+                self.do_move_entities(step_len);
+                let ctx = TickCtx {
+                    dt: step_len,
+                    time,
+                    tick,
+                    inputs: &self.inputs,
+                    resources: &self.resources,
+                };
+                self.objects.iter_mut().for_each(|o| o.on_tick(&ctx));
+                self.do_systems(Phase::Tick, step_len);
+                self.do_collisions();
+                self.do_solve_constraints();
+                self.do_limit_speeds();
+                self.do_spawn();
+                self.do_systems(Phase::PostTick, step_len);
+            }
+
+            fn do_spawn(&mut self) {
+                self.spawning(|objects, spawner| {
+                    objects.iter_mut().for_each(|o| o.on_spawn(spawner));
+                });
+            }
+
+            /// Tell each object about the hits its body took, as `(object index, impact)`
+            fn do_impacts(&mut self, impacts: Vec<(usize, Impact)>) {
+                if impacts.is_empty() {
+                    return;
+                }
+                self.spawning(|objects, spawner| {
+                    for (i, impact) in &impacts {
+                        objects[*i].on_impact(impact, spawner);
+                    }
+                });
+            }
+
+            /// Hand the objects a spawner, then add whatever they spawned once they're done,
+            /// so nothing moves under them while they're being called
+            fn spawning(&mut self, f: impl FnOnce(&mut [T], &mut Spawner)) {
+                let mut spawned = Vec::new();
+                let mut entities = Vec::new();
+                let mut events = Vec::new();
+                let mut spawner = Spawner {
+                    rng: &mut self.rng,
+                    pool: &mut self.pool,
+                    spawned: &mut spawned,
+                    entities: &mut entities,
+                    events: &mut events,
+                };
+                f(&mut self.objects, &mut spawner);
+                events.into_iter().for_each(|event| self.push_event(event));
+                for object in spawned.into_iter().filter_map(adopt) {
+                    self.add_object(object);
+                }
+                for entity in entities {
+                    self.spawn(entity);
+                }
+            }
+
+            fn do_systems(&mut self, phase: Phase, step_len: f64) {
+                if self.systems.iter().all(|(p, _)| *p != phase) {
+                    return;
+                }
+                let mut bodies: Vec<_> = self
+                    .handles
+                    .iter()
+                    .zip(&mut self.objects)
+                    .filter_map(|(&handle, o)| Some((handle, o.body_mut()?)))
+                    .collect();
+                for (_, system) in self.systems.iter_mut().filter(|(p, _)| *p == phase) {
+                    system.on_step(
+                        &mut bodies,
+                        &mut self.components,
+                        &mut self.resources,
+                        step_len,
+                    );
+                }
+            }
+
+            /// Rein in anything going faster than it's allowed to, so a bad collision or an
+            /// overstretched constraint can't fling a body across the screen next step
+            fn do_limit_speeds(&mut self) {
+                for body in self.objects.iter_mut().filter_map(|o| o.body_mut()) {
+                    let limits = [self.max_speed, body.max_speed].into_iter().flatten();
+                    let Some(limit) = limits.reduce(Real::min) else {
+                        continue;
+                    };
+                    body.velocity = body.velocity.clamp_length_max(limit);
+                }
+            }
+
+            /// Acceleration something small and still would feel at `point` from the wind, the
+            /// force fields and whatever the objects exert, for seeing why things curve. Gravity
+            /// is left out, being the same everywhere.
+            pub fn field_at(&self, point: Vec2) -> Vec2 {
+                let probe = RigidBody {
+                    pos: point,
+                    prev_pos: point,
+                    radius: FIELD_PROBE_RADIUS,
+                    mass: Some(1.),
+                    ..Default::default()
+                };
+                let state = BodyState::of(&probe);
+                let time = self.get_time();
+                let fields = self.force_fields.iter().map(|f| f.force(&state, time));
+                let pulls = self.objects.iter().map(|o| o.force_on(&probe));
+                fields
+                    .chain(pulls)
+                    .fold(self.wind_at(time), |acc, f| acc + f)
+            }
+
+            /// Move every body forward one tick under the force fields, buoyancy and whatever
+            /// the other objects are exerting on it
+            fn do_integrate(&mut self, time: f64, dt: Real) {
+                let gravity = Gravity(self.gravity);
+                let wind = self.wind.map(Wind);
+                // drag goes last, so what presses a body against the ground can leave it out
+                let fields: Vec<&dyn ForceField> = std::iter::once(&gravity as &dyn ForceField)
+                    .chain(wind.as_ref().map(|w| w as &dyn ForceField))
+                    .chain(self.force_fields.iter().map(|f| f.as_ref()))
+                    .chain(std::iter::once(&AirResistance as &dyn ForceField))
+                    .collect();
+                let pushes = &fields[..fields.len() - 1];
+                let (objects, electrostatics) = (&self.objects, self.electrostatics);
+                let mut pulls: Vec<_> = (0..objects.len()).map(|i| (i, Vec2::ZERO)).collect();
+                for_each_parallel(&mut pulls, self.threads, |(i, pull)| {
+                    let Some(body) = objects[*i].body() else {
+                        return;
+                    };
+                    *pull = objects.iter().enumerate().filter(|(j, _)| j != i).fold(
+                        Vec2::ZERO,
+                        |acc, (_, other)| {
+                            let coulomb = match other.body() {
+                                Some(source) if electrostatics => {
+                                    physics::coulomb_force(source, body)
+                                }
+                                _ => Vec2::ZERO,
+                            };
+                            acc + other.force_on(body) + coulomb
+                        },
+                    );
+                });
+                let pulls = self.add_soft_ring_forces(pulls.into_iter().map(|(_, p)| p).collect());
+                let pulls = self.add_mouse_joint_force(pulls);
+                let (fluids, gravity, integrator) = (&self.fluids, self.gravity, &self.integrator);
+                let damping = (-self.damping * dt).exp();
+                let mut bodies: Vec<_> = self
+                    .objects
+                    .iter_mut()
+                    .zip(pulls)
+                    .filter_map(|(o, pull)| Some((o.body_mut()?, pull)))
+                    .collect();
+                for_each_parallel(&mut bodies, self.threads, |(body, pull)| {
+                    let pull = *pull;
+                    // forces from other objects are held steady over the step, while fields get
+                    // re-evaluated at the integrator's intermediate states
+                    let mut surroundings = AIR_DENSITY;
+                    let mut buoyancy = Vec2::ZERO;
+                    for fluid in fluids {
+                        let submerged = fluid.submerged_fraction(body);
+                        surroundings += (fluid.density - AIR_DENSITY) * submerged;
+                        // Archimedes: the weight of the fluid pushed aside
+                        buoyancy -= gravity * fluid.density * submerged * body.area();
+                    }
+                    let mass = body.mass();
+                    let state = BodyState {
+                        medium_density: surroundings,
+                        ..BodyState::of(body)
+                    };
+                    let field_accel = |fields: &[&dyn ForceField], pos, velocity| {
+                        let state = BodyState {
+                            pos,
+                            velocity,
+                            ..state
+                        };
+                        let force = fields
+                            .iter()
+                            .fold(Vec2::ZERO, |acc, f| acc + f.force(&state, time));
+                        force / mass
+                    };
+                    let held = (pull + buoyancy) / mass;
+                    let applied = held + field_accel(pushes, body.pos, body.velocity);
+                    let accel = |pos, velocity| held + field_accel(&fields, pos, velocity);
+                    body.prev_pos = body.pos;
+                    body.applied_accel = applied;
+                    integrator.step(&mut body.pos, &mut body.velocity, dt, &accel);
+                    body.velocity *= damping;
+                    body.angle += body.angular_velocity * dt;
+                });
+            }
+
+            /// Keep every body out of the scenery. Circles are swept along this step's motion
+            /// first so fast ones can't skip through thin walls between steps.
+            /// Move every entity with a velocity along in a straight line, bounce the ones with
+            /// colliders off the scenery, and run their lifetimes down
+            fn do_move_entities(&mut self, step_len: f64) {
+                let dt = step_len as Real;
+                self.components.for_each_pair(
+                    |_, transform: &mut Transform, velocity: &mut Velocity| {
+                        transform.pos += velocity.linear * dt;
+                        transform.angle += velocity.angular * dt;
+                    },
+                );
+                let colliders: Vec<_> = self.components.iter::<Collider>().collect();
+                let mut bounced = Vec::new();
+                if !colliders.is_empty() {
+                    let static_bodies: Vec<_> = self.statics.iter().map(|s| s.body()).collect();
+                    for (handle, collider) in colliders {
+                        let transform = self.components.get::<Transform>(handle);
+                        let velocity = self.components.get::<Velocity>(handle);
+                        let (Some(transform), Some(velocity)) = (transform, velocity) else {
+                            continue;
+                        };
+                        // stands in for the entity, so it meets the scenery the way a ball would
+                        let mut body = RigidBody {
+                            pos: transform.pos,
+                            prev_pos: transform.pos - velocity.linear * dt,
+                            velocity: velocity.linear,
+                            radius: collider.radius,
+                            material: collider.material,
+                            angle: transform.angle,
+                            angular_velocity: velocity.angular,
+                            ..Default::default()
+                        };
+                        hit_statics(
+                            &self.statics,
+                            &static_bodies,
+                            &mut body,
+                            self.gravity,
+                            self.restitution_scale,
+                            dt,
+                            &mut Vec::new(),
+                        );
+                        bounced.push((handle, body));
+                    }
+                }
+                for (handle, body) in bounced {
+                    self.components.insert(
+                        handle,
+                        Transform {
+                            pos: body.pos,
+                            angle: body.angle,
+                        },
+                    );
+                    self.components.insert(
+                        handle,
+                        Velocity {
+                            linear: body.velocity,
+                            angular: body.angular_velocity,
+                        },
+                    );
+                }
+                for (_, lifetime) in self.components.iter_mut::<Lifetime>() {
+                    lifetime.elapsed += step_len;
+                }
+            }
+
+            fn do_static_collisions(&mut self, dt: Real) {
+                let static_bodies: Vec<_> = self.statics.iter().map(|s| s.body()).collect();
+                let mut impacts = Vec::new();
+                let bodies = self.objects.iter_mut().enumerate();
+                for (i, body) in bodies.filter_map(|(i, o)| Some((i, o.body_mut()?))) {
+                    let hits = hit_statics(
+                        &self.statics,
+                        &static_bodies,
+                        body,
+                        self.gravity,
+                        self.restitution_scale,
+                        dt,
+                        &mut self.contacts,
+                    );
+                    impacts.extend(hits.into_iter().map(|(point, impulse)| {
+                        let other = None;
+                        (
+                            i,
+                            Impact {
+                                other,
+                                point,
+                                impulse,
+                            },
+                        )
+                    }));
+                }
+                self.do_impacts(impacts);
+            }
+
+            pub fn add_static(&mut self, collider: StaticCollider) {
+                self.statics.push(collider);
+            }
+
+            /// Take out the scenery at `index`, moving everything after it down one
+            pub fn remove_static(&mut self, index: usize) -> Option<StaticCollider> {
+                (index < self.statics.len()).then(|| self.statics.remove(index))
+            }
+
+            /// Blast every body within `radius` of `center` outwards. The push is `strength`
+            /// per unit of cross-section at the centre and fades to nothing at the edge.
+            pub fn apply_radial_impulse(&mut self, center: Vec2, radius: Real, strength: Real) {
+                for i in self.query_shape(&physics::circle_probe(center, radius)) {
+                    let Some(body) = self.objects[i].body_mut() else {
+                        continue;
+                    };
+                    let offset = body.pos - center;
+                    let falloff = 1. - offset.length() / radius;
+                    if falloff <= 0. {
+                        continue;
+                    }
+                    let impulse =
+                        offset.normalize_or_zero() * strength * falloff * 2. * body.radius;
+                    body.apply_impulse_at(impulse, Vec2::ZERO);
+                }
+            }
+
+            pub fn add_fluid(&mut self, fluid: FluidRegion) {
+                self.fluids.push(fluid);
+            }
+
+            /// Ask the broad phase for nearby pairs, then resolve any that actually overlap
+            fn do_collisions(&mut self) {
+                let bounds = self.body_bounds();
+                let mut events = Vec::new();
+                let mut impacts = Vec::new();
+                for (i, j) in self.broad_phase.candidate_pairs(&bounds) {
+                    let (head, tail) = self.objects.split_at_mut(j);
+                    let (Some(a), Some(b)) = (head[i].body_mut(), tail[0].body_mut()) else {
+                        continue;
+                    };
+                    if let Some(contact) = physics::collide(a, b) {
+                        let impulse =
+                            physics::resolve_contact(a, b, &contact, self.restitution_scale);
+                        let (a, b) = (self.handles[i], self.handles[j]);
+                        let point = contact.point;
+                        self.contacts.push(point);
+                        events.push(CollisionEvent {
+                            a,
+                            b,
+                            point,
+                            impulse,
+                        });
+                        if impulse > 0. {
+                            impacts.push((
+                                i,
+                                Impact {
+                                    other: Some(b),
+                                    point,
+                                    impulse,
+                                },
+                            ));
+                            impacts.push((
+                                j,
+                                Impact {
+                                    other: Some(a),
+                                    point,
+                                    impulse,
+                                },
+                            ));
+                        }
+                    }
+                }
+                self.do_impacts(impacts);
+                for event in events {
+                    self.collision_listeners.iter_mut().for_each(|f| f(&event));
+                    if event.impulse > 0. {
+                        if let Some(count) = self.collision_history.back_mut() {
+                            *count += 1;
+                        }
+                        self.push_event(Event::Collision(event));
+                    }
+                }
+            }
+
+            /// Queue up an event for the main loop
+            pub fn push_event(&mut self, event: Event) {
+                if self.events.len() == MAX_QUEUED_EVENTS {
+                    self.events.pop_front();
+                }
+                self.events.push_back(event);
+            }
+
+            /// Take every event queued since the last call, oldest first
+            pub fn drain_events(&mut self) -> impl Iterator<Item = Event> + '_ {
+                self.events.drain(..)
+            }
+
+            /// Find the nearest body a ray from `origin` heading along `dir` hits within
+            /// `max_dist`
+            pub fn raycast(&self, origin: Vec2, dir: Vec2, max_dist: Real) -> Option<RayHit> {
+                let dir = dir.try_normalize()?;
+                self.objects
+                    .iter()
+                    .zip(&self.handles)
+                    .filter_map(|(o, id)| {
+                        let (distance, normal) = o.body()?.raycast(origin, dir)?;
+                        Some(RayHit {
+                            id: *id,
+                            point: origin + dir * distance,
+                            normal,
+                            distance,
+                        })
+                    })
+                    .filter(|hit| hit.distance <= max_dist)
+                    .min_by(|a, b| a.distance.total_cmp(&b.distance))
+            }
+
+            /// `(object index, bounds)` for every object with a body, as the broad phase wants
+            fn body_bounds(&self) -> Vec<(usize, Aabb)> {
+                self.objects
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, o)| o.body().map(|b| (i, b.aabb())))
+                    .collect()
+            }
+
+            /// The smallest box holding all the scenery and every body, wherever they've got to,
+            /// or `None` for an empty world
+            pub fn extent(&self) -> Option<Aabb> {
+                let statics = self.statics.iter().map(|s| s.body().aabb());
+                let bodies = self.body_bounds().into_iter().map(|(_, aabb)| aabb);
+                statics.chain(bodies).reduce(|a, b| a.union(&b))
+            }
+
+            /// Where bodies touched each other or scenery during the last tick, for debug views
+            pub fn contacts(&self) -> &[Vec2] {
+                &self.contacts
+            }
+
+            /// How the broad phase would carve up space for the bodies as they are now
+            pub fn broad_phase_cells(&self) -> Vec<Aabb> {
+                self.broad_phase.cells(&self.body_bounds())
+            }
+
+            pub fn statics(&self) -> &[StaticCollider] {
+                &self.statics
+            }
+
+            /// The scenery, for moving it around once the simulation is running
+            pub fn statics_mut(&mut self) -> &mut [StaticCollider] {
+                &mut self.statics
+            }
+
+            /// Like `SimulationBuilder::bounds`, for when the world changes size mid-run
+            pub fn set_bounds(&mut self, bounds: Aabb) {
+                self.broad_phase = Box::new(Quadtree::new(
+                    bounds,
+                    QUADTREE_MAX_DEPTH,
+                    QUADTREE_NODE_CAPACITY,
+                ));
+            }
+
+            /// Every body overlapping `region`
+            #[allow(dead_code)]
+            pub fn query_aabb(&self, region: Aabb) -> Vec<ObjectHandle> {
+                let probe = StaticCollider::Box(region).body();
+                self.query_shape(&probe)
+                    .into_iter()
+                    .map(|i| self.handles[i])
+                    .collect()
+            }
+
+            /// Every body overlapping the circle
+            #[allow(dead_code)]
+            pub fn query_circle(&self, center: Vec2, radius: Real) -> Vec<ObjectHandle> {
+                let probe = physics::circle_probe(center, radius);
+                self.query_shape(&probe)
+                    .into_iter()
+                    .map(|i| self.handles[i])
+                    .collect()
+            }
+
+            /// The newest object whose body covers `point`, which is the one drawn on top
+            pub fn object_at(&self, point: Vec2) -> Option<ObjectHandle> {
+                let probe = physics::circle_probe(point, 0.);
+                let newest = self.query_shape(&probe).into_iter().max()?;
+                Some(self.handles[newest])
+            }
+
+            /// Narrow the broad phase's guesses down to the indices of objects actually
+            /// touching `probe`
+            fn query_shape(&self, probe: &RigidBody) -> Vec<usize> {
+                let candidates = self.broad_phase.query(&self.body_bounds(), &probe.aabb());
+                candidates
+                    .into_iter()
+                    .filter(|&i| {
+                        self.objects[i]
+                            .body()
+                            .is_some_and(|body| physics::collide(probe, body).is_some())
+                    })
+                    .collect()
+            }
+
+            /// Call `callback` for every contact between two bodies from now on
+            #[allow(dead_code)]
+            pub fn on_collision(&mut self, callback: impl FnMut(&CollisionEvent) + 'static) {
+                self.collision_listeners.push(Box::new(callback));
+            }
+
+            /// Add each soft ring's spring and pressure forces onto its nodes' entries in
+            /// `pulls`
+            fn add_soft_ring_forces(&self, mut pulls: Vec<Vec2>) -> Vec<Vec2> {
+                for ring in &self.soft_rings {
+                    let indices: Option<Vec<_>> = ring
+                        .nodes
+                        .iter()
+                        .map(|id| {
+                            self.index_of(*id)
+                                .filter(|&i| self.objects[i].body().is_some())
+                        })
+                        .collect();
+                    let Some(indices) = indices else {
+                        continue;
+                    };
+                    let bodies: Vec<_> = indices
+                        .iter()
+                        .filter_map(|&i| self.objects[i].body())
+                        .collect();
+                    let points: Vec<_> = bodies.iter().map(|b| b.pos).collect();
+                    let area = physics::polygon_area(&points).abs().max(MIN_SOFT_RING_AREA);
+                    // points wind clockwise or anticlockwise; either way this faces outwards
+                    let outwards = -physics::polygon_area(&points).signum();
+                    let pressure = ring.gas / area;
+                    for k in 0..indices.len() {
+                        let next = (k + 1) % indices.len();
+                        let (a, b) = (bodies[k], bodies[next]);
+                        let axis = b.pos - a.pos;
+                        let len = axis.length();
+                        let dir = axis.normalize_or_zero();
+                        let stretch_speed = (b.velocity - a.velocity).dot(dir);
+                        let tension = ring.stiffness * (len - ring.rest_length)
+                            + ring.damping * stretch_speed;
+                        // the gas pushes on each edge, shared between the two ends
+                        let push = axis.perp() * outwards * pressure / 2.;
+                        pulls[indices[k]] += dir * tension + push;
+                        pulls[indices[next]] += -dir * tension + push;
+                    }
+                }
+                pulls
+            }
+
+            /// Add the mouse joint's spring onto its body's entry in `pulls`
+            fn add_mouse_joint_force(&self, mut pulls: Vec<Vec2>) -> Vec<Vec2> {
+                let Some(joint) = self.mouse_joint else {
+                    return pulls;
+                };
+                let Some(i) = self.index_of(joint.body) else {
+                    return pulls;
+                };
+                if let Some(body) = self.objects[i].body() {
+                    let stretch = joint.target - body.pos;
+                    pulls[i] +=
+                        (stretch * joint.stiffness - body.velocity * joint.damping) * body.mass();
+                }
+                pulls
+            }
+
+            fn index_of(&self, handle: ObjectHandle) -> Option<usize> {
+                self.slots
+                    .get(handle.slot as usize)
+                    .filter(|slot| slot.generation == handle.generation)
+                    .and_then(Slot::index)
+            }
+
+            /// Whether `handle` names an object or entity that's still here
+            fn is_live(&self, handle: ObjectHandle) -> bool {
+                self.slots.get(handle.slot as usize).is_some_and(|slot| {
+                    slot.generation == handle.generation && slot.occupant != Occupant::Vacant
+                })
+            }
+
+            /// Every object and entity there is, by slot
+            fn live_handles(&self) -> Vec<ObjectHandle> {
+                self.slots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, slot)| slot.occupant != Occupant::Vacant)
+                    .map(|(k, slot)| ObjectHandle {
+                        slot: k as u32,
+                        generation: slot.generation,
+                    })
+                    .collect()
+            }
+
+            /// Nudge constrained bodies back to their rest lengths, a few passes at a time so
+            /// chains of constraints converge together
+            fn do_solve_constraints(&mut self) {
+                for _ in 0..CONSTRAINT_ITERATIONS {
+                    for i in 0..self.constraints.len() {
+                        let DistanceConstraint { a, b, length } = self.constraints[i];
+                        let (Some(a), Some(b)) = (self.resolve_anchor(a), self.resolve_anchor(b))
+                        else {
+                            continue;
+                        };
+                        match (a, b) {
+                            (AnchorIndex::Point(pa), AnchorIndex::Object(j))
+                            | (AnchorIndex::Object(j), AnchorIndex::Point(pa)) => {
+                                if let Some(body) = self.objects[j].body_mut() {
+                                    physics::solve_distance(
+                                        ConstraintEnd::Fixed(pa),
+                                        ConstraintEnd::Body(body),
+                                        length,
+                                    );
+                                }
+                            }
+                            (AnchorIndex::Object(i), AnchorIndex::Object(j)) => {
+                                if let Some((a, b)) = self.bodies_mut(i, j) {
+                                    physics::solve_distance(
+                                        ConstraintEnd::Body(a),
+                                        ConstraintEnd::Body(b),
+                                        length,
+                                    );
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    for k in 0..self.hinges.len() {
+                        self.do_solve_hinge(self.hinges[k]);
+                    }
+                }
+            }
+
+            fn do_solve_hinge(&mut self, hinge: HingeJoint) {
+                let Some(i) = self.index_of(hinge.body) else {
+                    return;
+                };
+                match hinge.anchor {
+                    HingeAnchor::World(point) => {
+                        if let Some(body) = self.objects[i].body_mut() {
+                            physics::solve_hinge(
+                                ConstraintEnd::Fixed(point),
+                                Vec2::ZERO,
+                                ConstraintEnd::Body(body),
+                                hinge.pivot,
+                            );
+                        }
+                    }
+                    HingeAnchor::Body(id, other_pivot) => {
+                        let Some(j) = self.index_of(id) else {
+                            return;
+                        };
+                        if let Some((a, b)) = self.bodies_mut(j, i) {
+                            physics::solve_hinge(
+                                ConstraintEnd::Body(a),
+                                other_pivot,
+                                ConstraintEnd::Body(b),
+                                hinge.pivot,
+                            );
+                        }
+                    }
+                }
+            }
+
+            /// Borrow two different objects' bodies at once
+            fn bodies_mut(
+                &mut self,
+                i: usize,
+                j: usize,
+            ) -> Option<(&mut RigidBody, &mut RigidBody)> {
+                if i == j {
+                    return None;
+                }
+                let (head, tail) = self.objects.split_at_mut(i.max(j));
+                let (lo, hi) = (head[i.min(j)].body_mut()?, tail[0].body_mut()?);
+                Some(if i < j { (lo, hi) } else { (hi, lo) })
+            }
+
+            fn resolve_anchor(&self, anchor: ObjectAnchor) -> Option<AnchorIndex> {
+                match anchor {
+                    ObjectAnchor::Point(p) => Some(AnchorIndex::Point(p)),
+                    ObjectAnchor::Object(id) => self.index_of(id).map(AnchorIndex::Object),
+                }
+            }
+
+            pub fn add_constraint(&mut self, constraint: DistanceConstraint) {
+                self.constraints.push(constraint);
+            }
+
+            pub fn add_hinge(&mut self, hinge: HingeJoint) {
+                self.hinges.push(hinge);
+            }
+
+            pub fn add_soft_ring(&mut self, ring: SoftRing) {
+                self.soft_rings.push(ring);
+            }
+
+            /// Start dragging a body about, in place of whatever was being dragged, or let go
+            /// with `None`
+            pub fn set_mouse_joint(&mut self, joint: Option<MouseJoint>) {
+                self.mouse_joint = joint;
+            }
+
+            pub fn mouse_joint(&self) -> Option<&MouseJoint> {
+                self.mouse_joint.as_ref()
+            }
+
+            /// For moving the target along as the cursor moves
+            pub fn mouse_joint_mut(&mut self) -> Option<&mut MouseJoint> {
+                self.mouse_joint.as_mut()
+            }
+
+            /// Draw the world as seen through `camera`
+            pub fn do_draw(&self, renderer: &mut dyn Renderer, camera: Camera) {
+                let time = self.get_time();
+                let lead = self.lead_seconds();
+                let mut ctx = DrawContext::new(renderer, camera, self.tick_fraction(), lead)
+                    .with_resources(&self.resources);
+                let order = self.draw_order();
+                let layer = |from: DrawLayer, to: DrawLayer| {
+                    let objects = order.iter().map(|&i| &self.objects[i]);
+                    objects.filter(move |o| (from..=to).contains(&o.draw_layer()))
+                };
+                let background = layer(DrawLayer::Background, DrawLayer::Background);
+                background.for_each(|o| o.on_draw(&mut ctx));
+                self.draw_entities(DrawLayer::Background, &mut ctx, lead);
+                self.statics.iter().for_each(|s| s.on_draw(&mut ctx, time));
+                for c in &self.constraints {
+                    let ends = (self.drawn_pos(c.a, lead), self.drawn_pos(c.b, lead));
+                    if let (Some(a), Some(b)) = ends {
+                        let (a, b) = (to_mq(a), to_mq(b));
+                        ctx.draw_line(a.x, a.y, b.x, b.y, 0.025, mq::GRAY);
+                    }
+                }
+                for ring in &self.soft_rings {
+                    let points: Option<Vec<_>> = ring
+                        .nodes
+                        .iter()
+                        .map(|id| self.drawn_pos(ObjectAnchor::Object(*id), lead))
+                        .collect();
+                    let Some(points) = points else {
+                        continue;
+                    };
+                    let center = to_mq(physics::centroid(&points));
+                    let points: Vec<_> = points.into_iter().map(to_mq).collect();
+                    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+                        ctx.draw_triangle(center, *a, *b, ring.color);
+                    }
+                }
+                // under the objects, so whatever a system draws for one sits behind it
+                self.systems
+                    .iter()
+                    .for_each(|(_, s)| s.on_draw(&self.components, &mut ctx));
+                for on in [DrawLayer::Bodies, DrawLayer::Particles] {
+                    layer(on, on).for_each(|o| o.on_draw(&mut ctx));
+                    self.draw_entities(on, &mut ctx, lead);
+                }
+                for hinge in &self.hinges {
+                    let pin = self
+                        .index_of(hinge.body)
+                        .and_then(|i| self.objects[i].body())
+                        .map(|body| body.to_world(hinge.pivot) + body.velocity * lead as Real);
+                    if let Some(pin) = pin.map(to_mq) {
+                        ctx.draw_circle(pin.x, pin.y, 0.075, mq::GRAY);
+                    }
+                }
+                // on top, so whatever's underwater looks it
+                self.fluids.iter().for_each(|f| f.on_draw(&mut ctx));
+                let annotations = layer(DrawLayer::Annotations, DrawLayer::Annotations);
+                annotations.for_each(|o| o.on_draw(&mut ctx));
+                self.draw_entities(DrawLayer::Annotations, &mut ctx, lead);
+            }
+
+            /// Draw whatever's `Renderable` on `layer`, carried on `lead` seconds from the last
+            /// tick, in slot order
+            fn draw_entities(&self, layer: DrawLayer, ctx: &mut DrawContext, lead: f32) {
+                let renderables = self.components.iter::<Renderable>();
+                for (handle, renderable) in renderables.filter(|(_, r)| r.layer == layer) {
+                    let Some(transform) = self.components.get::<Transform>(handle) else {
+                        continue;
+                    };
+                    let velocity = self.components.get::<Velocity>(handle);
+                    let lead = velocity.map_or(Vec2::ZERO, |v| v.linear * lead as Real);
+                    let remaining = self.components.get::<Lifetime>(handle);
+                    renderable.draw(
+                        ctx,
+                        to_mq(transform.pos + lead),
+                        remaining.map(Lifetime::remaining),
+                    );
+                }
+            }
+
+            /// Draw what the objects give off light from, for a glow pass, as seen through
+            /// `camera`
+            pub fn do_draw_glow(&self, renderer: &mut dyn Renderer, camera: Camera) {
+                let (tick_fraction, lead) = (self.tick_fraction(), self.lead_seconds());
+                let mut ctx = DrawContext::new(renderer, camera, tick_fraction, lead)
+                    .with_resources(&self.resources);
+                for i in self.draw_order() {
+                    self.objects[i].on_draw_glow(&mut ctx);
+                }
+            }
+
+            /// Object indices from the bottom layer up, keeping the order they were added in
+            /// within each layer
+            fn draw_order(&self) -> Vec<usize> {
+                let mut order: Vec<_> = (0..self.objects.len()).collect();
+                order.sort_by_key(|&i| self.objects[i].draw_layer());
+                order
+            }
+
+            /// Where to draw an anchor, carried on `lead` seconds from the last tick
+            fn drawn_pos(&self, anchor: ObjectAnchor, lead: f32) -> Option<Vec2> {
+                match self.resolve_anchor(anchor)? {
+                    AnchorIndex::Point(p) => Some(p),
+                    AnchorIndex::Object(i) => self.objects[i]
+                        .body()
+                        .map(|b| b.pos + b.velocity * lead as Real),
+                }
+            }
+
+            pub fn do_handle_expiry(&mut self) {
+                let lapsed: Vec<_> = self
+                    .components
+                    .iter::<Lifetime>()
+                    .filter(|(_, lifetime)| lifetime.is_up())
+                    .map(|(handle, _)| handle)
+                    .collect();
+                for handle in lapsed {
+                    self.despawn(handle);
+                }
+                let mut expired = self.objects.iter().map(|o| o.is_expired());
+                let mut gone = Vec::new();
+                self.handles.retain(|&handle| {
+                    let keep = !expired.next().unwrap();
+                    if !keep {
+                        gone.push(handle);
+                    }
+                    keep
+                });
+                if gone.is_empty() {
+                    return;
+                }
+                // kept so the next one of the same kind can reuse its allocation
+                for object in self.objects.extract_if(.., |o| o.is_expired()) {
+                    if self.pool.len() < MAX_POOLED_OBJECTS {
+                        self.pool.extend(boxed(object));
+                    }
+                }
+                for handle in gone {
+                    self.free_slot(handle);
+                    self.push_event(Event::Expired(handle));
+                }
+                self.do_reindex();
+            }
+
+            /// Take out every object and entity along with everything joining them, leaving the
+            /// scenery, the settings and the clock as they were. Snapshots are kept, so
+            /// rewinding brings the objects back.
+            pub fn clear(&mut self) {
+                for handle in self.live_handles() {
+                    self.free_slot(handle);
+                }
+                self.handles.clear();
+                self.objects.clear();
+                self.constraints.clear();
+                self.hinges.clear();
+                self.soft_rings.clear();
+                self.mouse_joint = None;
+                self.do_reindex();
+            }
+
+            /// Take an object out of the simulation, along with anything attached to it
+            pub fn remove_object(&mut self, handle: ObjectHandle) -> Option<T> {
+                let index = self.index_of(handle)?;
+                self.handles.remove(index);
+                let removed = self.objects.remove(index);
+                self.free_slot(handle);
+                self.do_reindex();
+                Some(removed)
+            }
+
+            pub fn get(&self, handle: ObjectHandle) -> Option<&T> {
+                self.index_of(handle).map(|i| &self.objects[i])
+            }
+
+            pub fn get_mut(&mut self, handle: ObjectHandle) -> Option<&mut T> {
+                let index = self.index_of(handle)?;
+                Some(&mut self.objects[index])
+            }
+
+            #[allow(dead_code)]
+            pub fn get_body(&self, handle: ObjectHandle) -> Option<&RigidBody> {
+                self.get(handle)?.body()
+            }
+
+            /// Every object with its handle, oldest first
+            pub fn objects(&self) -> impl Iterator<Item = (ObjectHandle, &T)> {
+                self.handles.iter().copied().zip(&self.objects)
+            }
+
+            /// The body of every object that has one, with its handle, oldest first
+            pub fn bodies(&self) -> impl Iterator<Item = (ObjectHandle, &RigidBody)> {
+                self.objects()
+                    .filter_map(|(handle, object)| Some((handle, object.body()?)))
+            }
+
+            fn free_slot(&mut self, handle: ObjectHandle) {
+                self.next_generation = self.next_generation.wrapping_add(1);
+                let slot = &mut self.slots[handle.slot as usize];
+                slot.occupant = Occupant::Vacant;
+                slot.generation = self.next_generation;
+                self.free_slots.push(handle.slot);
+                self.components.dismiss(handle);
+                self.systems
+                    .iter_mut()
+                    .for_each(|(_, s)| s.on_remove(handle));
+            }
+
+            /// Point every slot back at its object after the list has shifted, and drop
+            /// anything left attached to an object that's gone
+            fn do_reindex(&mut self) {
+                for (i, handle) in self.handles.iter().enumerate() {
+                    self.slots[handle.slot as usize].occupant = Occupant::Object(i);
+                }
+                // constraints attached to something that's gone would just dangle
+                let live = |handle: ObjectHandle| {
+                    self.slots.get(handle.slot as usize).is_some_and(|slot| {
+                        slot.generation == handle.generation && slot.index().is_some()
+                    })
+                };
+                self.constraints.retain(|c| {
+                    [c.a, c.b].iter().all(|anchor| match *anchor {
+                        ObjectAnchor::Point(_) => true,
+                        ObjectAnchor::Object(handle) => live(handle),
+                    })
+                });
+                self.hinges.retain(|h| {
+                    let anchored = match h.anchor {
+                        HingeAnchor::World(_) => true,
+                        HingeAnchor::Body(handle, _) => live(handle),
+                    };
+                    anchored && live(h.body)
+                });
+                // a ring with a node missing has burst
+                self.soft_rings
+                    .retain(|r| r.nodes.iter().all(|&handle| live(handle)));
+            }
+
+            /// Add an object, making room for it if the cap calls for it. An object that's
+            /// turned away gets a handle that never finds anything.
+            pub fn add_object(&mut self, object: T) -> ObjectHandle {
+                if let Some(cap) = self.object_cap {
+                    let effects = self.objects.iter().filter(|o| o.is_effect()).count();
+                    if object.is_effect() {
+                        if effects >= MAX_EFFECTS {
+                            return self.turn_away(object);
+                        }
+                    } else {
+                        let mut counted = self.objects.len() - effects;
+                        if counted >= cap.max {
+                            self.notify("Object cap reached");
+                        }
+                        while counted >= cap.max {
+                            if !self.do_evict(cap.eviction) {
+                                return self.turn_away(object);
+                            }
+                            counted -= 1;
+                        }
+                    }
+                }
+                let handle = self.take_slot(Occupant::Object(self.objects.len()));
+                self.objects.push(object);
+                self.handles.push(handle);
+                self.push_event(Event::Spawned(handle));
+                handle
+            }
+
+            /// Put something in a free slot, or a new one if there are none, and name it
+            fn take_slot(&mut self, occupant: Occupant) -> ObjectHandle {
+                let slot = match self.free_slots.pop() {
+                    Some(slot) => slot,
+                    None => {
+                        self.slots.push(Slot {
+                            generation: 0,
+                            occupant: Occupant::Vacant,
+                        });
+                        (self.slots.len() - 1) as u32
+                    }
+                };
+                self.slots[slot as usize].occupant = occupant;
+                let handle = ObjectHandle {
+                    slot,
+                    generation: self.slots[slot as usize].generation,
+                };
+                self.components.admit(handle);
+                handle
+            }
+
+            /// Add an entity made of `entity`'s components. With an object cap, entities are
+            /// held to the same limit as effects, and one past it gets a handle that never
+            /// finds anything.
+            pub fn spawn(&mut self, entity: Entity) -> ObjectHandle {
+                if self.object_cap.is_some() && self.get_entity_count() >= MAX_EFFECTS {
+                    return ObjectHandle {
+                        slot: u32::MAX,
+                        generation: 0,
+                    };
+                }
+                let handle = self.take_slot(Occupant::Entity);
+                for component in entity.components {
+                    component.attach(&mut self.components, handle);
+                }
+                handle
+            }
+
+            /// Take an entity out along with its components, returning false if it had
+            /// already gone or is an object
+            pub fn despawn(&mut self, handle: ObjectHandle) -> bool {
+                let is_entity = self.slots.get(handle.slot as usize).is_some_and(|slot| {
+                    slot.generation == handle.generation && slot.occupant == Occupant::Entity
+                });
+                if is_entity {
+                    self.free_slot(handle);
+                }
+                is_entity
+            }
+
+            pub fn get_entity_count(&self) -> usize {
+                let entities = self.slots.iter().filter(|s| s.occupant == Occupant::Entity);
+                entities.count()
+            }
+
+            #[allow(dead_code)]
+            pub fn components(&self) -> &Components {
+                &self.components
+            }
+
+            /// For attaching components to objects and entities, and changing them
+            #[allow(dead_code)]
+            pub fn components_mut(&mut self) -> &mut Components {
+                &mut self.components
+            }
+
+            /// Keep an object that won't fit for reuse, and hand back a handle to nothing
+            fn turn_away(&mut self, object: T) -> ObjectHandle {
+                if self.pool.len() < MAX_POOLED_OBJECTS {
+                    self.pool.extend(boxed(object));
+                }
+                ObjectHandle {
+                    slot: u32::MAX,
+                    generation: 0,
+                }
+            }
+
+            /// Drop one object that counts towards the cap to make room for another,
+            /// returning false if nothing can go
+            fn do_evict(&mut self, eviction: Eviction) -> bool {
+                let mut candidates = self
+                    .handles
+                    .iter()
+                    .zip(&self.objects)
+                    .filter(|(_, o)| !o.is_effect())
+                    .filter(|(&handle, _)| !self.is_attached(handle));
+                let victim =
+                    match eviction {
+                        Eviction::Reject => None,
+                        Eviction::Oldest => candidates.next(),
+                        Eviction::Slowest => candidates.filter(|(_, o)| o.body().is_some()).min_by(
+                            |(_, a), (_, b)| {
+                                let speed =
+                                    |o: &T| o.body().map_or(0., |b| b.velocity.length_squared());
+                                speed(a).total_cmp(&speed(b))
+                            },
+                        ),
+                    }
+                    .map(|(&handle, _)| handle);
+                let Some(victim) = victim else {
+                    return false;
+                };
+                self.push_event(Event::Expired(victim));
+                if let Some(object) = self.remove_object(victim) {
+                    if self.pool.len() < MAX_POOLED_OBJECTS {
+                        self.pool.extend(boxed(object));
+                    }
+                }
+                true
+            }
+
+            /// Whether something else depends on this object staying put
+            fn is_attached(&self, handle: ObjectHandle) -> bool {
+                let anchored = |anchor: &ObjectAnchor| matches!(*anchor, ObjectAnchor::Object(o) if o == handle);
+                self.constraints
+                    .iter()
+                    .any(|c| anchored(&c.a) || anchored(&c.b))
+                    || self.hinges.iter().any(|h| {
+                        h.body == handle
+                            || matches!(h.anchor, HingeAnchor::Body(body, _) if body == handle)
+                    })
+                    || self.soft_rings.iter().any(|r| r.nodes.contains(&handle))
+            }
+        }
+
+        /// A constraint anchor looked up for the current tick
+        enum AnchorIndex {
+            Point(Vec2),
+            Object(usize),
+        }
+        /// A feature packaged up to be added to a simulation in one go, like a system along
+        /// with the settings and loaders it relies on
+        pub trait Plugin<T = Box<dyn TickDrawExpire>> {
+            fn build(&self, sim: &mut SimulationBuilder<T>);
+        }
+
+        /// How long each tick lasts unless a builder says otherwise
+        pub const DEFAULT_TICK_LEN_SECONDS: f64 = 1. / 120.;
+        /// How finely `SimulationBuilder::bounds` divides the world up to find collisions
+        const QUADTREE_MAX_DEPTH: usize = 6;
+        const QUADTREE_NODE_CAPACITY: usize = 8;
+
+        /// Puts a simulation together before it starts, from settings and plugins. Every
+        /// setting has a default, so only the ones that matter need mentioning.
+        pub struct SimulationBuilder<T = Box<dyn TickDrawExpire>> {
+            simulation: Simulation<T>,
+            /// Held back until `build`, since how many snapshots it takes depends on the
+            /// tick length
+            rewind_seconds: f64,
+        }
+
+        impl Simulation {
+            pub fn builder() -> SimulationBuilder {
+                SimulationBuilder::new()
+            }
+        }
+
+        impl SimulationBuilder {
+            pub fn new() -> Self {
+                Self::homogeneous()
+            }
+        }
+
+        impl Default for SimulationBuilder {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        // every option a simulation has, whether or not the demo below sets it
+        #[allow(dead_code)]
+        impl<T: TickDrawExpire + Clone + 'static> SimulationBuilder<T> {
+            /// A builder for a simulation that holds only objects of type `T`
+            pub fn homogeneous() -> Self {
+                Self {
+                    simulation: Simulation::homogeneous(
+                        DEFAULT_TICK_LEN_SECONDS,
+                        Box::new(AllPairs),
+                    ),
+                    rewind_seconds: 0.,
+                }
+            }
+
+            pub fn tick_len(&mut self, seconds_per_tick: f64) -> &mut Self {
+                self.simulation.seconds_per_tick = seconds_per_tick;
+                self
+            }
+
+            pub fn gravity(&mut self, gravity: Vec2) -> &mut Self {
+                self.simulation.set_gravity(gravity);
+                self
+            }
+
+            pub fn damping(&mut self, damping: Real) -> &mut Self {
+                self.simulation.set_damping(damping);
+                self
+            }
+
+            pub fn restitution_scale(&mut self, scale: Real) -> &mut Self {
+                self.simulation.set_restitution_scale(scale);
+                self
+            }
+
+            /// The region the world takes up, which collisions are then looked for by
+            /// dividing up. Bodies outside it still collide, just less efficiently.
+            pub fn bounds(&mut self, bounds: Aabb) -> &mut Self {
+                self.broad_phase(Box::new(Quadtree::new(
+                    bounds,
+                    QUADTREE_MAX_DEPTH,
+                    QUADTREE_NODE_CAPACITY,
+                )))
+            }
+
+            pub fn broad_phase(&mut self, broad_phase: Box<dyn BroadPhase>) -> &mut Self {
+                self.simulation.broad_phase = broad_phase;
+                self
+            }
+
+            /// Where anything random about the run starts from
+            pub fn seed(&mut self, seed: u64) -> &mut Self {
+                self.simulation.set_rng(Rng::new(seed));
+                self
+            }
+
+            pub fn integrator(&mut self, integrator: Box<dyn Integrator>) -> &mut Self {
+                self.simulation.set_integrator(integrator);
+                self
+            }
+
+            pub fn substeps(&mut self, substeps: usize) -> &mut Self {
+                self.simulation.set_substeps(substeps);
+                self
+            }
+
+            pub fn wind(&mut self, wind: fn(f64) -> Vec2) -> &mut Self {
+                self.simulation.set_wind(wind);
+                self
+            }
+
+            pub fn max_speed(&mut self, max_speed: Real) -> &mut Self {
+                self.simulation.set_max_speed(Some(max_speed));
+                self
+            }
+
+            pub fn object_cap(&mut self, object_cap: ObjectCap) -> &mut Self {
+                self.simulation.set_object_cap(Some(object_cap));
+                self
+            }
+
+            pub fn threads(&mut self, threads: usize) -> &mut Self {
+                self.simulation.set_threads(threads);
+                self
+            }
+
+            pub fn max_catch_up_seconds(&mut self, seconds: f64) -> &mut Self {
+                self.simulation.set_max_catch_up_seconds(seconds);
+                self
+            }
+
+            pub fn rewind_seconds(&mut self, seconds: f64) -> &mut Self {
+                self.rewind_seconds = seconds;
+                self
+            }
+
+            pub fn add_plugin(&mut self, plugin: &dyn Plugin<T>) -> &mut Self {
+                plugin.build(self);
+                self
+            }
+
+            pub fn add_system(&mut self, phase: Phase, system: Box<dyn System>) -> &mut Self {
+                self.simulation.add_system(phase, system);
+                self
+            }
+
+            pub fn insert_resource<R: Any>(&mut self, resource: R) -> &mut Self {
+                self.simulation.insert_resource(resource);
+                self
+            }
+
+            pub fn add_force_field(&mut self, field: Box<dyn ForceField>) -> &mut Self {
+                self.simulation.add_force_field(field);
+                self
+            }
+
+            pub fn add_static(&mut self, collider: StaticCollider) -> &mut Self {
+                self.simulation.add_static(collider);
+                self
+            }
+
+            pub fn on_collision(
+                &mut self,
+                callback: impl FnMut(&CollisionEvent) + 'static,
+            ) -> &mut Self {
+                self.simulation.on_collision(callback);
+                self
+            }
+
+            pub fn register_loader<U: TickDrawExpire + Clone + DeserializeOwned + 'static>(
+                &mut self,
+                tag: &'static str,
+            ) -> &mut Self {
+                self.simulation.register_loader::<U>(tag);
+                self
+            }
+
+            /// Anything else about the simulation that a plugin needs to set up
+            pub fn simulation_mut(&mut self) -> &mut Simulation<T> {
+                &mut self.simulation
+            }
+
+            /// Hand over the finished simulation, leaving the builder as good as new
+            pub fn build(&mut self) -> Simulation<T> {
+                let mut simulation =
+                    std::mem::replace(&mut self.simulation, Self::homogeneous().simulation);
+                simulation.set_rewind_seconds(self.rewind_seconds);
+                simulation
+            }
+        }
+    }
+
+    pub mod components {
+        use macroquad::prelude as mq;
+
+        use super::physics::Material;
+        use super::real::{Real, Vec2};
+        use super::render::{DrawContext, Renderer};
+        use super::simulator::DrawLayer;
+
+        /// Where an entity is and which way it's facing
+        #[derive(Clone, Copy, PartialEq)]
+        pub struct Transform {
+            pub pos: Vec2,
+            /// In radians, clockwise on screen
+            pub angle: Real,
+        }
+
+        impl Transform {
+            pub fn at(pos: Vec2) -> Self {
+                Self { pos, angle: 0. }
+            }
+        }
+
+        /// How fast an entity's transform changes. The simulation moves anything with both in
+        /// a straight line every step, as nothing pushes entities about.
+        #[derive(Clone, Copy, PartialEq)]
+        pub struct Velocity {
+            pub linear: Vec2,
+            /// In radians per second
+            pub angular: Real,
+        }
+
+        impl Velocity {
+            pub fn linear(linear: Vec2) -> Self {
+                Self {
+                    linear,
+                    angular: 0.,
+                }
+            }
+        }
+
+        /// Makes a moving entity bounce off the scenery like a circle of `radius`. It still
+        /// passes through objects and other entities.
+        #[derive(Clone, Copy)]
+        pub struct Collider {
+            pub radius: Real,
+            pub material: Material,
+        }
+
+        /// How long an entity lasts. The simulation runs it down every step and takes the
+        /// entity out once it's up.
+        #[derive(Clone, Copy, PartialEq)]
+        pub struct Lifetime {
+            pub seconds: f64,
+            pub elapsed: f64,
+        }
+
+        impl Lifetime {
+            pub fn new(seconds: f64) -> Self {
+                Self {
+                    seconds,
+                    elapsed: 0.,
+                }
+            }
+
+            pub fn is_up(&self) -> bool {
+                self.elapsed >= self.seconds
+            }
+
+            /// How much is left, from 1 when new down to 0 once it's up
+            pub fn remaining(&self) -> f32 {
+                (1. - self.elapsed / self.seconds).clamp(0., 1.) as f32
+            }
+        }
+
+        /// What shape an entity is drawn as
+        #[derive(Clone, Copy)]
+        pub enum Look {
+            Disc { radius: f32 },
+            Ring { radius: f32, thickness: f32 },
+        }
+
+        /// How an entity's look changes as its lifetime runs down
+        #[derive(Clone, Copy)]
+        pub enum OverLifetime {
+            #[allow(dead_code)]
+            Stays,
+            FadesOut,
+            /// Fades out while shrinking away to nothing
+            Shrinks,
+            /// Fades out while growing from nothing, like a shock wave
+            Grows,
+        }
+
+        /// Draws an entity where its transform puts it
+        #[derive(Clone, Copy)]
+        pub struct Renderable {
+            pub look: Look,
+            pub color: mq::Color,
+            pub layer: DrawLayer,
+            pub over_lifetime: OverLifetime,
+        }
+
+        impl Renderable {
+            /// Draw at `pos`, with `remaining` of its lifetime left if it has one
+            pub fn draw(&self, ctx: &mut DrawContext, pos: mq::Vec2, remaining: Option<f32>) {
+                let (fade, scale) = match (self.over_lifetime, remaining) {
+                    (OverLifetime::Stays, _) | (_, None) => (1., 1.),
+                    (OverLifetime::FadesOut, Some(left)) => (left, 1.),
+                    (OverLifetime::Shrinks, Some(left)) => (left, left),
+                    (OverLifetime::Grows, Some(left)) => (left, 1. - left),
+                };
+                let color = mq::Color {
+                    a: self.color.a * fade,
+                    ..self.color
+                };
+                match self.look {
+                    Look::Disc { radius } => ctx.draw_circle(pos.x, pos.y, radius * scale, color),
+                    Look::Ring { radius, thickness } => {
+                        ctx.draw_circle_lines(pos.x, pos.y, radius * scale, thickness, color)
+                    }
+                }
+            }
+        }
+    }
+
+    pub mod physics {
+        use macroquad::prelude as mq;
+        use serde::{Deserialize, Serialize};
+
+        use super::real::{consts, to_mq, vec2, Mat2, Real, Vec2};
+        use super::render::Renderer;
+
+        pub const EARTH_ACCELERATION_M_PER_S: f64 = 9.8;
+
+        /// Physical properties of whatever a body is made of
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub struct Material {
+            /// Fraction of the closing speed kept after a bounce
+            pub restitution: Real,
+            /// Coulomb friction coefficient against surfaces
+            pub friction: Real,
+            /// Deceleration while rolling, as a fraction of the force pressing into the surface
+            pub rolling_resistance: Real,
+            /// Mass per square metre
+            pub density: Real,
+        }
+
+        impl Material {
+            pub const RUBBER: Material = Material {
+                restitution: 0.8,
+                friction: 0.7,
+                rolling_resistance: 0.02,
+                density: 1.1,
+            };
+            pub const STEEL: Material = Material {
+                restitution: 0.6,
+                friction: 0.15,
+                rolling_resistance: 0.005,
+                density: 7.8,
+            };
+            pub const CLAY: Material = Material {
+                restitution: 0.15,
+                friction: 0.5,
+                rolling_resistance: 0.1,
+                density: 1.8,
+            };
+        }
+
+        /// Contacts whose normal is at least this close to straight up count as ground
+        const GROUND_SLOPE: Real = 0.5;
+        /// How many times a swept circle can bounce within one step before giving up
+        const MAX_SWEEPS_PER_STEP: usize = 4;
+        /// Segment colliders are treated as capsules this thick either side, so overlap tests
+        /// have some depth to work with
+        const SEGMENT_THICKNESS: Real = 0.0125;
+        /// Impacts slower than this don't bounce at all, so bodies settle instead of buzzing
+        /// in ever tinier hops on the floor
+        const BOUNCE_CUTOFF_SPEED: Real = 0.5;
+        /// Distance between the stripes painted on conveyor belts
+        const CONVEYOR_STRIPE_GAP: Real = 0.3;
+
+        /// Density of the air bodies fly through, in simulation units
+        pub const AIR_DENSITY: Real = 0.05;
+        /// Dense enough to float rubber but sink steel and clay
+        pub const WATER_DENSITY: Real = 1.5;
+        /// Scales the force between two charges, in newton square metres per square coulomb
+        pub const COULOMB_CONSTANT: Real = 8.99e9;
+
+        /// How air resistance scales with speed
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub enum Drag {
+            None,
+            /// Force proportional to speed, for slow or viscous motion. The coefficient is for
+            /// air and scales up in denser fluids.
+            Linear(Real),
+            /// Force proportional to speed squared, using the given drag coefficient
+            Quadratic(Real),
+        }
+
+        #[derive(Clone, Serialize, Deserialize)]
+        pub enum Shape {
+            /// A circle of the body's radius
+            Circle,
+            /// A convex polygon, with vertices relative to the body's centre at zero rotation.
+            /// The body's radius should enclose every vertex.
+            Polygon(Vec<Vec2>),
+            /// An axis-aligned box that never rotates
+            Rect { half_extents: Vec2 },
+            /// Every point within `radius` of a segment running `half_length` either side of the
+            /// centre, along the body's local x axis
+            Capsule { half_length: Real, radius: Real },
+        }
+
+        impl Shape {
+            /// A regular polygon with its vertices `radius` from the centre, sitting on a flat
+            /// bottom edge
+            pub fn regular_polygon(sides: usize, radius: Real) -> Shape {
+                use consts::{FRAC_PI_2, PI, TAU};
+                Shape::Polygon(
+                    (0..sides)
+                        .map(|i| {
+                            let theta = TAU * i as Real / sides as Real;
+                            Vec2::from_angle(theta + FRAC_PI_2 + PI / sides as Real) * radius
+                        })
+                        .collect(),
+                )
+            }
+        }
+
+        #[derive(Clone, Serialize, Deserialize)]
+        pub struct RigidBody {
+            pub pos: Vec2,
+            /// Where the body was before the simulation last integrated it
+            pub prev_pos: Vec2,
+            /// Acceleration from gravity and other forces during the last step, not counting drag
+            pub applied_accel: Vec2,
+            /// Whether the body rested on scenery facing against its gravity during the last step
+            pub grounded: bool,
+            pub velocity: Vec2,
+            /// Radius of a circle, or of the circle enclosing any other shape
+            pub radius: Real,
+            pub shape: Shape,
+            pub material: Material,
+            /// Explicit mass, for when the material's density and the shape's area won't do
+            pub mass: Option<Real>,
+            pub drag: Drag,
+            /// Electric charge, which only matters while the simulation has electrostatics on
+            pub charge: Real,
+            /// How strongly gravity pulls on this body; negative values make it fall upwards
+            pub gravity_scale: Real,
+            /// Fastest this body may go, on top of any limit the simulation sets
+            pub max_speed: Option<Real>,
+            /// Orientation in radians, clockwise on screen
+            pub angle: Real,
+            pub angular_velocity: Real,
+        }
+
+        impl Default for RigidBody {
+            fn default() -> Self {
+                Self {
+                    pos: Vec2::ZERO,
+                    prev_pos: Vec2::ZERO,
+                    applied_accel: Vec2::ZERO,
+                    grounded: false,
+                    velocity: Vec2::ZERO,
+                    radius: 1.,
+                    shape: Shape::Circle,
+                    material: Material::RUBBER,
+                    mass: None,
+                    drag: Drag::None,
+                    charge: 0.,
+                    gravity_scale: 1.,
+                    max_speed: None,
+                    angle: 0.,
+                    angular_velocity: 0.,
+                }
+            }
+        }
+
+        impl RigidBody {
+            /// Moment of inertia divided by mass, assuming uniform density
+            pub fn inertia_per_mass(&self) -> Real {
+                match &self.shape {
+                    Shape::Circle => 0.5 * self.radius * self.radius,
+                    Shape::Polygon(vertices) => {
+                        // sum over the triangles fanning out from the centre
+                        let (mut numerator, mut denominator) = (0., 0.);
+                        for (a, b) in edges(vertices) {
+                            let cross = a.perp_dot(b).abs();
+                            numerator += cross * (a.dot(a) + a.dot(b) + b.dot(b));
+                            denominator += cross;
+                        }
+                        numerator / (6. * denominator)
+                    }
+                    // infinitely hard to spin, so impulses never rotate it
+                    Shape::Rect { .. } => Real::INFINITY,
+                    Shape::Capsule {
+                        half_length,
+                        radius,
+                    } => {
+                        use consts::PI;
+                        let (h, r) = (*half_length, *radius);
+                        let rect_area = 4. * h * r;
+                        let rect_inertia = rect_area * (h * h + r * r) / 3.;
+                        // the two end caps together make one disc, shifted out to the ends
+                        let caps_area = PI * r * r;
+                        let caps_inertia =
+                            caps_area * (r * r / 2. + h * h + 8. * h * r / (3. * PI));
+                        (rect_inertia + caps_inertia) / (rect_area + caps_area)
+                    }
+                }
+            }
+
+            /// Ends of a capsule's core segment in world space, or the centre twice for anything
+            /// else
+            pub fn capsule_segment(&self) -> (Vec2, Vec2) {
+                match self.shape {
+                    Shape::Capsule { half_length, .. } => {
+                        let axis = Vec2::from_angle(self.angle) * half_length;
+                        (self.pos - axis, self.pos + axis)
+                    }
+                    _ => (self.pos, self.pos),
+                }
+            }
+
+            /// The point on the body furthest along `dir`, which should be a unit vector
+            pub fn support(&self, dir: Vec2) -> Vec2 {
+                match self.shape {
+                    Shape::Circle => self.pos + dir * self.radius,
+                    Shape::Capsule { radius, .. } => {
+                        let (a, b) = self.capsule_segment();
+                        let end = if a.dot(dir) > b.dot(dir) { a } else { b };
+                        end + dir * radius
+                    }
+                    Shape::Polygon(_) | Shape::Rect { .. } => self
+                        .world_vertices()
+                        .into_iter()
+                        .max_by(|p, q| p.dot(dir).total_cmp(&q.dot(dir)))
+                        .unwrap_or(self.pos),
+                }
+            }
+
+            /// Polygon vertices in world space, or nothing for round shapes
+            pub fn world_vertices(&self) -> Vec<Vec2> {
+                match &self.shape {
+                    Shape::Circle | Shape::Capsule { .. } => Vec::new(),
+                    Shape::Polygon(vertices) => {
+                        let rotation = Vec2::from_angle(self.angle);
+                        vertices
+                            .iter()
+                            .map(|v| self.pos + rotation.rotate(*v))
+                            .collect()
+                    }
+                    Shape::Rect { .. } => {
+                        let Aabb { min, max } = self.aabb();
+                        vec![min, vec2(max.x, min.y), max, vec2(min.x, max.y)]
+                    }
+                }
+            }
+
+            /// Where a point given in the body's own unrotated frame currently is
+            pub fn to_world(&self, local: Vec2) -> Vec2 {
+                self.pos + Vec2::from_angle(self.angle).rotate(local)
+            }
+
+            /// Velocity of a point on the body, `offset` away from its centre
+            pub fn point_velocity(&self, offset: Vec2) -> Vec2 {
+                self.velocity + self.angular_velocity * offset.perp()
+            }
+
+            /// Area of the shape, in square metres
+            pub fn area(&self) -> Real {
+                match &self.shape {
+                    Shape::Circle => consts::PI * self.radius * self.radius,
+                    Shape::Polygon(vertices) => polygon_area(vertices).abs(),
+                    Shape::Rect { half_extents } => 4. * half_extents.x * half_extents.y,
+                    Shape::Capsule {
+                        half_length,
+                        radius,
+                    } => 4. * half_length * radius + consts::PI * radius * radius,
+                }
+            }
+
+            pub fn mass(&self) -> Real {
+                self.mass
+                    .unwrap_or_else(|| self.area() * self.material.density)
+            }
+
+            /// Apply an impulse at `offset` from the centre, spinning the body if it's off centre
+            pub fn apply_impulse_at(&mut self, impulse: Vec2, offset: Vec2) {
+                self.apply_velocity_change_at(impulse / self.mass(), offset);
+            }
+
+            /// Like [`RigidBody::apply_impulse_at`] but already divided through by mass, for
+            /// contacts with immovable scenery where mass cancels out
+            pub fn apply_velocity_change_at(&mut self, dv: Vec2, offset: Vec2) {
+                self.velocity += dv;
+                self.angular_velocity += offset.perp_dot(dv) / self.inertia_per_mass();
+            }
+
+            /// Where a ray from `origin` along unit vector `dir` first meets the body, as the
+            /// distance along the ray and the surface normal there. A ray starting inside the
+            /// body hits straight away.
+            pub fn raycast(&self, origin: Vec2, dir: Vec2) -> Option<(Real, Vec2)> {
+                match self.shape {
+                    Shape::Circle => ray_circle(origin, dir, self.pos, self.radius),
+                    Shape::Polygon(_) | Shape::Rect { .. } => {
+                        ray_polygon(origin, dir, &self.world_vertices())
+                    }
+                    Shape::Capsule { radius, .. } => {
+                        // two end caps and the slab between them
+                        let (a, b) = self.capsule_segment();
+                        let side = (b - a).perp().normalize_or_zero() * radius;
+                        let slab = [a + side, b + side, b - side, a - side];
+                        [
+                            ray_circle(origin, dir, a, radius),
+                            ray_circle(origin, dir, b, radius),
+                            ray_polygon(origin, dir, &slab),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .min_by(|p, q| p.0.total_cmp(&q.0))
+                    }
+                }
+            }
+
+            pub fn aabb(&self) -> Aabb {
+                let half_extents = match self.shape {
+                    Shape::Rect { half_extents } => half_extents,
+                    _ => Vec2::splat(self.radius),
+                };
+                Aabb {
+                    min: self.pos - half_extents,
+                    max: self.pos + half_extents,
+                }
+            }
+        }
+
+        #[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+        pub struct Aabb {
+            pub min: Vec2,
+            pub max: Vec2,
+        }
+
+        impl Aabb {
+            pub fn overlaps(&self, other: &Aabb) -> bool {
+                self.min.x <= other.max.x
+                    && other.min.x <= self.max.x
+                    && self.min.y <= other.max.y
+                    && other.min.y <= self.max.y
+            }
+
+            pub fn contains(&self, other: &Aabb) -> bool {
+                self.min.x <= other.min.x
+                    && self.min.y <= other.min.y
+                    && other.max.x <= self.max.x
+                    && other.max.y <= self.max.y
+            }
+
+            pub fn center(&self) -> Vec2 {
+                (self.min + self.max) / 2.
+            }
+
+            /// The smallest box holding both
+            pub fn union(&self, other: &Aabb) -> Aabb {
+                Aabb {
+                    min: self.min.min(other.min),
+                    max: self.max.max(other.max),
+                }
+            }
+        }
+
+        pub struct Contact {
+            /// Unit vector pointing from the first body to the second
+            pub normal: Vec2,
+            /// How far the bodies overlap along the normal
+            pub depth: Real,
+            /// Where the bodies touch, in world space
+            pub point: Vec2,
+        }
+
+        impl Contact {
+            /// The same contact seen from the other body
+            fn flipped(self) -> Contact {
+                Contact {
+                    normal: -self.normal,
+                    ..self
+                }
+            }
+        }
+
+        /// Consecutive vertex pairs around a polygon, wrapping back to the start
+        fn edges(vertices: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+            vertices
+                .iter()
+                .zip(vertices.iter().cycle().skip(1))
+                .map(|(a, b)| (*a, *b))
+        }
+
+        pub fn centroid(vertices: &[Vec2]) -> Vec2 {
+            vertices.iter().sum::<Vec2>() / vertices.len() as Real
+        }
+
+        /// Signed area, positive when the vertices wind anticlockwise in y-up terms
+        pub fn polygon_area(vertices: &[Vec2]) -> Real {
+            edges(vertices).map(|(a, b)| a.perp_dot(b)).sum::<Real>() / 2.
+        }
+
+        /// Outward unit normal of each edge, whichever way the polygon is wound
+        fn edge_normals(vertices: &[Vec2]) -> Vec<Vec2> {
+            let center = centroid(vertices);
+            edges(vertices)
+                .map(|(a, b)| {
+                    let normal = (b - a).perp().normalize_or_zero();
+                    if normal.dot(a - center) < 0. {
+                        -normal
+                    } else {
+                        normal
+                    }
+                })
+                .collect()
+        }
+
+        fn project(vertices: &[Vec2], axis: Vec2) -> (Real, Real) {
+            vertices
+                .iter()
+                .map(|v| v.dot(axis))
+                .fold((Real::MAX, Real::MIN), |(lo, hi), d| (lo.min(d), hi.max(d)))
+        }
+
+        /// Narrow-phase test between any two bodies
+        pub fn collide(a: &RigidBody, b: &RigidBody) -> Option<Contact> {
+            match (&a.shape, &b.shape) {
+                (Shape::Circle, Shape::Circle) => circle_circle(a, b),
+                (Shape::Rect { .. }, Shape::Circle) => aabb_circle(&a.aabb(), b.pos, b.radius),
+                (Shape::Circle, Shape::Rect { .. }) => {
+                    aabb_circle(&b.aabb(), a.pos, a.radius).map(Contact::flipped)
+                }
+                (Shape::Rect { .. }, Shape::Rect { .. }) => aabb_aabb(&a.aabb(), &b.aabb()),
+                // rects are just polygons that happen to stay upright
+                (Shape::Rect { .. }, Shape::Polygon(_))
+                | (Shape::Polygon(_), Shape::Rect { .. }) => {
+                    polygon_polygon(&a.world_vertices(), &b.world_vertices())
+                }
+                (Shape::Polygon(_), Shape::Circle) => {
+                    polygon_circle(&a.world_vertices(), b.pos, b.radius)
+                }
+                (Shape::Circle, Shape::Polygon(_)) => {
+                    polygon_circle(&b.world_vertices(), a.pos, a.radius).map(Contact::flipped)
+                }
+                (Shape::Polygon(_), Shape::Polygon(_)) => {
+                    polygon_polygon(&a.world_vertices(), &b.world_vertices())
+                }
+                (Shape::Capsule { radius, .. }, _) => capsule_body(a.capsule_segment(), *radius, b),
+                (_, Shape::Capsule { radius, .. }) => {
+                    capsule_body(b.capsule_segment(), *radius, a).map(Contact::flipped)
+                }
+            }
+        }
+
+        pub fn circle_circle(a: &RigidBody, b: &RigidBody) -> Option<Contact> {
+            let offset = b.pos - a.pos;
+            let radii = a.radius + b.radius;
+            let dist_sq = offset.length_squared();
+            if dist_sq >= radii * radii {
+                return None;
+            }
+            let dist = dist_sq.sqrt();
+            // perfectly coincident centres have no meaningful direction, so pick one
+            let normal = if dist > Real::EPSILON {
+                offset / dist
+            } else {
+                Vec2::Y
+            };
+            let depth = radii - dist;
+            Some(Contact {
+                normal,
+                depth,
+                point: a.pos + normal * (a.radius - depth / 2.),
+            })
+        }
+
+        pub fn closest_on_segment(a: Vec2, b: Vec2, point: Vec2) -> Vec2 {
+            let edge = b - a;
+            let len_sq = edge.length_squared();
+            if len_sq <= Real::EPSILON {
+                return a;
+            }
+            a + edge * ((point - a).dot(edge) / len_sq).clamp(0., 1.)
+        }
+
+        /// Contact between a capsule and any other body, with the normal pointing away from the
+        /// capsule. Round shapes reduce to finding the right sphere along the capsule's spine.
+        fn capsule_body(
+            (start, end): (Vec2, Vec2),
+            radius: Real,
+            other: &RigidBody,
+        ) -> Option<Contact> {
+            let sphere_at = |center: Vec2| RigidBody {
+                pos: center,
+                radius,
+                ..Default::default()
+            };
+            match &other.shape {
+                Shape::Circle => {
+                    circle_circle(&sphere_at(closest_on_segment(start, end, other.pos)), other)
+                }
+                Shape::Capsule { radius: r, .. } => {
+                    let (p, q) = other.capsule_segment();
+                    let (on_self, on_other) = closest_between_segments(start, end, p, q);
+                    let other_sphere = RigidBody {
+                        pos: on_other,
+                        radius: *r,
+                        ..Default::default()
+                    };
+                    circle_circle(&sphere_at(on_self), &other_sphere)
+                }
+                Shape::Polygon(_) | Shape::Rect { .. } => {
+                    // treat the capsule as a fattened two-point polygon
+                    let vertices = other.world_vertices();
+                    let spine_normal = (end - start).perp().try_normalize();
+                    let axes = edge_normals(&vertices)
+                        .into_iter()
+                        .map(|n| (n, false))
+                        .chain(spine_normal.map(|n| (n, true)));
+                    separating_axis(&[start, end], radius, &vertices, axes)
+                }
+            }
+        }
+
+        /// Closest pair of points between segments `a0-a1` and `b0-b1`
+        fn closest_between_segments(a0: Vec2, a1: Vec2, b0: Vec2, b1: Vec2) -> (Vec2, Vec2) {
+            // crossing segments touch where they cross
+            let (da, db) = (a1 - a0, b1 - b0);
+            let denom = da.perp_dot(db);
+            if denom.abs() > Real::EPSILON {
+                let t = (b0 - a0).perp_dot(db) / denom;
+                let u = (b0 - a0).perp_dot(da) / denom;
+                if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+                    let p = a0 + da * t;
+                    return (p, p);
+                }
+            }
+            // otherwise one of the four endpoints is involved
+            [
+                (a0, closest_on_segment(b0, b1, a0)),
+                (a1, closest_on_segment(b0, b1, a1)),
+                (closest_on_segment(a0, a1, b0), b0),
+                (closest_on_segment(a0, a1, b1), b1),
+            ]
+            .into_iter()
+            .min_by(|(p, q), (r, s)| p.distance_squared(*q).total_cmp(&r.distance_squared(*s)))
+            .unwrap()
+        }
+
+        /// Contact between a box and a circle, with the normal pointing at the circle
+        pub fn aabb_circle(aabb: &Aabb, center: Vec2, radius: Real) -> Option<Contact> {
+            let closest = center.clamp(aabb.min, aabb.max);
+            if closest != center {
+                let offset = center - closest;
+                let dist = offset.length();
+                if dist >= radius {
+                    return None;
+                }
+                return Some(Contact {
+                    normal: offset / dist,
+                    depth: radius - dist,
+                    point: closest,
+                });
+            }
+            // centre inside the box: push out through the nearest side
+            let to_min = center - aabb.min;
+            let to_max = aabb.max - center;
+            let (normal, dist) = [
+                (-Vec2::X, to_min.x),
+                (Vec2::X, to_max.x),
+                (-Vec2::Y, to_min.y),
+                (Vec2::Y, to_max.y),
+            ]
+            .into_iter()
+            .min_by(|a, b| a.1.total_cmp(&b.1))?;
+            Some(Contact {
+                normal,
+                depth: radius + dist,
+                point: center + normal * dist,
+            })
+        }
+
+        pub fn aabb_aabb(a: &Aabb, b: &Aabb) -> Option<Contact> {
+            let overlap = a.max.min(b.max) - a.min.max(b.min);
+            if overlap.x <= 0. || overlap.y <= 0. {
+                return None;
+            }
+            let offset = b.center() - a.center();
+            // separate along whichever axis needs the smaller push
+            let (normal, depth) = if overlap.x < overlap.y {
+                (vec2(offset.x.signum(), 0.), overlap.x)
+            } else {
+                (vec2(0., offset.y.signum()), overlap.y)
+            };
+            // middle of the overlapping region
+            let point = (a.min.max(b.min) + a.max.min(b.max)) / 2.;
+            Some(Contact {
+                normal,
+                depth,
+                point,
+            })
+        }
+
+        /// Contact between a convex polygon and a circle, with the normal pointing at the circle
+        pub fn polygon_circle(vertices: &[Vec2], center: Vec2, radius: Real) -> Option<Contact> {
+            let normals = edge_normals(vertices);
+            // how far outside each edge's line the centre sits
+            let (face, face_dist) = edges(vertices)
+                .zip(&normals)
+                .map(|((a, _), n)| (center - a).dot(*n))
+                .enumerate()
+                .fold(
+                    (0, Real::MIN),
+                    |best, (i, d)| if d > best.1 { (i, d) } else { best },
+                );
+            if face_dist > radius {
+                return None;
+            }
+            if face_dist <= 0. {
+                // centre inside the polygon: push out through the nearest face
+                let normal = normals[face];
+                return Some(Contact {
+                    normal,
+                    depth: radius - face_dist,
+                    point: center - normal * face_dist,
+                });
+            }
+            let closest = edges(vertices)
+                .map(|(a, b)| closest_on_segment(a, b, center))
+                .min_by(|p, q| {
+                    p.distance_squared(center)
+                        .total_cmp(&q.distance_squared(center))
+                })?;
+            let offset = center - closest;
+            let dist = offset.length();
+            if dist >= radius || dist <= Real::EPSILON {
+                return None;
+            }
+            Some(Contact {
+                normal: offset / dist,
+                depth: radius - dist,
+                point: closest,
+            })
+        }
+
+        /// Separating-axis test between two convex polygons
+        pub fn polygon_polygon(a: &[Vec2], b: &[Vec2]) -> Option<Contact> {
+            let axes = edge_normals(a)
+                .into_iter()
+                .map(|n| (n, true))
+                .chain(edge_normals(b).into_iter().map(|n| (n, false)));
+            separating_axis(a, 0., b, axes)
+        }
+
+        /// Separating-axis test between two convex point sets, where `a` may be rounded off by
+        /// `a_radius`. `axes` are the candidate normals, each flagged with whether it's one of
+        /// `a`'s faces. The contact normal points from `a` to `b`.
+        fn separating_axis(
+            a: &[Vec2],
+            a_radius: Real,
+            b: &[Vec2],
+            axes: impl Iterator<Item = (Vec2, bool)>,
+        ) -> Option<Contact> {
+            let mut best: Option<(Real, Vec2, bool)> = None;
+            for (axis, on_a) in axes {
+                let (a_lo, a_hi) = project(a, axis);
+                let (b_lo, b_hi) = project(b, axis);
+                // how far `b` would have to move either way along the axis to get clear
+                let forwards = a_hi + a_radius - b_lo;
+                let backwards = b_hi - (a_lo - a_radius);
+                if forwards <= 0. || backwards <= 0. {
+                    // found a gap, so they can't be touching
+                    return None;
+                }
+                let (depth, normal) = if forwards < backwards {
+                    (forwards, axis)
+                } else {
+                    (backwards, -axis)
+                };
+                if best.is_none_or(|(d, ..)| depth < d) {
+                    best = Some((depth, normal, on_a));
+                }
+            }
+            let (depth, normal, on_a) = best?;
+            // the deepest point of whichever side didn't supply the separating face
+            let point = if on_a {
+                *b.iter()
+                    .min_by(|p, q| p.dot(normal).total_cmp(&q.dot(normal)))?
+            } else {
+                *a.iter()
+                    .max_by(|p, q| p.dot(normal).total_cmp(&q.dot(normal)))?
+                    + normal * a_radius
+            };
+            Some(Contact {
+                normal,
+                depth,
+                point,
+            })
+        }
+
+        /// A bare circle for testing what overlaps a round region
+        pub fn circle_probe(center: Vec2, radius: Real) -> RigidBody {
+            RigidBody {
+                pos: center,
+                radius,
+                ..Default::default()
+            }
+        }
+
+        fn ray_circle(origin: Vec2, dir: Vec2, center: Vec2, radius: Real) -> Option<(Real, Vec2)> {
+            let offset = origin - center;
+            let along = offset.dot(dir);
+            let outside = offset.length_squared() - radius * radius;
+            if outside <= 0. {
+                return Some((0., -dir));
+            }
+            let discriminant = along * along - outside;
+            let t = -along - discriminant.sqrt();
+            // either it misses entirely or the circle is behind the ray
+            if discriminant < 0. || t < 0. {
+                return None;
+            }
+            let point = origin + dir * t;
+            Some((t, (point - center).normalize_or_zero()))
+        }
+
+        /// Clip the ray against each edge of a convex polygon in turn, keeping the last
+        /// edge it enters through
+        fn ray_polygon(origin: Vec2, dir: Vec2, vertices: &[Vec2]) -> Option<(Real, Vec2)> {
+            let (mut enter, mut exit) = (0., Real::INFINITY);
+            let mut normal = -dir;
+            for ((a, _), n) in edges(vertices).zip(edge_normals(vertices)) {
+                // how far inside this edge the origin is, and how fast the ray heads out
+                let depth = n.dot(a - origin);
+                let heading = n.dot(dir);
+                if heading == 0. {
+                    if depth < 0. {
+                        return None;
+                    }
+                    continue;
+                }
+                let t = depth / heading;
+                if heading < 0. {
+                    if t > enter {
+                        enter = t;
+                        normal = n;
+                    }
+                } else {
+                    exit = Real::min(exit, t);
+                }
+                if enter > exit {
+                    return None;
+                }
+            }
+            Some((enter, normal))
+        }
+
+        /// Fraction of `motion` a point can travel before crossing into the solid side of the
+        /// plane through `plane_point` facing `normal`
+        pub fn sweep_point_plane(
+            start: Vec2,
+            motion: Vec2,
+            plane_point: Vec2,
+            normal: Vec2,
+        ) -> Option<Real> {
+            let approach = motion.dot(normal);
+            if approach >= 0. {
+                // moving away from or along the surface
+                return None;
+            }
+            let dist = (start - plane_point).dot(normal);
+            if dist <= 0. {
+                // already touching or inside, so it hits immediately
+                return Some(0.);
+            }
+            let t = dist / -approach;
+            (t <= 1.).then_some(t)
+        }
+
+        /// Fraction of `motion` a circle can travel before touching the segment `a-b`
+        pub fn sweep_circle_segment(
+            start: Vec2,
+            motion: Vec2,
+            radius: Real,
+            a: Vec2,
+            b: Vec2,
+        ) -> Option<Real> {
+            // against the segment's face, offset by the radius towards the circle
+            let mut normal = (b - a).perp().normalize_or_zero();
+            if (start - a).dot(normal) < 0. {
+                normal = -normal;
+            }
+            let face_hit =
+                sweep_point_plane(start, motion, a + normal * radius, normal).filter(|&t| {
+                    let along = (start + motion * t - a).dot(b - a);
+                    (0. ..=(b - a).length_squared()).contains(&along)
+                });
+            // and against the rounded ends
+            let end_hits = [a, b]
+                .into_iter()
+                .filter_map(|end| sweep_point_circle(start, motion, end, radius));
+            face_hit
+                .into_iter()
+                .chain(end_hits)
+                .min_by(|p, q| p.total_cmp(q))
+        }
+
+        /// Fraction of `motion` a point can travel before entering the circle at `center`
+        fn sweep_point_circle(
+            start: Vec2,
+            motion: Vec2,
+            center: Vec2,
+            radius: Real,
+        ) -> Option<Real> {
+            let offset = start - center;
+            let a = motion.length_squared();
+            let b = 2. * offset.dot(motion);
+            let c = offset.length_squared() - radius * radius;
+            if c <= 0. {
+                return (b < 0.).then_some(0.);
+            }
+            let discriminant = b * b - 4. * a * c;
+            if a <= Real::EPSILON || discriminant < 0. {
+                return None;
+            }
+            let t = (-b - discriminant.sqrt()) / (2. * a);
+            (0. ..=1.).contains(&t).then_some(t)
+        }
+
+        /// Acceleration from air (or water) resistance on a body moving at `velocity` through
+        /// a fluid of the given density.
+        ///
+        /// The force acts on the body's cross-section but has to move its whole mass, so small
+        /// or light bodies get slowed far more than large, heavy ones at the same speed.
+        pub fn drag_acceleration(
+            drag: Drag,
+            cross_section: Real,
+            mass: Real,
+            fluid_density: Real,
+            velocity: Vec2,
+        ) -> Vec2 {
+            let speed = velocity.length();
+            let force = match drag {
+                Drag::None => return Vec2::ZERO,
+                Drag::Linear(k) => k * cross_section * speed * fluid_density / AIR_DENSITY,
+                Drag::Quadratic(cd) => 0.5 * fluid_density * cd * cross_section * speed * speed,
+            };
+            let decel = force / mass;
+            -velocity.normalize_or_zero() * decel
+        }
+
+        /// Electrostatic force `source` puts on `target`: like charges repel, opposites attract
+        pub fn coulomb_force(source: &RigidBody, target: &RigidBody) -> Vec2 {
+            let offset = target.pos - source.pos;
+            // bodies can't get closer than touching, so don't let the force blow up past that
+            let touching = source.radius + target.radius;
+            let dist_sq = offset.length_squared().max(touching * touching);
+            offset.normalize_or_zero() * COULOMB_CONSTANT * source.charge * target.charge / dist_sq
+        }
+
+        /// What a force field gets to see of a body, which may be one of an integrator's
+        /// in-between guesses rather than where the body really is
+        #[derive(Clone, Copy)]
+        pub struct BodyState {
+            pub pos: Vec2,
+            pub velocity: Vec2,
+            pub mass: Real,
+            pub radius: Real,
+            pub gravity_scale: Real,
+            pub drag: Drag,
+            /// Density of the air or liquid around the body
+            pub medium_density: Real,
+        }
+
+        impl BodyState {
+            /// Where `body` really is, out in the open air
+            pub fn of(body: &RigidBody) -> Self {
+                Self {
+                    pos: body.pos,
+                    velocity: body.velocity,
+                    mass: body.mass(),
+                    radius: body.radius,
+                    gravity_scale: body.gravity_scale,
+                    drag: body.drag,
+                    medium_density: AIR_DENSITY,
+                }
+            }
+        }
+
+        /// Something that pushes on every body, like gravity or a magnet
+        pub trait ForceField: Send + Sync {
+            fn force(&self, body: &BodyState, t: f64) -> Vec2;
+        }
+
+        /// Pulls everything the same way, however heavy, unless the body scales it
+        pub struct Gravity(pub Vec2);
+
+        impl ForceField for Gravity {
+            fn force(&self, body: &BodyState, _t: f64) -> Vec2 {
+                self.0 * body.mass * body.gravity_scale
+            }
+        }
+
+        /// Like [`Gravity`] but changing over time
+        pub struct Wind(pub fn(f64) -> Vec2);
+
+        impl ForceField for Wind {
+            fn force(&self, body: &BodyState, t: f64) -> Vec2 {
+                (self.0)(t) * body.mass
+            }
+        }
+
+        /// Slows each body down as its [`Drag`] says, through whatever it's moving in
+        pub struct AirResistance;
+
+        impl ForceField for AirResistance {
+            fn force(&self, body: &BodyState, _t: f64) -> Vec2 {
+                let cross_section = 2. * body.radius;
+                let accel = drag_acceleration(
+                    body.drag,
+                    cross_section,
+                    body.mass,
+                    body.medium_density,
+                    body.velocity,
+                );
+                accel * body.mass
+            }
+        }
+
+        /// An inverse-square pull towards a fixed point, softened inside `core_radius`
+        #[derive(Clone, Copy)]
+        pub struct Attractor {
+            pub pos: Vec2,
+            pub strength: Real,
+            pub core_radius: Real,
+        }
+
+        impl ForceField for Attractor {
+            fn force(&self, body: &BodyState, _t: f64) -> Vec2 {
+                let offset = self.pos - body.pos;
+                let dist_sq = offset
+                    .length_squared()
+                    .max(self.core_radius * self.core_radius);
+                offset.normalize_or_zero() * self.strength * body.mass / dist_sq
+            }
+        }
+
+        pub trait Integrator: Send + Sync {
+            /// Advance `pos` and `velocity` by `dt` through the acceleration field
+            /// `accel(pos, velocity)`
+            fn step(
+                &self,
+                pos: &mut Vec2,
+                velocity: &mut Vec2,
+                dt: Real,
+                accel: &dyn Fn(Vec2, Vec2) -> Vec2,
+            );
+
+            fn name(&self) -> &'static str;
+        }
+
+        /// Moves using the velocity from the start of the step. Simple, but steadily gains
+        /// energy.
+        pub struct ExplicitEuler;
+
+        impl Integrator for ExplicitEuler {
+            fn step(
+                &self,
+                pos: &mut Vec2,
+                velocity: &mut Vec2,
+                dt: Real,
+                accel: &dyn Fn(Vec2, Vec2) -> Vec2,
+            ) {
+                let a = accel(*pos, *velocity);
+                *pos += *velocity * dt;
+                *velocity += a * dt;
+            }
+
+            fn name(&self) -> &'static str {
+                "explicit Euler"
+            }
+        }
+
+        /// Updates velocity first and moves with the new one, which keeps orbits and
+        /// bounces from drifting
+        pub struct SemiImplicitEuler;
+
+        impl Integrator for SemiImplicitEuler {
+            fn step(
+                &self,
+                pos: &mut Vec2,
+                velocity: &mut Vec2,
+                dt: Real,
+                accel: &dyn Fn(Vec2, Vec2) -> Vec2,
+            ) {
+                *velocity += accel(*pos, *velocity) * dt;
+                *pos += *velocity * dt;
+            }
+
+            fn name(&self) -> &'static str {
+                "semi-implicit Euler"
+            }
+        }
+
+        pub struct VelocityVerlet;
+
+        impl Integrator for VelocityVerlet {
+            fn step(
+                &self,
+                pos: &mut Vec2,
+                velocity: &mut Vec2,
+                dt: Real,
+                accel: &dyn Fn(Vec2, Vec2) -> Vec2,
+            ) {
+                let a0 = accel(*pos, *velocity);
+                *pos += *velocity * dt + a0 * (0.5 * dt * dt);
+                // velocity-dependent forces need a guess at the end velocity
+                let a1 = accel(*pos, *velocity + a0 * dt);
+                *velocity += (a0 + a1) * (0.5 * dt);
+            }
+
+            fn name(&self) -> &'static str {
+                "velocity Verlet"
+            }
+        }
+
+        /// Classic fourth-order Runge-Kutta
+        pub struct Rk4;
+
+        impl Integrator for Rk4 {
+            fn step(
+                &self,
+                pos: &mut Vec2,
+                velocity: &mut Vec2,
+                dt: Real,
+                accel: &dyn Fn(Vec2, Vec2) -> Vec2,
+            ) {
+                let (p0, v0) = (*pos, *velocity);
+                let (dp1, dv1) = (v0, accel(p0, v0));
+                let (dp2, dv2) = (
+                    v0 + dv1 * (dt / 2.),
+                    accel(p0 + dp1 * (dt / 2.), v0 + dv1 * (dt / 2.)),
+                );
+                let (dp3, dv3) = (
+                    v0 + dv2 * (dt / 2.),
+                    accel(p0 + dp2 * (dt / 2.), v0 + dv2 * (dt / 2.)),
+                );
+                let (dp4, dv4) = (v0 + dv3 * dt, accel(p0 + dp3 * dt, v0 + dv3 * dt));
+                *pos = p0 + (dp1 + dp2 * 2. + dp3 * 2. + dp4) * (dt / 6.);
+                *velocity = v0 + (dv1 + dv2 * 2. + dv3 * 2. + dv4) * (dt / 6.);
+            }
+
+            fn name(&self) -> &'static str {
+                "RK4"
+            }
+        }
+
+        /// One end of a distance constraint
+        pub enum ConstraintEnd<'a> {
+            Fixed(Vec2),
+            Body(&'a mut RigidBody),
+        }
+
+        impl ConstraintEnd<'_> {
+            fn pos(&self) -> Vec2 {
+                match self {
+                    ConstraintEnd::Fixed(pos) => *pos,
+                    ConstraintEnd::Body(body) => body.pos,
+                }
+            }
+
+            fn velocity(&self) -> Vec2 {
+                match self {
+                    ConstraintEnd::Fixed(_) => Vec2::ZERO,
+                    ConstraintEnd::Body(body) => body.velocity,
+                }
+            }
+
+            /// Share of any correction this end has to absorb, before normalising
+            fn mobility(&self) -> Real {
+                match self {
+                    ConstraintEnd::Fixed(_) => 0.,
+                    ConstraintEnd::Body(body) => 1. / body.mass(),
+                }
+            }
+
+            fn shift(&mut self, dpos: Vec2, dvel: Vec2) {
+                if let ConstraintEnd::Body(body) = self {
+                    body.pos += dpos;
+                    body.velocity += dvel;
+                }
+            }
+
+            /// Offset from the centre to a point given in the end's own frame
+            fn arm(&self, local: Vec2) -> Vec2 {
+                match self {
+                    ConstraintEnd::Fixed(_) => local,
+                    ConstraintEnd::Body(body) => body.to_world(local) - body.pos,
+                }
+            }
+
+            fn point_velocity(&self, arm: Vec2) -> Vec2 {
+                match self {
+                    ConstraintEnd::Fixed(_) => Vec2::ZERO,
+                    ConstraintEnd::Body(body) => body.point_velocity(arm),
+                }
+            }
+
+            /// How far a point `arm` from the centre moves per unit of push there, counting
+            /// both the body sliding and the body turning
+            fn pivot_mobility(&self, arm: Vec2) -> Mat2 {
+                let ConstraintEnd::Body(body) = self else {
+                    return Mat2::ZERO;
+                };
+                let inv_inertia = 1. / (body.mass() * body.inertia_per_mass());
+                let lever = arm.perp();
+                Mat2::from_diagonal(Vec2::splat(1. / body.mass()))
+                    + Mat2::from_cols(lever * lever.x, lever * lever.y) * inv_inertia
+            }
+
+            /// Push a point `arm` from the centre, both moving it by `correction` and changing
+            /// its momentum by `impulse`
+            fn push_at(&mut self, arm: Vec2, correction: Vec2, impulse: Vec2) {
+                if let ConstraintEnd::Body(body) = self {
+                    let inv_inertia = 1. / (body.mass() * body.inertia_per_mass());
+                    body.pos += correction / body.mass();
+                    body.angle += arm.perp_dot(correction) * inv_inertia;
+                    body.apply_impulse_at(impulse, arm);
+                }
+            }
+        }
+
+        /// Move both ends of a rod so they're exactly `length` apart, removing any velocity
+        /// that would stretch or compress it
+        pub fn solve_distance(mut a: ConstraintEnd, mut b: ConstraintEnd, length: Real) {
+            let total_mobility = a.mobility() + b.mobility();
+            let offset = b.pos() - a.pos();
+            let dist = offset.length();
+            if total_mobility == 0. || dist <= Real::EPSILON {
+                return;
+            }
+            let normal = offset / dist;
+            let stretch = dist - length;
+            let stretch_speed = (b.velocity() - a.velocity()).dot(normal);
+            let a_share = a.mobility() / total_mobility;
+            let b_share = b.mobility() / total_mobility;
+            a.shift(normal * stretch * a_share, normal * stretch_speed * a_share);
+            b.shift(
+                -normal * stretch * b_share,
+                -normal * stretch_speed * b_share,
+            );
+        }
+
+        /// Bring a pivot point on `a` and one on `b` together, turning and sliding each body
+        /// according to its mass and inertia, then stop the two points drifting apart
+        pub fn solve_hinge(
+            mut a: ConstraintEnd,
+            a_pivot: Vec2,
+            mut b: ConstraintEnd,
+            b_pivot: Vec2,
+        ) {
+            let (a_arm, b_arm) = (a.arm(a_pivot), b.arm(b_pivot));
+            let mobility = a.pivot_mobility(a_arm) + b.pivot_mobility(b_arm);
+            if mobility.determinant().abs() <= Real::EPSILON {
+                return;
+            }
+            let inverse = mobility.inverse();
+            let gap = (b.pos() + b_arm) - (a.pos() + a_arm);
+            let drift = b.point_velocity(b_arm) - a.point_velocity(a_arm);
+            let (correction, impulse) = (inverse * gap, inverse * drift);
+            a.push_at(a_arm, correction, impulse);
+            b.push_at(b_arm, -correction, -impulse);
+        }
+
+        /// Restitution for an impact at `closing_speed` (negative, as things approach), scaled
+        /// by `scale` but never past a perfect bounce. It drops to nothing for gentle touches so
+        /// they turn into resting contact.
+        fn bounciness(restitution: Real, scale: Real, closing_speed: Real) -> Real {
+            if -closing_speed < BOUNCE_CUTOFF_SPEED {
+                0.
+            } else {
+                (restitution * scale).min(1.)
+            }
+        }
+
+        /// Push overlapping bodies apart and exchange an impulse along the contact normal, with
+        /// the restitution scaled by `restitution_scale`. Returns the size of that impulse,
+        /// which is zero if they were already separating.
+        pub fn resolve_contact(
+            a: &mut RigidBody,
+            b: &mut RigidBody,
+            contact: &Contact,
+            restitution_scale: Real,
+        ) -> Real {
+            let (a_inv_mass, b_inv_mass) = (1. / a.mass(), 1. / b.mass());
+            // the lighter body gets shoved further
+            let correction = contact.normal * contact.depth / (a_inv_mass + b_inv_mass);
+            a.pos -= correction * a_inv_mass;
+            b.pos += correction * b_inv_mass;
+
+            let normal = contact.normal;
+            let a_arm = contact.point - a.pos;
+            let b_arm = contact.point - b.pos;
+            let closing_speed = (b.point_velocity(b_arm) - a.point_velocity(a_arm)).dot(normal);
+            if closing_speed >= 0. {
+                // already separating
+                return 0.;
+            }
+            // the less bouncy material dominates, e.g. clay doesn't rebound off rubber
+            let restitution = bounciness(
+                a.material.restitution.min(b.material.restitution),
+                restitution_scale,
+                closing_speed,
+            );
+            // conserving momentum means heavy bodies barely budge, and off-centre hits also
+            // have to spin the bodies up
+            let resistance = a_inv_mass
+                + b_inv_mass
+                + a_arm.perp_dot(normal).powi(2) * a_inv_mass / a.inertia_per_mass()
+                + b_arm.perp_dot(normal).powi(2) * b_inv_mass / b.inertia_per_mass();
+            let impulse = -(1. + restitution) * closing_speed / resistance;
+            a.apply_impulse_at(-normal * impulse, a_arm);
+            b.apply_impulse_at(normal * impulse, b_arm);
+            impulse
+        }
+
+        /// Resolve a body touching immovable scenery, with the contact normal pointing out of
+        /// the scenery. The scenery's surface may be sliding along at `surface_velocity`, like a
+        /// conveyor belt. `up` is the direction opposing gravity, for telling floors from walls.
+        /// The body's restitution is scaled by `restitution_scale`. Returns the size of the
+        /// impulse along the normal, which is zero if the body was already leaving.
+        pub fn resolve_static_contact(
+            body: &mut RigidBody,
+            contact: &Contact,
+            surface_velocity: Vec2,
+            up: Vec2,
+            dt: Real,
+            restitution_scale: Real,
+        ) -> Real {
+            let normal = contact.normal;
+            let arm = contact.point - body.pos;
+            body.pos += normal * contact.depth;
+            if normal.dot(up) > GROUND_SLOPE {
+                body.grounded = true;
+            }
+
+            let inertia = body.inertia_per_mass();
+            let tangent = normal.perp();
+            let normal_speed = body.point_velocity(arm).dot(normal);
+            let mut impulse = 0.;
+            if normal_speed < 0. {
+                let restitution =
+                    bounciness(body.material.restitution, restitution_scale, normal_speed);
+                let normal_impulse = -(1. + restitution) * normal_speed
+                    / (1. + arm.perp_dot(normal).powi(2) / inertia);
+                body.apply_velocity_change_at(normal * normal_impulse, arm);
+                impulse = normal_impulse * body.mass();
+
+                // sliding friction works against the contact point slipping over the surface,
+                // trading linear speed for spin until the body rolls
+                let slip = (body.point_velocity(arm) - surface_velocity).dot(tangent);
+                let max_friction = body.material.friction * normal_impulse;
+                let friction = (-slip / (1. + arm.perp_dot(tangent).powi(2) / inertia))
+                    .clamp(-max_friction, max_friction);
+                body.apply_velocity_change_at(tangent * friction, arm);
+            }
+
+            // rolling resistance slows travel over the surface and spin together without ever
+            // reversing either
+            let pressing = body.applied_accel.dot(-normal).max(0.);
+            let slowdown = body.material.rolling_resistance * pressing * dt;
+            let tangent_speed = (body.velocity - surface_velocity).dot(tangent);
+            let new_speed = tangent_speed.signum() * (tangent_speed.abs() - slowdown).max(0.);
+            body.velocity += tangent * (new_speed - tangent_speed);
+            let spin = body.angular_velocity;
+            body.angular_velocity = spin.signum() * (spin.abs() - slowdown / body.radius).max(0.);
+            impulse
+        }
+
+        /// Replay a circle's motion over the last step, stopping at each static edge it hits on
+        /// the way so a fast ball bounces from where it actually struck instead of wherever it
+        /// ended up. Each edge comes with the velocity of its surface. Returns where each hit
+        /// was and the size of its impulse.
+        pub fn sweep_against_edges(
+            body: &mut RigidBody,
+            edges: &[((Vec2, Vec2), Vec2)],
+            up: Vec2,
+            dt: Real,
+            restitution_scale: Real,
+        ) -> Vec<(Vec2, Real)> {
+            let mut hits = Vec::new();
+            let mut motion = body.pos - body.prev_pos;
+            let mut remaining = 1.;
+            body.pos = body.prev_pos;
+            for _ in 0..MAX_SWEEPS_PER_STEP {
+                let hit = edges
+                    .iter()
+                    .filter_map(|&((a, b), surface)| {
+                        sweep_circle_segment(body.pos, motion, body.radius, a, b)
+                            .map(|t| (t, a, b, surface))
+                    })
+                    .min_by(|p, q| p.0.total_cmp(&q.0));
+                let Some((t, a, b, surface)) = hit else {
+                    body.pos += motion;
+                    return hits;
+                };
+                body.pos += motion * t;
+                remaining *= 1. - t;
+                let point = closest_on_segment(a, b, body.pos);
+                let normal = (body.pos - point).normalize_or_zero();
+                let contact = Contact {
+                    normal,
+                    depth: 0.,
+                    point,
+                };
+                let impulse =
+                    resolve_static_contact(body, &contact, surface, up, dt, restitution_scale);
+                if impulse > 0. {
+                    hits.push((point, impulse));
+                }
+                // carry on with whatever's left of the step at the new velocity
+                motion = body.velocity * dt * remaining;
+            }
+            hits
+        }
+
+        /// A rectangle of liquid with its surface along the top edge
+        pub struct FluidRegion {
+            pub bounds: Aabb,
+            pub density: Real,
+        }
+
+        impl FluidRegion {
+            /// How much of `body` is under the surface, from 0 to 1. Bodies are treated as their
+            /// bounding circle, which is close enough for bobbing.
+            pub fn submerged_fraction(&self, body: &RigidBody) -> Real {
+                let r = body.radius;
+                let Aabb { min, max } = self.bounds;
+                if body.pos.x + r < min.x || body.pos.x - r > max.x || r <= 0. {
+                    return 0.;
+                }
+                // share of the circle below the line at height `y`, from the area of the cap
+                // that line cuts off; the slice between surface and bottom is then a difference
+                let below = |y: Real| {
+                    let h = (body.pos.y + r - y).clamp(0., 2. * r);
+                    let cap = r * r * ((r - h) / r).acos() - (r - h) * (2. * r * h - h * h).sqrt();
+                    cap / (consts::PI * r * r)
+                };
+                below(min.y) - below(max.y)
+            }
+
+            pub fn on_draw(&self, renderer: &mut dyn Renderer) {
+                let (min, size) = (
+                    to_mq(self.bounds.min),
+                    to_mq(self.bounds.max - self.bounds.min),
+                );
+                let color = mq::Color::new(0.2, 0.45, 1., 0.35);
+                renderer.draw_rectangle(min.x, min.y, size.x, size.y, color);
+            }
+        }
+
+        /// A piece of immovable scenery
+        pub enum StaticCollider {
+            /// A thin wall between two points
+            Segment(Vec2, Vec2),
+            /// A solid block
+            Box(Aabb),
+            /// A ledge that bodies can jump up through but land on from above
+            Platform(Vec2, Vec2),
+            /// A belt whose surface runs from the first point towards the second at the given
+            /// speed, carrying along whatever rests on it
+            Conveyor(Vec2, Vec2, Real),
+        }
+
+        impl StaticCollider {
+            /// Segments joining each point to the next, for ramps, funnels and other bent walls
+            pub fn chain(points: &[Vec2]) -> impl Iterator<Item = StaticCollider> + '_ {
+                points
+                    .windows(2)
+                    .map(|pair| StaticCollider::Segment(pair[0], pair[1]))
+            }
+
+            /// Stand-in body for narrow-phase tests
+            pub fn body(&self) -> RigidBody {
+                match self {
+                    StaticCollider::Segment(a, b)
+                    | StaticCollider::Platform(a, b)
+                    | StaticCollider::Conveyor(a, b, _) => {
+                        let axis = *b - *a;
+                        RigidBody {
+                            pos: (*a + *b) / 2.,
+                            radius: axis.length() / 2. + SEGMENT_THICKNESS,
+                            shape: Shape::Capsule {
+                                half_length: axis.length() / 2.,
+                                radius: SEGMENT_THICKNESS,
+                            },
+                            angle: axis.y.atan2(axis.x),
+                            ..Default::default()
+                        }
+                    }
+                    StaticCollider::Box(aabb) => RigidBody {
+                        pos: aabb.center(),
+                        radius: (aabb.max - aabb.min).length() / 2.,
+                        shape: Shape::Rect {
+                            half_extents: (aabb.max - aabb.min) / 2.,
+                        },
+                        ..Default::default()
+                    },
+                }
+            }
+
+            /// Whether this should stop `body` this step. Platforms only catch bodies that
+            /// started the step entirely on their upper side.
+            pub fn blocks(&self, body: &RigidBody, up: Vec2) -> bool {
+                let StaticCollider::Platform(a, b) = self else {
+                    return true;
+                };
+                let normal = (*b - *a).perp().normalize_or_zero();
+                let top = if normal.dot(up) < 0. { -normal } else { normal };
+                let lowest = body.support(-top) - body.pos + body.prev_pos;
+                (lowest - *a).dot(top) >= -SEGMENT_THICKNESS
+            }
+
+            /// How fast the collider's surface is moving, which is nothing unless it's a belt
+            pub fn surface_velocity(&self) -> Vec2 {
+                match self {
+                    StaticCollider::Conveyor(a, b, speed) => (*b - *a).normalize_or_zero() * *speed,
+                    _ => Vec2::ZERO,
+                }
+            }
+
+            /// Outline segments, for sweeping circles against
+            pub fn edges(&self) -> Vec<(Vec2, Vec2)> {
+                match self {
+                    StaticCollider::Segment(a, b)
+                    | StaticCollider::Platform(a, b)
+                    | StaticCollider::Conveyor(a, b, _) => vec![(*a, *b)],
+                    StaticCollider::Box(aabb) => {
+                        let Aabb { min, max } = *aabb;
+                        let (tr, bl) = (vec2(max.x, min.y), vec2(min.x, max.y));
+                        vec![(min, tr), (tr, max), (max, bl), (bl, min)]
+                    }
+                }
+            }
+
+            /// Draw the collider as of simulation time `time`, which moves a belt's stripes
+            pub fn on_draw(&self, renderer: &mut dyn Renderer, time: f64) {
+                let mut line = |a: Vec2, b: Vec2, thickness, color| {
+                    let (a, b) = (to_mq(a), to_mq(b));
+                    renderer.draw_line(a.x, a.y, b.x, b.y, thickness, color);
+                };
+                match self {
+                    StaticCollider::Segment(a, b) => line(*a, *b, 0.05, mq::GRAY),
+                    StaticCollider::Box(aabb) => {
+                        let (min, size) = (to_mq(aabb.min), to_mq(aabb.max - aabb.min));
+                        renderer.draw_rectangle(min.x, min.y, size.x, size.y, mq::DARKGRAY);
+                    }
+                    StaticCollider::Platform(a, b) => line(*a, *b, 0.1, mq::BROWN),
+                    StaticCollider::Conveyor(a, b, speed) => {
+                        line(*a, *b, 0.15, mq::DARKGRAY);
+                        let (length, dir) = ((*b - *a).length(), (*b - *a).normalize_or_zero());
+                        let across = dir.perp() * 0.075;
+                        let shift = (time as Real * speed).rem_euclid(CONVEYOR_STRIPE_GAP);
+                        let mut along = shift;
+                        while along < length {
+                            let (p, q) = (*a + dir * along - across, *a + dir * along + across);
+                            line(p, q, 0.05, mq::YELLOW);
+                            along += CONVEYOR_STRIPE_GAP;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub mod broad_phase {
+        use std::cell::{Ref, RefCell};
+
+        use super::physics::Aabb;
+        use super::real::vec2;
+
+        pub trait BroadPhase {
+            /// Given `(object index, bounds)` entries, return the index pairs `(i, j)` with
+            /// `i < j` whose bounds might overlap
+            fn candidate_pairs(&mut self, entries: &[(usize, Aabb)]) -> Vec<(usize, usize)>;
+
+            /// Object indices from `entries` whose bounds might overlap `region`
+            fn query(&self, entries: &[(usize, Aabb)], region: &Aabb) -> Vec<usize> {
+                entries
+                    .iter()
+                    .filter(|(_, aabb)| aabb.overlaps(region))
+                    .map(|(i, _)| *i)
+                    .collect()
+            }
+
+            /// The regions space gets split into for `entries`, for drawing. Broad phases that
+            /// don't split space have none.
+            fn cells(&self, _entries: &[(usize, Aabb)]) -> Vec<Aabb> {
+                Vec::new()
+            }
+        }
+
+        /// Test every body against every other body
+        pub struct AllPairs;
+
+        impl BroadPhase for AllPairs {
+            fn candidate_pairs(&mut self, entries: &[(usize, Aabb)]) -> Vec<(usize, usize)> {
+                let mut pairs = Vec::new();
+                for (k, (i, a)) in entries.iter().enumerate() {
+                    for (j, b) in &entries[k + 1..] {
+                        if a.overlaps(b) {
+                            pairs.push((*i, *j));
+                        }
+                    }
+                }
+                pairs
+            }
+        }
+
+        /// Region quadtree rebuilt whenever the bodies have moved. Dense clusters get
+        /// subdivided while empty regions stay as single nodes.
+        pub struct Quadtree {
+            bounds: Aabb,
+            max_depth: usize,
+            node_capacity: usize,
+            /// The last tree built, and the entries it was built from. Finding pairs and
+            /// every query after it share one tree until something moves.
+            built: RefCell<Option<BuiltTree>>,
+        }
+
+        struct BuiltTree {
+            entries: Vec<(usize, Aabb)>,
+            root: QuadNode,
+        }
+
+        impl Quadtree {
+            pub fn new(bounds: Aabb, max_depth: usize, node_capacity: usize) -> Self {
+                Self {
+                    bounds,
+                    max_depth,
+                    node_capacity,
+                    built: RefCell::new(None),
+                }
+            }
+
+            /// The tree for `entries`, built only if they've changed since last time
+            fn tree(&self, entries: &[(usize, Aabb)]) -> Ref<'_, QuadNode> {
+                let stale =
+                    !matches!(&*self.built.borrow(), Some(built) if built.entries == entries);
+                if stale {
+                    *self.built.borrow_mut() = Some(BuiltTree {
+                        entries: entries.to_vec(),
+                        root: self.build(entries),
+                    });
+                }
+                Ref::map(self.built.borrow(), |built| {
+                    &built.as_ref().expect("just built").root
+                })
+            }
+
+            fn build(&self, entries: &[(usize, Aabb)]) -> QuadNode {
+                // anything poking outside the tree's bounds just lives in the root
+                let mut root = QuadNode::new(self.bounds);
+                for item in 0..entries.len() {
+                    root.insert(item, entries, 0, self);
+                }
+                root
+            }
+        }
+
+        struct QuadNode {
+            bounds: Aabb,
+            /// Indices into the entry list of everything that doesn't fit in a single child
+            items: Vec<usize>,
+            children: Option<Box<[QuadNode; 4]>>,
+        }
+
+        impl QuadNode {
+            fn new(bounds: Aabb) -> Self {
+                Self {
+                    bounds,
+                    items: Vec::new(),
+                    children: None,
+                }
+            }
+
+            fn insert(
+                &mut self,
+                item: usize,
+                entries: &[(usize, Aabb)],
+                depth: usize,
+                tree: &Quadtree,
+            ) {
+                let aabb = &entries[item].1;
+                if let Some(children) = &mut self.children {
+                    if let Some(child) = children.iter_mut().find(|c| c.bounds.contains(aabb)) {
+                        child.insert(item, entries, depth + 1, tree);
+                        return;
+                    }
+                }
+                self.items.push(item);
+                if self.children.is_none()
+                    && self.items.len() > tree.node_capacity
+                    && depth < tree.max_depth
+                {
+                    self.split();
+                    for item in std::mem::take(&mut self.items) {
+                        self.insert(item, entries, depth, tree);
+                    }
+                }
+            }
+
+            fn split(&mut self) {
+                let Aabb { min, max } = self.bounds;
+                let mid = self.bounds.center();
+                let quadrant = |min, max| QuadNode::new(Aabb { min, max });
+                self.children = Some(Box::new([
+                    quadrant(min, mid),
+                    quadrant(vec2(mid.x, min.y), vec2(max.x, mid.y)),
+                    quadrant(vec2(min.x, mid.y), vec2(mid.x, max.y)),
+                    quadrant(mid, max),
+                ]));
+            }
+
+            fn collect_leaves(&self, out: &mut Vec<Aabb>) {
+                match &self.children {
+                    Some(children) => children.iter().for_each(|c| c.collect_leaves(out)),
+                    None => out.push(self.bounds),
+                }
+            }
+
+            fn query(&self, aabb: &Aabb, entries: &[(usize, Aabb)], out: &mut Vec<usize>) {
+                out.extend(self.items.iter().filter(|&&i| entries[i].1.overlaps(aabb)));
+                if let Some(children) = &self.children {
+                    for child in children.iter().filter(|c| c.bounds.overlaps(aabb)) {
+                        child.query(aabb, entries, out);
+                    }
+                }
+            }
+        }
+
+        impl BroadPhase for Quadtree {
+            fn candidate_pairs(&mut self, entries: &[(usize, Aabb)]) -> Vec<(usize, usize)> {
+                let root = self.tree(entries);
+                let mut pairs = Vec::new();
+                let mut hits = Vec::new();
+                for (k, (i, aabb)) in entries.iter().enumerate() {
+                    hits.clear();
+                    root.query(aabb, entries, &mut hits);
+                    pairs.extend(hits.iter().filter(|&&h| h > k).map(|&h| (*i, entries[h].0)));
+                }
+                pairs
+            }
+
+            fn query(&self, entries: &[(usize, Aabb)], region: &Aabb) -> Vec<usize> {
+                let mut hits = Vec::new();
+                self.tree(entries).query(region, entries, &mut hits);
+                hits.into_iter().map(|h| entries[h].0).collect()
+            }
+
+            fn cells(&self, entries: &[(usize, Aabb)]) -> Vec<Aabb> {
+                let mut leaves = Vec::new();
+                self.tree(entries).collect_leaves(&mut leaves);
+                leaves
+            }
+        }
+    }
+
+    pub mod constraints {
+        use macroquad::prelude as mq;
+        use serde::{Deserialize, Serialize};
+
+        use super::persist::ColorDef;
+        use super::real::{Real, Vec2};
+        use super::simulator::ObjectHandle;
+
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub enum ObjectAnchor {
+            /// A fixed point in the world
+            Point(Vec2),
+            /// The centre of an object's body
+            Object(ObjectHandle),
+        }
+
+        /// Keeps two anchors exactly `length` apart, like a weightless rod
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub struct DistanceConstraint {
+            pub a: ObjectAnchor,
+            pub b: ObjectAnchor,
+            pub length: Real,
+        }
+
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub enum HingeAnchor {
+            /// A fixed point in the world
+            World(Vec2),
+            /// A point on another body, in that body's own unrotated frame
+            Body(ObjectHandle, Vec2),
+        }
+
+        /// Pins a point on a body to an anchor while leaving it free to turn about that point,
+        /// like an axle or a seesaw's fulcrum
+        #[derive(Clone, Copy, Serialize, Deserialize)]
+        pub struct HingeJoint {
+            pub body: ObjectHandle,
+            /// Where the pin goes through the body, in its own unrotated frame
+            pub pivot: Vec2,
+            pub anchor: HingeAnchor,
+        }
+
+        /// A loop of bodies joined edge to edge by damped springs and inflated by the gas
+        /// trapped inside, so the whole thing squashes on impact and springs back
+        #[derive(Clone, Serialize, Deserialize)]
+        pub struct SoftRing {
+            /// In order around the loop
+            pub nodes: Vec<ObjectHandle>,
+            pub rest_length: Real,
+            pub stiffness: Real,
+            pub damping: Real,
+            /// Pressure times area, which stays fixed as the ring squashes (Boyle's law)
+            pub gas: Real,
+            #[serde(with = "ColorDef")]
+            pub color: mq::Color,
+        }
+
+        /// Hauls a body towards a target that can move every frame on a damped spring, for
+        /// dragging things about by hand. Being a force rather than a teleport, the body keeps
+        /// whatever speed it had when let go.
+        #[derive(Clone, Copy)]
+        pub struct MouseJoint {
+            pub body: ObjectHandle,
+            pub target: Vec2,
+            /// Spring strength per kilogram, so heavy and light bodies follow just as quickly
+            pub stiffness: Real,
+            /// Per kilogram too, taken off the body's velocity
+            pub damping: Real,
+        }
+    }
+    pub mod persist {
+        use std::fmt;
+        use std::io;
+        use std::marker::PhantomData;
+
+        use macroquad::prelude as mq;
+        use serde::de::{self, DeserializeOwned, DeserializeSeed, SeqAccess, Visitor};
+        use serde::{Deserialize, Deserializer, Serialize};
+
+        use super::simulator::ObjectHandle;
+
+        /// How macroquad's colours are saved, as they don't do it themselves. Put
+        /// `#[serde(with = "ColorDef")]` on a colour field to save it.
+        #[derive(Serialize, Deserialize)]
+        #[serde(remote = "mq::Color")]
+        pub struct ColorDef {
+            pub r: f32,
+            pub g: f32,
+            pub b: f32,
+            pub a: f32,
+        }
+
+        /// Reads back one kind of saved object
+        pub type Loader<T> = Box<
+            dyn Fn(&mut dyn erased_serde::Deserializer) -> Result<T, erased_serde::Error>
+                + Send
+                + Sync,
+        >;
+
+        /// The error for a save that's been mangled or wasn't one to begin with
+        pub fn invalid(message: impl Into<String>) -> io::Error {
+            io::Error::new(io::ErrorKind::InvalidData, message.into())
+        }
+
+        /// A save as RON: the format's version, then `world`, then each object as its
+        /// handle, the tag it's loaded by and whatever it saves of itself
+        pub fn write_save(
+            version: u32,
+            world: &impl Serialize,
+            objects: &[(ObjectHandle, &str, &dyn erased_serde::Serialize)],
+        ) -> io::Result<String> {
+            let config = ron::ser::PrettyConfig::new().depth_limit(2);
+            ron::ser::to_string_pretty(&(version, world, objects), config)
+                .map_err(|e| invalid(e.to_string()))
+        }
+
+        /// Read back what `write_save` wrote, handing each object to the loader for its tag
+        pub fn read_save<W: DeserializeOwned, T>(
+            text: &str,
+            version: u32,
+            loaders: &[(&'static str, Loader<T>)],
+        ) -> io::Result<(W, Vec<(ObjectHandle, T)>)> {
+            let mut input =
+                ron::Deserializer::from_str(text).map_err(|e| invalid(e.to_string()))?;
+            let save = SaveSeed {
+                version,
+                loaders,
+                world: PhantomData,
+            }
+            .deserialize(&mut input)
+            .map_err(|e| invalid(e.to_string()))?;
+            input.end().map_err(|e| invalid(e.to_string()))?;
+            Ok(save)
+        }
+
+        struct SaveSeed<'a, W, T> {
+            version: u32,
+            loaders: &'a [(&'static str, Loader<T>)],
+            world: PhantomData<W>,
+        }
+
+        impl<'de, W: DeserializeOwned, T> DeserializeSeed<'de> for SaveSeed<'_, W, T> {
+            type Value = (W, Vec<(ObjectHandle, T)>);
+
+            fn deserialize<D: Deserializer<'de>>(self, input: D) -> Result<Self::Value, D::Error> {
+                input.deserialize_tuple(3, self)
+            }
+        }
+
+        impl<'de, W: DeserializeOwned, T> Visitor<'de> for SaveSeed<'_, W, T> {
+            type Value = (W, Vec<(ObjectHandle, T)>);
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a save")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let version: u32 = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                if version != self.version {
+                    return Err(de::Error::custom(format!(
+                        "a save from version {version} of the format, not {}",
+                        self.version
+                    )));
+                }
+                let world = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let objects = seq
+                    .next_element_seed(ObjectsSeed(self.loaders))?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                Ok((world, objects))
+            }
+        }
+
+        struct ObjectsSeed<'a, T>(&'a [(&'static str, Loader<T>)]);
+
+        impl<'de, T> DeserializeSeed<'de> for ObjectsSeed<'_, T> {
+            type Value = Vec<(ObjectHandle, T)>;
+
+            fn deserialize<D: Deserializer<'de>>(self, input: D) -> Result<Self::Value, D::Error> {
+                input.deserialize_seq(self)
+            }
+        }
+
+        impl<'de, T> Visitor<'de> for ObjectsSeed<'_, T> {
+            type Value = Vec<(ObjectHandle, T)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a list of saved objects")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let mut objects = Vec::new();
+                while let Some(object) = seq.next_element_seed(ObjectSeed(self.0))? {
+                    objects.push(object);
+                }
+                Ok(objects)
+            }
+        }
+
+        /// One saved object, which the loader its tag names reads the rest of
+        struct ObjectSeed<'a, T>(&'a [(&'static str, Loader<T>)]);
+
+        impl<'de, T> DeserializeSeed<'de> for ObjectSeed<'_, T> {
+            type Value = (ObjectHandle, T);
+
+            fn deserialize<D: Deserializer<'de>>(self, input: D) -> Result<Self::Value, D::Error> {
+                input.deserialize_tuple(3, self)
+            }
+        }
+
+        impl<'de, T> Visitor<'de> for ObjectSeed<'_, T> {
+            type Value = (ObjectHandle, T);
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a handle, a tag and a saved object")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+                let handle = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let tag: String = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let Some((_, load)) = self.0.iter().find(|(t, _)| *t == tag) else {
+                    return Err(de::Error::custom(format!("no loader for {tag:?}")));
+                };
+                let object = seq
+                    .next_element_seed(LoadSeed(load))?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                Ok((handle, object))
+            }
+        }
+
+        struct LoadSeed<'a, T>(&'a Loader<T>);
+
+        impl<'de, T> DeserializeSeed<'de> for LoadSeed<'_, T> {
+            type Value = T;
+
+            fn deserialize<D: Deserializer<'de>>(self, input: D) -> Result<T, D::Error> {
+                (self.0)(&mut <dyn erased_serde::Deserializer>::erase(input))
+                    .map_err(de::Error::custom)
+            }
+        }
+    }
+
+    pub mod render {
+        use std::collections::VecDeque;
+        use std::f32::consts::FRAC_PI_6;
+
+        use macroquad::miniquad::{BlendFactor, BlendState, Equation};
+        use macroquad::prelude as mq;
+
+        use super::simulator::Resources;
+
+        /// Somewhere to draw to. Everything in the engine draws through one of these rather
+        /// than straight to the window, so it can run and be checked without one.
+        pub trait Renderer {
+            fn draw_line(
+                &mut self,
+                x1: f32,
+                y1: f32,
+                x2: f32,
+                y2: f32,
+                thickness: f32,
+                color: mq::Color,
+            );
+
+            fn draw_triangle(&mut self, a: mq::Vec2, b: mq::Vec2, c: mq::Vec2, color: mq::Color);
+
+            fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: mq::Color);
+
+            fn draw_circle_lines(
+                &mut self,
+                x: f32,
+                y: f32,
+                radius: f32,
+                thickness: f32,
+                color: mq::Color,
+            );
+
+            fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: mq::Color);
+
+            fn draw_rectangle_lines(
+                &mut self,
+                x: f32,
+                y: f32,
+                w: f32,
+                h: f32,
+                thickness: f32,
+                color: mq::Color,
+            );
+
+            fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: mq::Color);
+
+            /// How much room `text` would take up drawn at `font_size`
+            fn measure_text(&self, text: &str, font_size: f32) -> mq::TextDimensions;
+
+            /// Stretch `texture` over a `size` box centred on `center`, turned by `rotation`
+            /// radians about its centre and tinted by `color`
+            fn draw_texture(
+                &mut self,
+                texture: &mq::Texture2D,
+                center: mq::Vec2,
+                size: mq::Vec2,
+                rotation: f32,
+                color: mq::Color,
+            );
+
+            /// Draw only into `viewport` from here on, with screen points measured from its
+            /// top left, or over the whole window again for `None`
+            fn set_viewport(&mut self, viewport: Option<mq::Rect>);
+        }
+
+        pub const MIN_ZOOM: f32 = 0.1;
+        pub const MAX_ZOOM: f32 = 10.;
+
+        /// The view onto the world, which is measured in metres. A world point `p` shows
+        /// up on screen at `(p - offset) * zoom * pixels_per_meter`.
+        #[derive(Clone, Copy, Debug)]
+        pub struct Camera {
+            pub offset: mq::Vec2,
+            pub zoom: f32,
+            /// Screen pixels a metre takes up at a zoom of 1
+            pub pixels_per_meter: f32,
+            /// The part of the window the camera draws into, or all of it for `None`, so
+            /// several cameras can share the window side by side
+            pub viewport: Option<mq::Rect>,
+        }
+
+        impl Default for Camera {
+            fn default() -> Self {
+                Self {
+                    offset: mq::Vec2::ZERO,
+                    zoom: 1.,
+                    pixels_per_meter: 1.,
+                    viewport: None,
+                }
+            }
+        }
+
+        impl Camera {
+            pub fn with_pixels_per_meter(pixels_per_meter: f32) -> Self {
+                Self {
+                    pixels_per_meter,
+                    ..Default::default()
+                }
+            }
+
+            /// Screen pixels per metre, zoom and all
+            pub fn scale(&self) -> f32 {
+                self.zoom * self.pixels_per_meter
+            }
+
+            pub fn world_to_screen(&self, point: mq::Vec2) -> mq::Vec2 {
+                (point - self.offset) * self.scale()
+            }
+
+            pub fn screen_to_world(&self, point: mq::Vec2) -> mq::Vec2 {
+                point / self.scale() + self.offset
+            }
+
+            /// Zoom in by `factor`, keeping whatever is under `screen_point` where it is
+            pub fn zoom_about(&mut self, screen_point: mq::Vec2, factor: f32) {
+                let anchor = self.screen_to_world(screen_point);
+                self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+                self.offset = anchor - screen_point / self.scale();
+            }
+
+            /// Drag the world along by a distance on screen
+            pub fn pan(&mut self, screen_delta: mq::Vec2) {
+                self.offset -= screen_delta / self.scale();
+            }
+
+            /// Move so `point` is in the middle of the view
+            pub fn center_on(&mut self, point: mq::Vec2) {
+                self.offset = point - self.size() / 2. / self.scale();
+            }
+
+            /// How big the view is on screen, in pixels
+            pub fn size(&self) -> mq::Vec2 {
+                self.viewport
+                    .map_or(mq::vec2(mq::screen_width(), mq::screen_height()), |v| {
+                        v.size()
+                    })
+            }
+
+            /// Where screen points are measured from, in window pixels
+            pub fn origin(&self) -> mq::Vec2 {
+                self.viewport.map_or(mq::Vec2::ZERO, |v| v.point())
+            }
+        }
+
+        /// What objects draw with: a renderer seen through a camera, so they can draw in
+        /// world coordinates and leave panning and zooming to the context. A camera with a
+        /// viewport keeps the drawing inside it for as long as the context lives.
+        pub struct DrawContext<'a> {
+            renderer: &'a mut dyn Renderer,
+            camera: Camera,
+            tick_fraction: f32,
+            lead: f32,
+            resources: Option<&'a Resources>,
+        }
+
+        impl<'a> DrawContext<'a> {
+            /// `lead` is `tick_fraction` of a tick, in seconds
+            pub fn new(
+                renderer: &'a mut dyn Renderer,
+                camera: Camera,
+                tick_fraction: f32,
+                lead: f32,
+            ) -> Self {
+                if camera.viewport.is_some() {
+                    renderer.set_viewport(camera.viewport);
+                }
+                Self {
+                    renderer,
+                    camera,
+                    tick_fraction,
+                    lead,
+                    resources: None,
+                }
+            }
+
+            /// Let whatever draws see the simulation's resources, for settings about how to
+            /// draw it
+            pub fn with_resources(mut self, resources: &'a Resources) -> Self {
+                self.resources = Some(resources);
+                self
+            }
+
+            /// The resource of type `R`, if the context was given resources and one's there
+            pub fn resource<R: 'static>(&self) -> Option<&R> {
+                self.resources?.get()
+            }
+
+            pub fn camera(&self) -> &Camera {
+                &self.camera
+            }
+
+            /// How far real time has got into the tick after the last one run, from 0 to 1
+            pub fn tick_fraction(&self) -> f32 {
+                self.tick_fraction
+            }
+
+            /// How far real time has got into the tick after the last one run, in seconds, so
+            /// anything moving can be drawn at `pos + velocity * lead` and glide between ticks
+            pub fn lead(&self) -> f32 {
+                self.lead
+            }
+
+            /// Draw in screen coordinates instead, for anything that should stay put
+            pub fn screen(&mut self) -> &mut dyn Renderer {
+                &mut *self.renderer
+            }
+
+            fn to_screen(&self, x: f32, y: f32) -> mq::Vec2 {
+                self.camera.world_to_screen(mq::vec2(x, y))
+            }
+        }
+
+        impl Renderer for DrawContext<'_> {
+            fn draw_line(
+                &mut self,
+                x1: f32,
+                y1: f32,
+                x2: f32,
+                y2: f32,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                let (a, b) = (self.to_screen(x1, y1), self.to_screen(x2, y2));
+                let thickness = thickness * self.camera.scale();
+                self.renderer
+                    .draw_line(a.x, a.y, b.x, b.y, thickness, color);
+            }
+
+            fn draw_triangle(&mut self, a: mq::Vec2, b: mq::Vec2, c: mq::Vec2, color: mq::Color) {
+                let [a, b, c] = [a, b, c].map(|p| self.camera.world_to_screen(p));
+                self.renderer.draw_triangle(a, b, c, color);
+            }
+
+            fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: mq::Color) {
+                let center = self.to_screen(x, y);
+                let radius = radius * self.camera.scale();
+                self.renderer.draw_circle(center.x, center.y, radius, color);
+            }
+
+            fn draw_circle_lines(
+                &mut self,
+                x: f32,
+                y: f32,
+                radius: f32,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                let center = self.to_screen(x, y);
+                let scale = self.camera.scale();
+                self.renderer.draw_circle_lines(
+                    center.x,
+                    center.y,
+                    radius * scale,
+                    thickness * scale,
+                    color,
+                );
+            }
+
+            fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: mq::Color) {
+                let corner = self.to_screen(x, y);
+                let scale = self.camera.scale();
+                self.renderer
+                    .draw_rectangle(corner.x, corner.y, w * scale, h * scale, color);
+            }
+
+            fn draw_rectangle_lines(
+                &mut self,
+                x: f32,
+                y: f32,
+                w: f32,
+                h: f32,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                let corner = self.to_screen(x, y);
+                let scale = self.camera.scale();
+                self.renderer.draw_rectangle_lines(
+                    corner.x,
+                    corner.y,
+                    w * scale,
+                    h * scale,
+                    thickness * scale,
+                    color,
+                );
+            }
+
+            fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: mq::Color) {
+                let at = self.to_screen(x, y);
+                let font_size = font_size * self.camera.scale();
+                self.renderer.draw_text(text, at.x, at.y, font_size, color);
+            }
+
+            fn measure_text(&self, text: &str, font_size: f32) -> mq::TextDimensions {
+                let scale = self.camera.scale();
+                let size = self.renderer.measure_text(text, font_size * scale);
+                mq::TextDimensions {
+                    width: size.width / scale,
+                    height: size.height / scale,
+                    offset_y: size.offset_y / scale,
+                }
+            }
+
+            fn draw_texture(
+                &mut self,
+                texture: &mq::Texture2D,
+                center: mq::Vec2,
+                size: mq::Vec2,
+                rotation: f32,
+                color: mq::Color,
+            ) {
+                let center = self.camera.world_to_screen(center);
+                let size = size * self.camera.scale();
+                self.renderer
+                    .draw_texture(texture, center, size, rotation, color);
+            }
+
+            fn set_viewport(&mut self, viewport: Option<mq::Rect>) {
+                self.renderer.set_viewport(viewport);
+            }
+        }
+
+        impl Drop for DrawContext<'_> {
+            fn drop(&mut self) {
+                if self.camera.viewport.is_some() {
+                    self.renderer.set_viewport(None);
+                }
+            }
+        }
+
+        /// Where something has been lately, drawn as a line behind it that fades towards
+        /// the tail. The owner records a point every tick and draws the trail with itself.
+        #[derive(Clone)]
+        pub struct Trail {
+            /// `(time, position)`, oldest first
+            points: VecDeque<(f64, mq::Vec2)>,
+            /// How far back the trail reaches, in seconds
+            duration: f64,
+        }
+
+        impl Trail {
+            pub fn new(duration: f64) -> Self {
+                Self {
+                    points: VecDeque::new(),
+                    duration,
+                }
+            }
+
+            /// Note that the owner was at `pos` as of simulation time `time`
+            pub fn record(&mut self, time: f64, pos: mq::Vec2) {
+                self.points.push_back((time, pos));
+                while self
+                    .points
+                    .front()
+                    .is_some_and(|&(t, _)| t < time - self.duration)
+                {
+                    self.points.pop_front();
+                }
+            }
+
+            /// Draw the trail up to `head`, where the owner is drawn now, fading from
+            /// `color` in to nothing at the tail
+            pub fn on_draw(
+                &self,
+                renderer: &mut dyn Renderer,
+                head: mq::Vec2,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                let points: Vec<_> = self.points.iter().map(|&(_, p)| p).chain([head]).collect();
+                let segments = points.len().saturating_sub(1);
+                for (k, pair) in points.windows(2).enumerate() {
+                    let fade = (k + 1) as f32 / segments as f32;
+                    let color = mq::Color {
+                        a: color.a * fade,
+                        ..color
+                    };
+                    let (a, b) = (pair[0], pair[1]);
+                    renderer.draw_line(a.x, a.y, b.x, b.y, thickness * fade, color);
+                }
+            }
+        }
+
+        /// Colours spread along a line from 0 to 1, blended between
+        #[derive(Clone, Debug)]
+        pub struct Gradient {
+            /// `(position, colour)`, in order of position
+            stops: Vec<(f32, mq::Color)>,
+        }
+
+        impl Gradient {
+            /// Stops can come in any order. Anything before the first stop or after the last
+            /// gets that stop's colour.
+            pub fn new(mut stops: Vec<(f32, mq::Color)>) -> Self {
+                stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+                Self { stops }
+            }
+
+            /// Blue through cyan, green and yellow to red, like a heat map
+            pub fn heat() -> Self {
+                Self::new(vec![
+                    (0., mq::BLUE),
+                    (0.25, mq::SKYBLUE),
+                    (0.5, mq::GREEN),
+                    (0.75, mq::YELLOW),
+                    (1., mq::RED),
+                ])
+            }
+
+            pub fn at(&self, t: f32) -> mq::Color {
+                let after = self.stops.iter().position(|&(at, _)| at > t);
+                match after {
+                    None => self.stops.last().map_or(mq::WHITE, |&(_, c)| c),
+                    Some(0) => self.stops[0].1,
+                    Some(k) => {
+                        let ((t0, a), (t1, b)) = (self.stops[k - 1], self.stops[k]);
+                        let blend = (t - t0) / (t1 - t0);
+                        let mix = |x: f32, y: f32| x + (y - x) * blend;
+                        mq::Color::new(mix(a.r, b.r), mix(a.g, b.g), mix(a.b, b.b), mix(a.a, b.a))
+                    }
+                }
+            }
+        }
+
+        /// How an arrow's colour is picked
+        #[derive(Clone, Debug)]
+        pub enum ArrowColor {
+            Fixed(mq::Color),
+            /// From along `gradient`, reaching its far end once the vector shown is `max` long
+            ByMagnitude {
+                gradient: Gradient,
+                max: f32,
+            },
+        }
+
+        /// How an arrow showing a vector is drawn. Everything but the length is in screen
+        /// pixels, so arrows keep the same weight and head at any zoom.
+        #[derive(Clone, Debug)]
+        pub struct ArrowStyle {
+            pub thickness: f32,
+            /// How far back from the tip the head reaches, however long the arrow is
+            pub head_size: f32,
+            /// Pixels of shaft drawn then skipped, over and over, or a solid shaft if `None`
+            pub dash: Option<(f32, f32)>,
+            pub color: ArrowColor,
+            /// How long the arrow is per unit of the vector: metres when drawn in the world,
+            /// pixels when drawn on the screen
+            pub length_per_unit: f32,
+            /// Arrows are stretched or cut to come out between these, in pixels
+            pub min_length: f32,
+            pub max_length: f32,
+        }
+
+        impl Default for ArrowStyle {
+            fn default() -> Self {
+                Self {
+                    thickness: 2.,
+                    head_size: 8.,
+                    dash: None,
+                    color: ArrowColor::Fixed(mq::WHITE),
+                    length_per_unit: 1.,
+                    min_length: 0.,
+                    max_length: f32::INFINITY,
+                }
+            }
+        }
+
+        impl ArrowStyle {
+            pub fn color_for(&self, magnitude: f32) -> mq::Color {
+                match &self.color {
+                    ArrowColor::Fixed(color) => *color,
+                    ArrowColor::ByMagnitude { gradient, max } => gradient.at(magnitude / max),
+                }
+            }
+
+            /// Draw `vector` as an arrow from the world point `tail`
+            pub fn draw(&self, ctx: &mut DrawContext, tail: mq::Vec2, vector: mq::Vec2) {
+                let camera = *ctx.camera();
+                let from = camera.world_to_screen(tail);
+                let to = camera.world_to_screen(tail + vector * self.length_per_unit);
+                self.draw_between(ctx.screen(), from, to, vector.length());
+            }
+
+            /// Draw `vector` as an arrow from the screen point `tail`
+            pub fn draw_screen(
+                &self,
+                renderer: &mut dyn Renderer,
+                tail: mq::Vec2,
+                vector: mq::Vec2,
+            ) {
+                let to = tail + vector * self.length_per_unit;
+                self.draw_between(renderer, tail, to, vector.length());
+            }
+
+            fn draw_between(
+                &self,
+                renderer: &mut dyn Renderer,
+                from: mq::Vec2,
+                to: mq::Vec2,
+                magnitude: f32,
+            ) {
+                // with no length there's no way to point, so nothing to draw
+                let Some(direction) = (to - from).try_normalize() else {
+                    return;
+                };
+                let length = from.distance(to).clamp(self.min_length, self.max_length);
+                let tip = from + direction * length;
+                let color = self.color_for(magnitude);
+                let head = self.head_size.min(length);
+                let shaft = length - head;
+                match self.dash {
+                    None => {
+                        let end = from + direction * shaft;
+                        renderer.draw_line(from.x, from.y, end.x, end.y, self.thickness, color);
+                    }
+                    Some((on, off)) => {
+                        let mut at = 0.;
+                        while at < shaft {
+                            let (a, b) = (
+                                from + direction * at,
+                                from + direction * (at + on).min(shaft),
+                            );
+                            renderer.draw_line(a.x, a.y, b.x, b.y, self.thickness, color);
+                            at += on + off;
+                        }
+                    }
+                }
+                let back = tip - direction * head;
+                let side = direction.perp() * head * FRAC_PI_6.tan();
+                renderer.draw_triangle(tip, back + side, back - side, color);
+            }
+        }
+
+        /// A soft halo around whatever gives off light, drawn as a pass of its own: the light
+        /// sources are drawn small into a texture, blurred, and added over the finished frame
+        pub struct Glow {
+            /// Drawn into and blurred back and forth between, each the screen's size shrunk by
+            /// `downscale`
+            targets: [mq::RenderTarget; 2],
+            downscale: f32,
+            blur: mq::Material,
+            add: mq::Material,
+        }
+
+        impl Glow {
+            /// Fails if the shaders won't compile on this machine
+            pub fn new(downscale: f32) -> Result<Self, macroquad::Error> {
+                let blur = mq::load_material(
+                    mq::ShaderSource::Glsl {
+                        vertex: GLOW_VERTEX_SHADER,
+                        fragment: GLOW_BLUR_SHADER,
+                    },
+                    mq::MaterialParams {
+                        uniforms: vec![("step".to_string(), mq::UniformType::Float2)],
+                        ..Default::default()
+                    },
+                )?;
+                let additive = BlendState::new(Equation::Add, BlendFactor::One, BlendFactor::One);
+                let add = mq::load_material(
+                    mq::ShaderSource::Glsl {
+                        vertex: GLOW_VERTEX_SHADER,
+                        fragment: GLOW_ADD_SHADER,
+                    },
+                    mq::MaterialParams {
+                        pipeline_params: mq::PipelineParams {
+                            color_blend: Some(additive),
+                            ..Default::default()
+                        },
+                        uniforms: vec![("strength".to_string(), mq::UniformType::Float1)],
+                        ..Default::default()
+                    },
+                )?;
+                let downscale = downscale.max(1.);
+                let (width, height) = Self::target_size(downscale);
+                Ok(Self {
+                    targets: [(); 2].map(|_| Self::target(width, height)),
+                    downscale,
+                    blur,
+                    add,
+                })
+            }
+
+            fn target_size(downscale: f32) -> (u32, u32) {
+                let size = mq::vec2(mq::screen_width(), mq::screen_height()) / downscale;
+                (size.x.max(1.) as u32, size.y.max(1.) as u32)
+            }
+
+            fn target(width: u32, height: u32) -> mq::RenderTarget {
+                let target = mq::render_target(width, height);
+                target.texture.set_filter(mq::FilterMode::Linear);
+                target
+            }
+
+            /// Look at `target` as if it were the whole screen
+            fn camera_on(target: &mq::RenderTarget) -> mq::Camera2D {
+                let screen = mq::Rect::new(0., 0., mq::screen_width(), mq::screen_height());
+                mq::Camera2D {
+                    render_target: Some(target.clone()),
+                    ..mq::Camera2D::from_display_rect(screen)
+                }
+            }
+
+            /// Copy `from` over the whole of whatever's being drawn to. Render targets come out
+            /// upside down, so every copy turns them back over.
+            fn copy(from: &mq::RenderTarget) {
+                let params = mq::DrawTextureParams {
+                    dest_size: Some(mq::vec2(mq::screen_width(), mq::screen_height())),
+                    flip_y: true,
+                    ..Default::default()
+                };
+                mq::draw_texture_ex(&from.texture, 0., 0., mq::WHITE, params);
+            }
+
+            /// Run `draw` with everything it draws going into the glow instead of the screen,
+            /// in screen coordinates as usual
+            pub fn draw_sources(&mut self, draw: impl FnOnce()) {
+                let (width, height) = Self::target_size(self.downscale);
+                if self.targets[0].texture.size() != mq::vec2(width as f32, height as f32) {
+                    self.targets = [(); 2].map(|_| Self::target(width, height));
+                }
+                mq::set_camera(&Self::camera_on(&self.targets[0]));
+                mq::clear_background(mq::BLACK);
+                draw();
+                mq::set_default_camera();
+            }
+
+            /// Blur whatever was drawn into the glow and add it over the screen, `strength`
+            /// times as bright
+            pub fn composite(&self, strength: f32) {
+                let texel = mq::Vec2::ONE / self.targets[0].texture.size();
+                // across into the second target, then down back into the first
+                for (from, to, step) in [(0, 1, texel * mq::Vec2::X), (1, 0, texel * mq::Vec2::Y)] {
+                    mq::set_camera(&Self::camera_on(&self.targets[to]));
+                    mq::gl_use_material(&self.blur);
+                    self.blur.set_uniform("step", step);
+                    Self::copy(&self.targets[from]);
+                }
+                mq::set_default_camera();
+                mq::gl_use_material(&self.add);
+                self.add.set_uniform("strength", strength);
+                Self::copy(&self.targets[0]);
+                mq::gl_use_default_material();
+            }
+        }
+
+        const GLOW_VERTEX_SHADER: &str = r#"#version 100
+attribute vec3 position;
+attribute vec2 texcoord;
+attribute vec4 color0;
+
+varying lowp vec2 uv;
+varying lowp vec4 color;
+
+uniform mat4 Model;
+uniform mat4 Projection;
+
+void main() {
+    gl_Position = Projection * Model * vec4(position, 1);
+    color = color0 / 255.0;
+    uv = texcoord;
+}
+"#;
+
+        /// One direction of a Gaussian blur, five taps placed between texels so the linear
+        /// filtering does the work of nine
+        const GLOW_BLUR_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform vec2 step;
+
+void main() {
+    vec4 sum = texture2D(Texture, uv) * 0.227027;
+    sum += texture2D(Texture, uv + step * 1.384615) * 0.316216;
+    sum += texture2D(Texture, uv - step * 1.384615) * 0.316216;
+    sum += texture2D(Texture, uv + step * 3.230769) * 0.070270;
+    sum += texture2D(Texture, uv - step * 3.230769) * 0.070270;
+    gl_FragColor = sum * color;
+}
+"#;
+
+        const GLOW_ADD_SHADER: &str = r#"#version 100
+precision mediump float;
+
+varying vec2 uv;
+varying vec4 color;
+
+uniform sampler2D Texture;
+uniform float strength;
+
+void main() {
+    gl_FragColor = vec4(texture2D(Texture, uv).rgb * color.rgb * strength, 1.0);
+}
+"#;
+
+        /// Draws to the macroquad window
+        pub struct MacroquadRenderer;
+
+        impl Renderer for MacroquadRenderer {
+            fn draw_line(
+                &mut self,
+                x1: f32,
+                y1: f32,
+                x2: f32,
+                y2: f32,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                mq::draw_line(x1, y1, x2, y2, thickness, color);
+            }
+
+            fn draw_triangle(&mut self, a: mq::Vec2, b: mq::Vec2, c: mq::Vec2, color: mq::Color) {
+                mq::draw_triangle(a, b, c, color);
+            }
+
+            fn draw_circle(&mut self, x: f32, y: f32, radius: f32, color: mq::Color) {
+                mq::draw_circle(x, y, radius, color);
+            }
+
+            fn draw_circle_lines(
+                &mut self,
+                x: f32,
+                y: f32,
+                radius: f32,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                mq::draw_circle_lines(x, y, radius, thickness, color);
+            }
+
+            fn draw_rectangle(&mut self, x: f32, y: f32, w: f32, h: f32, color: mq::Color) {
+                mq::draw_rectangle(x, y, w, h, color);
+            }
+
+            fn draw_rectangle_lines(
+                &mut self,
+                x: f32,
+                y: f32,
+                w: f32,
+                h: f32,
+                thickness: f32,
+                color: mq::Color,
+            ) {
+                mq::draw_rectangle_lines(x, y, w, h, thickness, color);
+            }
+
+            fn draw_text(&mut self, text: &str, x: f32, y: f32, font_size: f32, color: mq::Color) {
+                mq::draw_text(text, x, y, font_size, color);
+            }
+
+            fn measure_text(&self, text: &str, font_size: f32) -> mq::TextDimensions {
+                mq::measure_text(text, None, font_size as u16, 1.)
+            }
+
+            fn draw_texture(
+                &mut self,
+                texture: &mq::Texture2D,
+                center: mq::Vec2,
+                size: mq::Vec2,
+                rotation: f32,
+                color: mq::Color,
+            ) {
+                let corner = center - size / 2.;
+                let params = mq::DrawTextureParams {
+                    dest_size: Some(size),
+                    rotation,
+                    ..Default::default()
+                };
+                mq::draw_texture_ex(texture, corner.x, corner.y, color, params);
+            }
+
+            fn set_viewport(&mut self, viewport: Option<mq::Rect>) {
+                let Some(rect) = viewport else {
+                    mq::set_default_camera();
+                    return;
+                };
+                // GL counts viewports up from the bottom of the window
+                let bottom = mq::screen_height() - rect.y - rect.h;
+                mq::set_camera(&mq::Camera2D {
+                    viewport: Some((rect.x as i32, bottom as i32, rect.w as i32, rect.h as i32)),
+                    ..mq::Camera2D::from_display_rect(mq::Rect::new(0., 0., rect.w, rect.h))
+                });
+            }
+        }
+
+        /// Draws nothing, for running the engine with no window
+        #[allow(dead_code)]
+        pub struct NullRenderer;
+
+        impl Renderer for NullRenderer {
+            fn draw_line(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: mq::Color) {}
+
+            fn draw_triangle(&mut self, _: mq::Vec2, _: mq::Vec2, _: mq::Vec2, _: mq::Color) {}
+
+            fn draw_circle(&mut self, _: f32, _: f32, _: f32, _: mq::Color) {}
+
+            fn draw_circle_lines(&mut self, _: f32, _: f32, _: f32, _: f32, _: mq::Color) {}
+
+            fn draw_rectangle(&mut self, _: f32, _: f32, _: f32, _: f32, _: mq::Color) {}
+
+            fn draw_rectangle_lines(
+                &mut self,
+                _: f32,
+                _: f32,
+                _: f32,
+                _: f32,
+                _: f32,
+                _: mq::Color,
+            ) {
+            }
+
+            fn draw_text(&mut self, _: &str, _: f32, _: f32, _: f32, _: mq::Color) {}
+
+            fn measure_text(&self, _: &str, _: f32) -> mq::TextDimensions {
+                mq::TextDimensions {
+                    width: 0.,
+                    height: 0.,
+                    offset_y: 0.,
+                }
+            }
+
+            fn draw_texture(
+                &mut self,
+                _: &mq::Texture2D,
+                _: mq::Vec2,
+                _: mq::Vec2,
+                _: f32,
+                _: mq::Color,
+            ) {
+            }
+
+            fn set_viewport(&mut self, _: Option<mq::Rect>) {}
+        }
+    }
+
+    pub mod record {
+        use std::io::{self, Write};
+        use std::process::{Command, Stdio};
+
+        use macroquad::prelude as mq;
+
+        /// What a finished recording gets written out as
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum RecordFormat {
+            Gif,
+            /// Handed to `ffmpeg` to encode, so it has to be installed
+            Mp4,
+        }
+
+        impl RecordFormat {
+            pub fn parse(name: &str) -> Option<Self> {
+                match name {
+                    "gif" => Some(RecordFormat::Gif),
+                    "mp4" => Some(RecordFormat::Mp4),
+                    _ => None,
+                }
+            }
+
+            pub fn extension(self) -> &'static str {
+                match self {
+                    RecordFormat::Gif => "gif",
+                    RecordFormat::Mp4 => "mp4",
+                }
+            }
+        }
+
+        /// One captured frame as packed RGB rows, top row first
+        #[derive(Clone)]
+        pub struct Frame {
+            pub width: u16,
+            pub height: u16,
+            pub rgb: Vec<u8>,
+        }
+
+        impl Frame {
+            /// Shrink a screen capture by `downscale` each way, averaging each block of pixels.
+            /// Screen captures come bottom row first, so this also turns it the right way up.
+            pub fn from_screen(image: &mq::Image, downscale: usize) -> Self {
+                let downscale = downscale.max(1);
+                let (w, h) = (image.width as usize, image.height as usize);
+                let (width, height) = (w / downscale, h / downscale);
+                let mut rgb = Vec::with_capacity(width * height * 3);
+                for y in 0..height {
+                    for x in 0..width {
+                        let mut sum = [0u32; 3];
+                        for dy in 0..downscale {
+                            let row = h - 1 - (y * downscale + dy);
+                            for dx in 0..downscale {
+                                let at = (row * w + x * downscale + dx) * 4;
+                                for (c, total) in sum.iter_mut().enumerate() {
+                                    *total += image.bytes[at + c] as u32;
+                                }
+                            }
+                        }
+                        let count = (downscale * downscale) as u32;
+                        rgb.extend(sum.map(|total| (total / count) as u8));
+                    }
+                }
+                Self {
+                    width: width as u16,
+                    height: height as u16,
+                    rgb,
+                }
+            }
+        }
+
+        /// Collects screen captures at a fixed rate of simulated time, so a recording plays
+        /// back at the simulation's pace however unevenly the frames were drawn
+        pub struct Recorder {
+            frames: Vec<Frame>,
+            /// Ticks between captured frames
+            ticks_per_frame: usize,
+            /// The tick the next capture falls due at
+            next_tick: usize,
+            downscale: usize,
+            max_frames: usize,
+        }
+
+        impl Recorder {
+            pub fn new(ticks_per_frame: usize, downscale: usize, max_frames: usize) -> Self {
+                Self {
+                    frames: Vec::new(),
+                    ticks_per_frame: ticks_per_frame.max(1),
+                    next_tick: 0,
+                    downscale,
+                    max_frames,
+                }
+            }
+
+            /// Capture the screen as drawn so far once for every frame that has fallen due by
+            /// `tick`. If the simulation got ahead of the drawing, the same picture fills in
+            /// for each frame it missed; if it went back, as in a rewind, capturing picks up
+            /// from there. Returns whether there's room for more.
+            pub fn capture(&mut self, tick: usize) -> bool {
+                if self.frames.is_empty() || tick + self.ticks_per_frame < self.next_tick {
+                    self.next_tick = tick;
+                }
+                if tick >= self.next_tick && !self.is_full() {
+                    let frame = Frame::from_screen(&mq::get_screen_data(), self.downscale);
+                    while tick >= self.next_tick && !self.is_full() {
+                        self.frames.push(frame.clone());
+                        self.next_tick += self.ticks_per_frame;
+                    }
+                }
+                !self.is_full()
+            }
+
+            pub fn is_full(&self) -> bool {
+                self.frames.len() >= self.max_frames
+            }
+
+            pub fn frame_count(&self) -> usize {
+                self.frames.len()
+            }
+
+            /// Everything captured, oldest first
+            pub fn into_frames(self) -> Vec<Frame> {
+                self.frames
+            }
+        }
+
+        /// Write `frames` out to `path` as a video playing at `fps`
+        pub fn encode(
+            frames: &[Frame],
+            fps: u32,
+            format: RecordFormat,
+            path: &str,
+        ) -> io::Result<()> {
+            match format {
+                RecordFormat::Gif => {
+                    let mut out = io::BufWriter::new(std::fs::File::create(path)?);
+                    write_gif(&mut out, frames, fps)?;
+                    out.flush()
+                }
+                RecordFormat::Mp4 => write_mp4(path, frames, fps),
+            }
+        }
+
+        /// Levels of red, green and blue in the palette, which has a colour for every
+        /// combination
+        const PALETTE_LEVELS: [usize; 3] = [6, 7, 6];
+
+        /// Which palette entry is closest to an RGB colour
+        fn palette_index(rgb: &[u8]) -> u8 {
+            let [r, g, b] = [0, 1, 2].map(|c| {
+                let top = PALETTE_LEVELS[c] - 1;
+                (rgb[c] as usize * top + 127) / 255
+            });
+            ((r * PALETTE_LEVELS[1] + g) * PALETTE_LEVELS[2] + b) as u8
+        }
+
+        /// The fixed 256-colour table every frame is drawn from, padded out with black
+        fn palette() -> Vec<u8> {
+            let [nr, ng, nb] = PALETTE_LEVELS;
+            let level = |k: usize, n: usize| (k * 255 / (n - 1)) as u8;
+            let mut table = Vec::with_capacity(256 * 3);
+            for r in 0..nr {
+                for g in 0..ng {
+                    for b in 0..nb {
+                        table.extend([level(r, nr), level(g, ng), level(b, nb)]);
+                    }
+                }
+            }
+            table.resize(256 * 3, 0);
+            table
+        }
+
+        /// An animated GIF that loops forever. GIF frame delays are whole hundredths of a
+        /// second, so they're rounded to stay in step with `fps` over the whole run.
+        pub fn write_gif(out: &mut impl Write, frames: &[Frame], fps: u32) -> io::Result<()> {
+            let (width, height) = frames.first().map_or((0, 0), |f| (f.width, f.height));
+            out.write_all(b"GIF89a")?;
+            out.write_all(&width.to_le_bytes())?;
+            out.write_all(&height.to_le_bytes())?;
+            // a global table of 256 colours, with no background or aspect ratio to speak of
+            out.write_all(&[0xf7, 0, 0])?;
+            out.write_all(&palette())?;
+            // the Netscape extension, asking for endless looping
+            out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+            let fps = fps.max(1) as u64;
+            for (k, frame) in frames.iter().enumerate() {
+                let centis = |k: u64| (k * 100 + fps / 2) / fps;
+                let delay = (centis(k as u64 + 1) - centis(k as u64)) as u16;
+                out.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+                out.write_all(&delay.to_le_bytes())?;
+                out.write_all(&[0, 0])?;
+                out.write_all(&[0x2c, 0, 0, 0, 0])?;
+                out.write_all(&frame.width.to_le_bytes())?;
+                out.write_all(&frame.height.to_le_bytes())?;
+                out.write_all(&[0])?;
+                let indices: Vec<u8> = frame.rgb.chunks_exact(3).map(palette_index).collect();
+                out.write_all(&[8])?;
+                for block in lzw_compress(&indices).chunks(255) {
+                    out.write_all(&[block.len() as u8])?;
+                    out.write_all(block)?;
+                }
+                out.write_all(&[0])?;
+            }
+            out.write_all(&[0x3b])
+        }
+
+        /// A still picture as a PNG, from RGBA rows top row first. The pixels go in
+        /// uncompressed, in stored deflate blocks, which any reader takes and spares
+        /// carrying a compressor around.
+        pub fn write_png(
+            out: &mut impl Write,
+            width: u32,
+            height: u32,
+            rgba: &[u8],
+        ) -> io::Result<()> {
+            let row_len = width as usize * 4;
+            let mut filtered = Vec::with_capacity((row_len + 1) * height as usize);
+            for row in rgba.chunks_exact(row_len.max(1)).take(height as usize) {
+                // each row starts by saying which filter it went through, here none at all
+                filtered.push(0);
+                filtered.extend_from_slice(row);
+            }
+            // zlib's header, for deflate with a 32K window and no dictionary
+            let mut zlib = vec![0x78, 0x01];
+            let blocks: Vec<_> = filtered.chunks(u16::MAX as usize).collect();
+            for (k, block) in blocks.iter().enumerate() {
+                let last = k + 1 == blocks.len();
+                let len = block.len() as u16;
+                zlib.push(last as u8);
+                zlib.extend(len.to_le_bytes());
+                zlib.extend((!len).to_le_bytes());
+                zlib.extend_from_slice(block);
+            }
+            if blocks.is_empty() {
+                zlib.extend([1, 0, 0, 0xff, 0xff]);
+            }
+            zlib.extend(adler32(&filtered).to_be_bytes());
+            let mut header = Vec::with_capacity(13);
+            header.extend(width.to_be_bytes());
+            header.extend(height.to_be_bytes());
+            // 8 bits a channel of RGBA, deflated, filtered per row and not interlaced
+            header.extend([8, 6, 0, 0, 0]);
+            out.write_all(b"\x89PNG\r\n\x1a\n")?;
+            write_png_chunk(out, b"IHDR", &header)?;
+            write_png_chunk(out, b"IDAT", &zlib)?;
+            write_png_chunk(out, b"IEND", &[])
+        }
+
+        fn write_png_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+            out.write_all(&(data.len() as u32).to_be_bytes())?;
+            out.write_all(kind)?;
+            out.write_all(data)?;
+            let crc = crc32(kind.iter().chain(data));
+            out.write_all(&crc.to_be_bytes())
+        }
+
+        /// The CRC every PNG chunk ends with
+        fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+            let table: Vec<u32> = (0..256)
+                .map(|n| {
+                    (0..8).fold(n, |c, _| {
+                        if c & 1 == 1 {
+                            0xedb8_8320 ^ (c >> 1)
+                        } else {
+                            c >> 1
+                        }
+                    })
+                })
+                .collect();
+            let crc = bytes.into_iter().fold(!0u32, |crc, &byte| {
+                table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+            });
+            !crc
+        }
+
+        /// The checksum zlib ends with
+        fn adler32(bytes: &[u8]) -> u32 {
+            const MOD: u32 = 65521;
+            let (mut a, mut b) = (1u32, 0u32);
+            // sums are only reduced every few thousand bytes, well before they could overflow
+            for run in bytes.chunks(5552) {
+                for &byte in run {
+                    a += byte as u32;
+                    b += a;
+                }
+                (a, b) = (a % MOD, b % MOD);
+            }
+            (b << 16) | a
+        }
+
+        /// GIF's variable-width LZW over 8-bit palette indices, codes packed least
+        /// significant bit first
+        fn lzw_compress(indices: &[u8]) -> Vec<u8> {
+            const CLEAR: u16 = 256;
+            const END: u16 = 257;
+            const MAX_CODES: u16 = 4096;
+            let mut out = Vec::new();
+            let (mut bits, mut bit_count) = (0u32, 0u32);
+            let mut emit = |code: u16, width: u32, out: &mut Vec<u8>| {
+                bits |= (code as u32) << bit_count;
+                bit_count += width;
+                while bit_count >= 8 {
+                    out.push(bits as u8);
+                    bits >>= 8;
+                    bit_count -= 8;
+                }
+            };
+            // codes for each (prefix code, next index) seen so far, with 0 for none yet, as
+            // no real entry is ever numbered that low
+            let mut table = vec![0u16; MAX_CODES as usize * 256];
+            let (mut next, mut width) = (END + 1, 9);
+            emit(CLEAR, width, &mut out);
+            let Some((&first, rest)) = indices.split_first() else {
+                emit(END, width, &mut out);
+                return finish_bits(out, bits, bit_count);
+            };
+            let mut prefix = first as u16;
+            for &index in rest {
+                let slot = prefix as usize * 256 + index as usize;
+                if table[slot] != 0 {
+                    prefix = table[slot];
+                    continue;
+                }
+                emit(prefix, width, &mut out);
+                if next == MAX_CODES {
+                    emit(CLEAR, width, &mut out);
+                    table.fill(0);
+                    (next, width) = (END + 1, 9);
+                } else {
+                    if next >= 1 << width {
+                        width += 1;
+                    }
+                    table[slot] = next;
+                    next += 1;
+                }
+                prefix = index as u16;
+            }
+            emit(prefix, width, &mut out);
+            emit(END, width, &mut out);
+            finish_bits(out, bits, bit_count)
+        }
+
+        fn finish_bits(mut out: Vec<u8>, bits: u32, bit_count: u32) -> Vec<u8> {
+            if bit_count > 0 {
+                out.push(bits as u8);
+            }
+            out
+        }
+
+        /// Pipe the frames through `ffmpeg` into an H.264 MP4
+        fn write_mp4(path: &str, frames: &[Frame], fps: u32) -> io::Result<()> {
+            let Some(first) = frames.first() else {
+                return Ok(());
+            };
+            let size = format!("{}x{}", first.width, first.height);
+            let mut ffmpeg = Command::new("ffmpeg")
+                .args([
+                    "-y",
+                    "-loglevel",
+                    "error",
+                    "-f",
+                    "rawvideo",
+                    "-pix_fmt",
+                    "rgb24",
+                ])
+                .args(["-s", &size, "-r", &fps.to_string(), "-i", "-"])
+                // most players want even dimensions for yuv420p
+                .args([
+                    "-vf",
+                    "pad=ceil(iw/2)*2:ceil(ih/2)*2",
+                    "-pix_fmt",
+                    "yuv420p",
+                    path,
+                ])
+                .stdin(Stdio::piped())
+                .spawn()?;
+            if let Some(stdin) = &mut ffmpeg.stdin {
+                for frame in frames {
+                    stdin.write_all(&frame.rgb)?;
+                }
+            }
+            // closes the pipe, so ffmpeg knows that's everything
+            drop(ffmpeg.stdin.take());
+            let status = ffmpeg.wait()?;
+            if !status.success() {
+                return Err(io::Error::other(format!("ffmpeg failed ({status})")));
+            }
+            Ok(())
+        }
+    }
+
+    pub mod gamepad {
+        use macroquad::prelude as mq;
+
+        const MAX_AXES: usize = 8;
+        const MAX_BUTTONS: usize = 16;
+        /// How far a stick has to be pushed before it counts, as they rarely sit dead centre
+        const DEAD_ZONE: f32 = 0.2;
+
+        // Where the Linux driver puts things on the usual Xbox-style layout
+        pub const LEFT_X: usize = 0;
+        pub const LEFT_Y: usize = 1;
+        pub const RIGHT_X: usize = 3;
+        pub const RIGHT_Y: usize = 4;
+        pub const RIGHT_TRIGGER: usize = 5;
+        pub const A: usize = 0;
+        pub const X: usize = 2;
+        pub const LEFT_BUMPER: usize = 4;
+        pub const RIGHT_BUMPER: usize = 5;
+        pub const START: usize = 7;
+
+        /// Something a controller reports
+        #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+        pub enum Change {
+            /// An axis has moved to somewhere from -1 to 1
+            Axis(usize, f32),
+            Button(usize, bool),
+        }
+
+        /// Wherever a platform's controller events come from, so a [`Gamepad`] reads the same
+        /// everywhere and nothing using one needs to know the platform
+        pub trait GamepadSource {
+            /// Whether there's a controller there, which stops being so once it's unplugged
+            fn is_connected(&self) -> bool;
+
+            /// The next thing the controller's reported since last asked, if there is one
+            fn next_change(&mut self) -> Option<Change>;
+        }
+
+        /// Stands in where there's no controller to be had, here or on this platform
+        pub struct NoGamepad;
+
+        impl GamepadSource for NoGamepad {
+            fn is_connected(&self) -> bool {
+                false
+            }
+
+            fn next_change(&mut self) -> Option<Change> {
+                None
+            }
+        }
+
+        /// The Linux joystick interface (`/dev/input/js*`), read on a thread of its own, as
+        /// macroquad doesn't read controllers
+        #[cfg(target_os = "linux")]
+        pub mod joystick {
+            use std::fs::File;
+            use std::io::Read;
+            use std::sync::mpsc::{self, Receiver, TryRecvError};
+
+            use super::{Change, GamepadSource};
+
+            // event types. The state on opening comes as both type and `INIT`.
+            const JS_EVENT_BUTTON: u8 = 0x01;
+            const JS_EVENT_AXIS: u8 = 0x02;
+            const JS_EVENT_INIT: u8 = 0x80;
+
+            pub struct Joystick {
+                changes: Receiver<Change>,
+                connected: bool,
+            }
+
+            impl Joystick {
+                /// The first controller plugged in, if there is one
+                pub fn open() -> Option<Self> {
+                    let mut device =
+                        (0..4).find_map(|k| File::open(format!("/dev/input/js{k}")).ok())?;
+                    let (send, changes) = mpsc::channel();
+                    std::thread::spawn(move || {
+                        // `struct js_event`: a timestamp, then the value, type and number
+                        let mut event = [0; 8];
+                        while device.read_exact(&mut event).is_ok() {
+                            let value = i16::from_ne_bytes([event[4], event[5]]);
+                            let number = event[7] as usize;
+                            let change = match event[6] & !JS_EVENT_INIT {
+                                JS_EVENT_AXIS => {
+                                    Change::Axis(number, value as f32 / i16::MAX as f32)
+                                }
+                                JS_EVENT_BUTTON => Change::Button(number, value != 0),
+                                _ => continue,
+                            };
+                            if send.send(change).is_err() {
+                                return;
+                            }
+                        }
+                    });
+                    Some(Self {
+                        changes,
+                        connected: true,
+                    })
+                }
+            }
+
+            impl GamepadSource for Joystick {
+                fn is_connected(&self) -> bool {
+                    self.connected
+                }
+
+                fn next_change(&mut self) -> Option<Change> {
+                    match self.changes.try_recv() {
+                        Ok(change) => Some(change),
+                        Err(TryRecvError::Empty) => None,
+                        Err(TryRecvError::Disconnected) => {
+                            self.connected = false;
+                            None
+                        }
+                    }
+                }
+            }
+        }
+
+        /// A game controller, with its sticks, triggers and buttons as of the latest update
+        pub struct Gamepad {
+            source: Box<dyn GamepadSource>,
+            /// From -1 to 1
+            axes: [f32; MAX_AXES],
+            /// Whether the driver has said where each axis is yet
+            reported: [bool; MAX_AXES],
+            down: [bool; MAX_BUTTONS],
+            /// As of the frame before, for telling presses from holds
+            was_down: [bool; MAX_BUTTONS],
+        }
+
+        impl Gamepad {
+            pub fn new(source: Box<dyn GamepadSource>) -> Self {
+                Self {
+                    source,
+                    axes: [0.; MAX_AXES],
+                    reported: [false; MAX_AXES],
+                    down: [false; MAX_BUTTONS],
+                    was_down: [false; MAX_BUTTONS],
+                }
+            }
+
+            /// The first controller plugged in, read however this platform reads them, or
+            /// one that never connects if there isn't one
+            pub fn open() -> Self {
+                #[cfg(target_os = "linux")]
+                if let Some(joystick) = joystick::Joystick::open() {
+                    return Self::new(Box::new(joystick));
+                }
+                Self::new(Box::new(NoGamepad))
+            }
+
+            /// Catch up on everything that's moved since last time. Call once a frame,
+            /// before reading anything.
+            pub fn update(&mut self) {
+                self.was_down = self.down;
+                while let Some(change) = self.source.next_change() {
+                    match change {
+                        Change::Axis(axis, value) => {
+                            if let Some(slot) = self.axes.get_mut(axis) {
+                                *slot = value.clamp(-1., 1.);
+                                self.reported[axis] = true;
+                            }
+                        }
+                        Change::Button(button, down) => {
+                            if let Some(slot) = self.down.get_mut(button) {
+                                *slot = down;
+                            }
+                        }
+                    }
+                }
+                // unplugged, so nothing's held any more
+                if !self.source.is_connected() {
+                    self.axes = [0.; MAX_AXES];
+                    self.reported = [false; MAX_AXES];
+                    self.down = [false; MAX_BUTTONS];
+                }
+            }
+
+            pub fn is_connected(&self) -> bool {
+                self.source.is_connected()
+            }
+
+            pub fn axis(&self, axis: usize) -> f32 {
+                self.axes.get(axis).copied().unwrap_or(0.)
+            }
+
+            /// A stick's two axes together, as nothing at all until it's pushed past the
+            /// dead zone
+            pub fn stick(&self, x: usize, y: usize) -> mq::Vec2 {
+                let stick = mq::vec2(self.axis(x), self.axis(y));
+                if stick.length() < DEAD_ZONE {
+                    mq::Vec2::ZERO
+                } else {
+                    stick.clamp_length_max(1.)
+                }
+            }
+
+            /// How far a trigger's pulled in, from 0 to 1. Their axes rest at -1, but read 0
+            /// until the driver first reports them, so until then they count as let go.
+            pub fn trigger(&self, axis: usize) -> f32 {
+                if !self.reported.get(axis).copied().unwrap_or(false) {
+                    return 0.;
+                }
+                (self.axis(axis) + 1.) / 2.
+            }
+
+            pub fn is_button_down(&self, button: usize) -> bool {
+                self.down.get(button).copied().unwrap_or(false)
+            }
+
+            /// Whether `button` went down since the last update
+            pub fn is_button_pressed(&self, button: usize) -> bool {
+                self.is_button_down(button) && !self.was_down[button]
+            }
+        }
+    }
+
+    pub mod notify {
+        use std::collections::VecDeque;
+        use std::sync::{Arc, Mutex};
+
+        use macroquad::prelude as mq;
+
+        use super::render::Renderer;
+
+        /// How long a toast stays up, the last of it fading out
+        const TOAST_SECONDS: f64 = 3.;
+        const FADE_SECONDS: f64 = 0.5;
+        /// Most toasts up at once. Any more wait their turn.
+        const MAX_SHOWN: usize = 4;
+        const FONT_SIZE: f32 = 20.;
+        const PADDING: f32 = 6.;
+
+        /// A short message for the player
+        struct Toast {
+            text: String,
+            /// When it first went up, which isn't known until it's drawn, since it might
+            /// have been posted from another thread
+            shown_at: Option<f64>,
+        }
+
+        /// Messages that show along the bottom of the screen for a few seconds each and then
+        /// fade away. Clones share one queue, so a handle can go into a simulation's
+        /// resources for its objects and systems, or off to another thread.
+        #[derive(Clone, Default)]
+        pub struct Toasts {
+            queue: Arc<Mutex<VecDeque<Toast>>>,
+        }
+
+        impl Toasts {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Put a message up. The same message again while it's still waiting or showing
+            /// just keeps it up longer, rather than stacking copies.
+            pub fn push(&self, text: impl Into<String>) {
+                let text = text.into();
+                let mut queue = self.queue.lock().unwrap();
+                match queue.iter_mut().find(|toast| toast.text == text) {
+                    Some(toast) => toast.shown_at = None,
+                    None => queue.push_back(Toast {
+                        text,
+                        shown_at: None,
+                    }),
+                }
+            }
+
+            /// Clear out the ones that have had their time, then draw the rest stacked up
+            /// from `bottom_center`, in screen coordinates, oldest on top. `time` is the real
+            /// time now.
+            pub fn draw(&self, renderer: &mut dyn Renderer, time: f64, bottom_center: mq::Vec2) {
+                let mut queue = self.queue.lock().unwrap();
+                queue.retain(|toast| toast.shown_at.is_none_or(|at| time - at < TOAST_SECONDS));
+                let shown = queue.len().min(MAX_SHOWN);
+                let line_height = FONT_SIZE + 2. * PADDING + 4.;
+                for (i, toast) in queue.iter_mut().take(MAX_SHOWN).enumerate() {
+                    let age = time - *toast.shown_at.get_or_insert(time);
+                    let alpha = ((TOAST_SECONDS - age) / FADE_SECONDS).clamp(0., 1.) as f32;
+                    let size = renderer.measure_text(&toast.text, FONT_SIZE);
+                    let (width, height) = (size.width + 2. * PADDING, FONT_SIZE + 2. * PADDING);
+                    let x = bottom_center.x - width / 2.;
+                    let y = bottom_center.y - line_height * (shown - i) as f32;
+                    let backdrop = mq::Color::new(0.1, 0.1, 0.1, 0.8 * alpha);
+                    renderer.draw_rectangle(x, y, width, height, backdrop);
+                    let edge = mq::Color::new(0.5, 0.5, 0.5, alpha);
+                    renderer.draw_rectangle_lines(x, y, width, height, 1., edge);
+                    let text_color = mq::Color::new(1., 1., 1., alpha);
+                    let baseline = y + PADDING + size.offset_y;
+                    renderer.draw_text(&toast.text, x + PADDING, baseline, FONT_SIZE, text_color);
+                }
+            }
+        }
+    }
+
+    pub mod scene {
+        use std::ops::{Index, IndexMut};
+
+        use super::render::{Camera, Renderer};
+        use super::simulator::Simulation;
+
+        /// Which layer of a scene stack
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub struct LayerId(usize);
+
+        /// Independent simulations drawn one over another, bottom layer first. Each ticks at
+        /// its own rate, and nothing in one layer can touch anything in another.
+        #[derive(Default)]
+        pub struct SceneStack {
+            layers: Vec<Simulation>,
+        }
+
+        impl SceneStack {
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Swap a layer's simulation for another, say a fresh one to restart it, handing
+            /// back the one it had
+            pub fn replace_layer(&mut self, layer: LayerId, simulation: Simulation) -> Simulation {
+                std::mem::replace(&mut self.layers[layer.0], simulation)
+            }
+
+            /// Add a layer on top of the others
+            pub fn push_layer(&mut self, simulation: Simulation) -> LayerId {
+                self.layers.push(simulation);
+                LayerId(self.layers.len() - 1)
+            }
+
+            /// Every layer, bottom first
+            pub fn iter_mut(&mut self) -> impl Iterator<Item = (LayerId, &mut Simulation)> {
+                self.layers
+                    .iter_mut()
+                    .enumerate()
+                    .map(|(i, layer)| (LayerId(i), layer))
+            }
+
+            pub fn do_handle_expiry(&mut self) {
+                self.layers.iter_mut().for_each(|l| l.do_handle_expiry());
+            }
+
+            pub fn do_draw(&self, renderer: &mut dyn Renderer, camera: Camera) {
+                self.layers.iter().for_each(|l| l.do_draw(renderer, camera));
+            }
+        }
+
+        impl Index<LayerId> for SceneStack {
+            type Output = Simulation;
+
+            fn index(&self, layer: LayerId) -> &Simulation {
+                &self.layers[layer.0]
+            }
+        }
+
+        impl IndexMut<LayerId> for SceneStack {
+            fn index_mut(&mut self, layer: LayerId) -> &mut Simulation {
+                &mut self.layers[layer.0]
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Ball {
+    body: RigidBody,
+    #[serde(with = "ColorDef")]
+    color: mq::Color,
+    expiry: ExpiryPolicy,
+    #[serde(skip, default = "Ball::new_trail")]
+    trail: Trail,
+    /// A picture to draw instead of a flat circle, turning as the ball spins. It's shared
+    /// with every other ball showing the same one, and isn't saved.
+    #[serde(skip)]
+    sprite: Option<mq::Texture2D>,
+    /// How long it's been out, in seconds. Not saved either, so loaded balls start from
+    /// nothing.
+    #[serde(skip)]
+    age: f64,
+}
+
+impl Tick for Ball {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.expiry.on_tick(Some(&self.body), ctx.dt);
+        self.trail.record(ctx.time, to_mq(self.body.pos));
+        self.age += ctx.dt;
+    }
+}
+
+fn color_with_alpha(color: mq::Color, a: f32) -> mq::Color {
+    mq::Color {
+        r: color.r,
+        g: color.g,
+        b: color.b,
+        a,
+    }
+}
+
+/// How far a body has moved `seconds` into the tick under way, so it's drawn where it is by now
+fn lead(body: &RigidBody, seconds: f32) -> Vec2 {
+    body.velocity * seconds as Real
+}
+
+/// Smear a fast round body out behind where it's drawn with fainter copies of itself,
+/// spread over the ground it covers in a slow frame, so it doesn't seem to skip about
+fn draw_motion_blur(
+    renderer: &mut dyn Renderer,
+    pos: mq::Vec2,
+    body: &RigidBody,
+    color: mq::Color,
+) {
+    let speed = to_f32(body.velocity.length());
+    if speed < MOTION_BLUR_SPEED {
+        return;
+    }
+    // fades in from the threshold rather than popping on
+    let strength = ((speed - MOTION_BLUR_SPEED) / MOTION_BLUR_SPEED).min(1.);
+    let streak = to_mq(body.velocity) * MOTION_BLUR_SECONDS;
+    for k in (1..=MOTION_BLUR_GHOSTS).rev() {
+        let behind = k as f32 / (MOTION_BLUR_GHOSTS + 1) as f32;
+        let ghost = pos - streak * behind;
+        let alpha = color.a * strength * (1. - behind) * 0.5;
+        renderer.draw_circle(
+            ghost.x,
+            ghost.y,
+            to_f32(body.radius),
+            color_with_alpha(color, alpha),
+        );
+    }
+}
+
+impl Ball {
+    fn get_alpha(&self) -> f32 {
+        // fades out as its time runs down
+        (self.expiry.time_left() / BALL_EXPIRY_TIME).min(1.) as f32
+    }
+
+    /// The ball's own colour, or one for its speed if balls are being coloured that way
+    fn draw_color(&self, ctx: &DrawContext) -> mq::Color {
+        match ctx.resource::<SpeedColoring>() {
+            Some(coloring) if coloring.on => {
+                coloring.color_for(to_f32(self.body.velocity.length()))
+            }
+            _ => self.color,
+        }
+    }
+}
+impl Draw for Ball {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let alpha = self.get_alpha();
+        let pos = to_mq(self.body.pos + lead(&self.body, ctx.lead()));
+        let (radius, angle) = (to_f32(self.body.radius), to_f32(self.body.angle));
+        let color = self.draw_color(ctx);
+        self.trail.on_draw(
+            ctx,
+            pos,
+            radius,
+            color_with_alpha(color, alpha * TRAIL_ALPHA),
+        );
+        draw_motion_blur(ctx, pos, &self.body, color_with_alpha(color, alpha));
+        if let Some(sprite) = &self.sprite {
+            let size = mq::Vec2::splat(2. * radius);
+            let tint = color_with_alpha(mq::WHITE, alpha);
+            ctx.draw_texture(sprite, pos, size, angle, tint);
+        } else {
+            ctx.draw_circle(pos.x, pos.y, radius, color_with_alpha(color, alpha));
+            // spin marker from the centre out to the rim
+            let rim = pos + mq::Vec2::from_angle(angle) * radius;
+            ctx.draw_line(
+                pos.x,
+                pos.y,
+                rim.x,
+                rim.y,
+                0.05,
+                color_with_alpha(mq::BLACK, alpha),
+            );
+        }
+        if self.body.charge != 0. {
+            let sign = if self.body.charge > 0. { "+" } else { "-" };
+            let (x, y) = (pos.x - 0.1, pos.y + 0.125);
+            ctx.draw_text(sign, x, y, 0.5, color_with_alpha(mq::BLACK, alpha));
+        }
+    }
+
+    /// A halo brighter the faster the ball goes, so anything still doesn't glow at all
+    fn on_draw_glow(&self, ctx: &mut DrawContext) {
+        let speed = to_f32(self.body.velocity.length());
+        let intensity = (speed / GLOW_FULL_SPEED).min(1.) * self.get_alpha();
+        if intensity <= 0. {
+            return;
+        }
+        let pos = to_mq(self.body.pos + lead(&self.body, ctx.lead()));
+        let color = self.draw_color(ctx);
+        let glow = mq::Color::new(
+            color.r * intensity,
+            color.g * intensity,
+            color.b * intensity,
+            1.,
+        );
+        let radius = to_f32(self.body.radius) * GLOW_RADIUS_SCALE;
+        ctx.draw_circle(pos.x, pos.y, radius, glow);
+    }
+}
+
+impl Expire for Ball {
+    fn is_expired(&self) -> bool {
+        self.expiry.is_expired(Some(&self.body))
+    }
+}
+
+impl Collide for Ball {
+    fn body(&self) -> Option<&RigidBody> {
+        Some(&self.body)
+    }
+
+    fn body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.body)
+    }
+
+    fn on_impact(&mut self, impact: &Impact, spawner: &mut Spawner) {
+        if impact.impulse <= BURST_IMPULSE_THRESHOLD {
+            return;
+        }
+        for _ in 0..BURST_PARTICLES {
+            let rng = spawner.rng();
+            let angle = rng.gen_range(0., 2. * PI);
+            let speed = rng.gen_range(BURST_SPEED.0, BURST_SPEED.1);
+            let velocity = Vec2::from_angle(angle) * speed;
+            spawner.spawn_entity(particle(impact.point, velocity, self.color));
+        }
+    }
+}
+
+impl Exert for Ball {}
+
+impl Spawn for Ball {}
+
+impl Save for Ball {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl Ball {
+    const SAVE_TAG: &'static str = "ball";
+
+    fn new_trail() -> Trail {
+        Trail::new(BALL_TRAIL_SECONDS)
+    }
+}
+
+impl TickDrawExpire for Ball {}
+
+/// A falling convex polygon, like a crate or a wedge
+#[derive(Clone, Serialize, Deserialize)]
+struct Block {
+    body: RigidBody,
+    #[serde(with = "ColorDef")]
+    color: mq::Color,
+    expiry: ExpiryPolicy,
+}
+
+impl Block {
+    fn regular(pos: Vec2, sides: usize, radius: Real, color: mq::Color) -> Self {
+        Self {
+            body: RigidBody {
+                pos,
+                radius,
+                shape: Shape::regular_polygon(sides, radius),
+                material: Material::CLAY,
+                drag: Drag::Quadratic(BLOCK_DRAG_COEFFICIENT),
+                ..Default::default()
+            },
+            color,
+            expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+        }
+    }
+}
+
+impl Block {
+    /// A rectangular slab that, unlike a [`BoxBody`], can turn
+    fn plank(pos: Vec2, half_extents: Vec2, color: mq::Color) -> Self {
+        let Vec2 { x, y } = half_extents;
+        Self {
+            body: RigidBody {
+                pos,
+                radius: half_extents.length(),
+                shape: Shape::Polygon(vec![vec2(-x, -y), vec2(x, -y), vec2(x, y), vec2(-x, y)]),
+                material: Material::CLAY,
+                drag: Drag::Quadratic(BLOCK_DRAG_COEFFICIENT),
+                ..Default::default()
+            },
+            color,
+            expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+        }
+    }
+}
+
+impl Tick for Block {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.expiry.on_tick(Some(&self.body), ctx.dt);
+    }
+}
+
+impl Draw for Block {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let alpha = (self.expiry.time_left() / BALL_EXPIRY_TIME).min(1.) as f32;
+        let color = color_with_alpha(self.color, alpha);
+        let lead = lead(&self.body, ctx.lead());
+        let vertices: Vec<_> = self
+            .body
+            .world_vertices()
+            .into_iter()
+            .map(|v| to_mq(v + lead))
+            .collect();
+        let center = to_mq(self.body.pos + lead);
+        for (a, b) in vertices.iter().zip(vertices.iter().cycle().skip(1)) {
+            ctx.draw_triangle(center, *a, *b, color);
+        }
+    }
+}
+
+impl Expire for Block {
+    fn is_expired(&self) -> bool {
+        self.expiry.is_expired(Some(&self.body))
+    }
+}
+
+impl Collide for Block {
+    fn body(&self) -> Option<&RigidBody> {
+        Some(&self.body)
+    }
+
+    fn body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.body)
+    }
+}
+
+impl Exert for Block {}
+
+impl Spawn for Block {}
+
+impl Save for Block {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl Block {
+    const SAVE_TAG: &'static str = "block";
+}
+
+impl TickDrawExpire for Block {}
+
+/// An upright crate that stacks and slides but never tips over
+#[derive(Clone, Serialize, Deserialize)]
+struct BoxBody {
+    body: RigidBody,
+    #[serde(with = "ColorDef")]
+    color: mq::Color,
+    expiry: ExpiryPolicy,
+}
+
+impl BoxBody {
+    fn new(pos: Vec2, half_extents: Vec2, velocity: Vec2) -> Self {
+        Self {
+            body: RigidBody {
+                pos,
+                velocity,
+                radius: half_extents.length(),
+                shape: Shape::Rect { half_extents },
+                material: Material::CLAY,
+                drag: Drag::Quadratic(BLOCK_DRAG_COEFFICIENT),
+                ..Default::default()
+            },
+            color: mq::BEIGE,
+            expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+        }
+    }
+}
+
+impl Tick for BoxBody {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.expiry.on_tick(Some(&self.body), ctx.dt);
+    }
+}
+
+impl Draw for BoxBody {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let alpha = (self.expiry.time_left() / BALL_EXPIRY_TIME).min(1.) as f32;
+        let Aabb { min, max } = self.body.aabb();
+        let min = min + lead(&self.body, ctx.lead());
+        let (min, size) = (to_mq(min), to_mq(max - min));
+        ctx.draw_rectangle(
+            min.x,
+            min.y,
+            size.x,
+            size.y,
+            color_with_alpha(self.color, alpha),
+        );
+        ctx.draw_rectangle_lines(
+            min.x,
+            min.y,
+            size.x,
+            size.y,
+            0.05,
+            color_with_alpha(mq::BROWN, alpha),
+        );
+    }
+}
+
+impl Expire for BoxBody {
+    fn is_expired(&self) -> bool {
+        self.expiry.is_expired(Some(&self.body))
+    }
+}
+
+impl Collide for BoxBody {
+    fn body(&self) -> Option<&RigidBody> {
+        Some(&self.body)
+    }
+
+    fn body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.body)
+    }
+}
+
+impl Exert for BoxBody {}
+
+impl Spawn for BoxBody {}
+
+impl Save for BoxBody {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl BoxBody {
+    const SAVE_TAG: &'static str = "box";
+}
+
+impl TickDrawExpire for BoxBody {}
+
+/// A pill shape that rolls along its length and tumbles end over end
+#[derive(Clone, Serialize, Deserialize)]
+struct Capsule {
+    body: RigidBody,
+    #[serde(with = "ColorDef")]
+    color: mq::Color,
+    expiry: ExpiryPolicy,
+}
+
+impl Capsule {
+    fn new(pos: Vec2, half_length: Real, radius: Real, angle: Real) -> Self {
+        Self {
+            body: RigidBody {
+                pos,
+                radius: half_length + radius,
+                shape: Shape::Capsule {
+                    half_length,
+                    radius,
+                },
+                material: Material::RUBBER,
+                drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                angle,
+                ..Default::default()
+            },
+            color: mq::PINK,
+            expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+        }
+    }
+}
+
+impl Tick for Capsule {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.expiry.on_tick(Some(&self.body), ctx.dt);
+    }
+}
+
+impl Draw for Capsule {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let alpha = (self.expiry.time_left() / BALL_EXPIRY_TIME).min(1.) as f32;
+        let color = color_with_alpha(self.color, alpha);
+        let Shape::Capsule { radius, .. } = self.body.shape else {
+            return;
+        };
+        let lead = lead(&self.body, ctx.lead());
+        let (a, b) = self.body.capsule_segment();
+        let (a, b, radius) = (to_mq(a + lead), to_mq(b + lead), to_f32(radius));
+        ctx.draw_line(a.x, a.y, b.x, b.y, radius * 2., color);
+        ctx.draw_circle(a.x, a.y, radius, color);
+        ctx.draw_circle(b.x, b.y, radius, color);
+    }
+}
+
+impl Expire for Capsule {
+    fn is_expired(&self) -> bool {
+        self.expiry.is_expired(Some(&self.body))
+    }
+}
+
+impl Collide for Capsule {
+    fn body(&self) -> Option<&RigidBody> {
+        Some(&self.body)
+    }
+
+    fn body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.body)
+    }
+}
+
+impl Exert for Capsule {}
+
+impl Spawn for Capsule {}
+
+impl Save for Capsule {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl Capsule {
+    const SAVE_TAG: &'static str = "capsule";
+}
+
+impl TickDrawExpire for Capsule {}
+
+/// Pulls every nearby body towards it for a while before fizzling out
+#[derive(Clone, Serialize, Deserialize)]
+struct GravityWell {
+    pos: Vec2,
+    strength: Real,
+    age: f64,
+}
+
+impl Tick for GravityWell {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.age += ctx.dt;
+    }
+}
+
+impl Draw for GravityWell {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let alpha = (1. - self.age / GRAVITY_WELL_LIFETIME) as f32;
+        let color = color_with_alpha(mq::VIOLET, alpha);
+        let (pos, core_radius) = (to_mq(self.pos), to_f32(GRAVITY_WELL_CORE_RADIUS));
+        ctx.draw_circle(pos.x, pos.y, 0.1, color);
+        ctx.draw_circle_lines(pos.x, pos.y, core_radius, 0.025, color);
+    }
+
+    fn draw_layer(&self) -> DrawLayer {
+        DrawLayer::Background
+    }
+}
+
+impl Expire for GravityWell {
+    fn is_expired(&self) -> bool {
+        self.age >= GRAVITY_WELL_LIFETIME
+    }
+}
+
+impl Collide for GravityWell {}
+
+impl Exert for GravityWell {
+    fn force_on(&self, body: &RigidBody) -> Vec2 {
+        // like real gravity, pulls harder on heavier bodies so everything falls in together,
+        // and the core keeps bodies passing through it from being flung to infinity
+        let attractor = Attractor {
+            pos: self.pos,
+            strength: self.strength,
+            core_radius: GRAVITY_WELL_CORE_RADIUS,
+        };
+        attractor.force(&BodyState::of(body), 0.)
+    }
+}
+
+impl Spawn for GravityWell {}
+
+impl Save for GravityWell {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl GravityWell {
+    const SAVE_TAG: &'static str = "gravity_well";
+}
+
+impl TickDrawExpire for GravityWell {}
+
+/// The expanding ring left behind by an explosion, purely for show
+fn blast_ring(pos: Vec2) -> Entity {
+    Entity::new()
+        .with(Transform::at(pos))
+        .with(Renderable {
+            look: Look::Ring {
+                radius: to_f32(EXPLOSION_RADIUS),
+                thickness: 0.075,
+            },
+            color: mq::ORANGE,
+            layer: DrawLayer::Particles,
+            over_lifetime: OverLifetime::Grows,
+        })
+        .with(Lifetime::new(EXPLOSION_FLASH_TIME))
+}
+
+/// A brief flash where two bodies hit each other hard
+fn spark(pos: Vec2) -> Entity {
+    Entity::new()
+        .with(Transform::at(pos))
+        .with(Renderable {
+            look: Look::Disc { radius: 0.15 },
+            color: mq::YELLOW,
+            layer: DrawLayer::Particles,
+            over_lifetime: OverLifetime::Shrinks,
+        })
+        .with(Lifetime::new(SPARK_LIFETIME))
+}
+
+/// A speck knocked loose by a hard hit, drifting off and fading out. It skitters off the
+/// scenery, but has no body, so it never gets in anything's way.
+fn particle(pos: Vec2, velocity: Vec2, color: mq::Color) -> Entity {
+    Entity::new()
+        .with(Transform::at(pos))
+        .with(Velocity::linear(velocity))
+        .with(Collider {
+            radius: PARTICLE_RADIUS,
+            material: Material::RUBBER,
+        })
+        .with(Renderable {
+            look: Look::Disc {
+                radius: to_f32(PARTICLE_RADIUS),
+            },
+            color,
+            layer: DrawLayer::Particles,
+            over_lifetime: OverLifetime::FadesOut,
+        })
+        .with(Lifetime::new(PARTICLE_LIFETIME))
+}
+
+/// Sprays balls out on a schedule, like a fountain or a hose
+#[derive(Clone, Serialize, Deserialize)]
+struct Emitter {
+    pos: Vec2,
+    /// Bursts per second
+    rate: f64,
+    /// Balls per burst
+    burst: usize,
+    /// Which way the balls go, in radians, and how far either side of it they can stray
+    direction: Real,
+    spread: Real,
+    /// Slowest and fastest launch speeds
+    speed: (Real, Real),
+    /// How long each ball lasts
+    ball_lifetime: f64,
+    /// Bursts that have fallen due but not gone off yet
+    due: f64,
+    age: f64,
+}
+
+impl Tick for Emitter {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.age += ctx.dt;
+        self.due += ctx.dt * self.rate;
+        if ctx.inputs.is_key_down(AIM_KEY) {
+            let aim = ctx.inputs.cursor - self.pos;
+            self.direction = aim.y.atan2(aim.x);
+        }
+    }
+}
+
+impl Draw for Emitter {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let alpha = (1. - self.age / EMITTER_LIFETIME) as f32;
+        let color = color_with_alpha(mq::SKYBLUE, alpha);
+        let pos = to_mq(self.pos);
+        ctx.draw_circle(pos.x, pos.y, 0.125, color);
+        let style = ArrowStyle {
+            head_size: 6.,
+            color: ArrowColor::Fixed(color),
+            length_per_unit: 0.375,
+            ..Default::default()
+        };
+        style.draw(ctx, pos, to_mq(Vec2::from_angle(self.direction)));
+    }
+
+    fn draw_layer(&self) -> DrawLayer {
+        DrawLayer::Annotations
+    }
+}
+
+impl Expire for Emitter {
+    fn is_expired(&self) -> bool {
+        self.age >= EMITTER_LIFETIME
+    }
+}
+
+impl Collide for Emitter {}
+
+impl Exert for Emitter {}
+
+impl Spawn for Emitter {
+    fn on_spawn(&mut self, spawner: &mut Spawner) {
+        while self.due >= 1. {
+            self.due -= 1.;
+            for _ in 0..self.burst {
+                let rng = spawner.rng();
+                let angle = self.direction + rng.gen_range(-self.spread, self.spread);
+                let speed = rng.gen_range(self.speed.0, self.speed.1);
+                let ball = Ball {
+                    body: RigidBody {
+                        pos: self.pos,
+                        velocity: Vec2::from_angle(angle) * speed,
+                        radius: rng.gen_range(0.125, 0.225),
+                        material: Material::RUBBER,
+                        drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                        ..Default::default()
+                    },
+                    color: mq::Color::from_rgba(
+                        rng.gen_range(50, 120),
+                        rng.gen_range(150, 255),
+                        255,
+                        255,
+                    ),
+                    expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME)
+                        .or(ExpiryPolicy::lifetime(self.ball_lifetime)),
+                    trail: Trail::new(BALL_TRAIL_SECONDS),
+                    sprite: None,
+                    age: 0.,
+                };
+                spawner.spawn(ball);
+            }
+        }
+    }
+}
+
+impl Save for Emitter {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl Emitter {
+    const SAVE_TAG: &'static str = "emitter";
+}
+
+impl TickDrawExpire for Emitter {}
+
+/// A faint speck drifting about in the background, purely for show
+#[derive(Clone)]
+struct Mote {
+    body: RigidBody,
+}
+
+impl Tick for Mote {
+    fn on_tick(&mut self, _ctx: &TickCtx) {}
+}
+
+impl Draw for Mote {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let color = color_with_alpha(mq::LIGHTGRAY, 0.15);
+        let pos = to_mq(self.body.pos + lead(&self.body, ctx.lead()));
+        ctx.draw_circle(pos.x, pos.y, to_f32(self.body.radius), color);
+    }
+
+    fn draw_layer(&self) -> DrawLayer {
+        DrawLayer::Background
+    }
+}
+
+impl Expire for Mote {
+    fn is_expired(&self) -> bool {
+        false
+    }
+}
+
+impl Collide for Mote {
+    fn body(&self) -> Option<&RigidBody> {
+        Some(&self.body)
+    }
+
+    fn body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.body)
+    }
+}
+
+impl Exert for Mote {}
+
+impl Spawn for Mote {}
+
+impl Save for Mote {}
+
+impl TickDrawExpire for Mote {}
+
+/// One of the point masses making up a soft ball's skin. The simulation draws the ball itself.
+#[derive(Clone, Serialize, Deserialize)]
+struct SoftNode {
+    body: RigidBody,
+    expiry: ExpiryPolicy,
+}
+
+impl Tick for SoftNode {
+    fn on_tick(&mut self, ctx: &TickCtx) {
+        self.expiry.on_tick(Some(&self.body), ctx.dt);
+    }
+}
+
+impl Draw for SoftNode {
+    fn on_draw(&self, ctx: &mut DrawContext) {
+        let mq::Vec2 { x, y } = to_mq(self.body.pos + lead(&self.body, ctx.lead()));
+        ctx.draw_circle(x, y, to_f32(self.body.radius), mq::DARKGRAY);
+    }
+}
+
+impl Expire for SoftNode {
+    fn is_expired(&self) -> bool {
+        self.expiry.is_expired(Some(&self.body))
+    }
+}
+
+impl Collide for SoftNode {
+    fn body(&self) -> Option<&RigidBody> {
+        Some(&self.body)
+    }
+
+    fn body_mut(&mut self) -> Option<&mut RigidBody> {
+        Some(&mut self.body)
+    }
+}
+
+impl Exert for SoftNode {}
+
+impl Spawn for SoftNode {}
+
+impl Save for SoftNode {
+    fn save(&self) -> Option<(&'static str, &dyn erased_serde::Serialize)> {
+        Some((Self::SAVE_TAG, self))
+    }
+}
+
+impl SoftNode {
+    const SAVE_TAG: &'static str = "soft_node";
+}
+
+impl TickDrawExpire for SoftNode {}
+
+fn draw_dbg_text(time: f64, ticks_so_far: usize, frames_so_far: usize, stats: &Stats) {
+    let kinds: Vec<String> = stats
+        .counts_by_kind
+        .iter()
+        .map(|(kind, count)| format!("{count} {kind}"))
+        .collect();
+    mq::draw_text(
+            &format!("Time elapsed {:.2}\nTicks: {}\nFrames: {}\nObjects: {} ({})\nEntities: {}\nKE: {:.2} J\nAvg speed: {:.2} m/s\nFastest: {:.2} m/s\nCollisions/s: {:.1}",
+                time,
+                ticks_so_far,
+                frames_so_far,
+            stats.object_count,
+            kinds.join(", "),
+            stats.entity_count,
+            stats.kinetic_energy,
+            stats.average_speed,
+            stats.top_speed,
+            stats.collisions_per_second),
+            5.,
+            20.,
+            16.,
+            mq::WHITE,
+        );
+}
+
+/// A compass-style dial in the top right showing a global vector's direction and size
+fn draw_vector_indicator(slot: usize, label: &str, vector: mq::Vec2, style: &ArrowStyle) {
+    let origin = mq::vec2(mq::screen_width() - 60. - 80. * slot as f32, 40.);
+    let color = style.color_for(vector.length());
+    mq::draw_circle_lines(origin.x, origin.y, 30., 1., mq::GRAY);
+    style.draw_screen(&mut MacroquadRenderer, origin, vector);
+    mq::draw_text(
+        &format!("{}: {:.1}", label, vector.length()),
+        origin.x - 30.,
+        origin.y + 45.,
+        16.,
+        color,
+    );
+}
+
+/// How long one frame took from start to finish, and how much of it went on ticking
+struct FrameTiming {
+    /// When the frame was, in real seconds
+    at: f64,
+    frame_seconds: f32,
+    tick_seconds: f32,
+}
+
+/// Plot how long the last few seconds' frames took, and the ticking within them, along the
+/// bottom, so hitches and the bursts of ticks that catch up after them stand out. The grey
+/// line is a 60 FPS frame.
+fn draw_timing_graph(history: &VecDeque<FrameTiming>, now: f64) {
+    let size = TIMING_GRAPH_SIZE;
+    let origin = mq::vec2(
+        (mq::screen_width() - size.x) / 2.,
+        mq::screen_height() - size.y - 10.,
+    );
+    mq::draw_rectangle_lines(origin.x, origin.y, size.x, size.y, 1., mq::GRAY);
+    let budget = 1. / 60.;
+    let slowest = history.iter().map(|t| t.frame_seconds.max(t.tick_seconds));
+    let top = slowest.fold(2. * budget, f32::max);
+    let point = |timing: &FrameTiming, seconds: f32| {
+        let age = ((now - timing.at) / TIMING_HISTORY_SECONDS) as f32;
+        mq::vec2(
+            origin.x + size.x * (1. - age),
+            origin.y + size.y * (1. - seconds / top),
+        )
+    };
+    let y = origin.y + size.y * (1. - budget / top);
+    mq::draw_line(origin.x, y, origin.x + size.x, y, 1., mq::DARKGRAY);
+    let frame: fn(&FrameTiming) -> f32 = |t| t.frame_seconds;
+    let ticks: fn(&FrameTiming) -> f32 = |t| t.tick_seconds;
+    for (seconds, color) in [(frame, mq::SKYBLUE), (ticks, mq::ORANGE)] {
+        let points: Vec<_> = history.iter().map(|t| point(t, seconds(t))).collect();
+        for pair in points.windows(2) {
+            mq::draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1., color);
+        }
+    }
+    let latest = |seconds: fn(&FrameTiming) -> f32| history.back().map_or(0., seconds) * 1000.;
+    mq::draw_text(
+        &format!(
+            "frame {:.1} ms, ticks {:.1} ms ({:.0} FPS)",
+            latest(frame),
+            latest(ticks),
+            mq::get_fps(),
+        ),
+        origin.x,
+        origin.y - 4.,
+        16.,
+        mq::WHITE,
+    );
+}
+
+/// The object count and kinetic energy as of one frame
+struct StatsSample {
+    /// When the frame was, in real seconds
+    at: f64,
+    object_count: usize,
+    kinetic_energy: f32,
+}
+
+/// Plot the object count and kinetic energy over the last little while, stacked above the
+/// minimap, so an emitter filling a scene up or energy climbing out of nowhere stands out
+fn draw_stats_graphs(history: &VecDeque<StatsSample>, now: f64) {
+    let latest = history.back();
+    let objects = latest.map_or(0, |s| s.object_count);
+    let energy = latest.map_or(0., |s| s.kinetic_energy);
+    let bottom = mq::screen_height() - MINIMAP_SIZE.y - 2. * MINIMAP_MARGIN;
+    let slot = STATS_GRAPH_SIZE.y + 20.;
+    let kinetic = |s: &StatsSample| s.kinetic_energy;
+    let label = format!("KE: {energy:.2} J");
+    draw_stats_graph(history, now, bottom - slot, &label, kinetic, mq::ORANGE);
+    let count = |s: &StatsSample| s.object_count as f32;
+    let label = format!("objects: {objects}");
+    draw_stats_graph(history, now, bottom - 2. * slot, &label, count, mq::LIME);
+}
+
+/// One of `draw_stats_graphs`'s plots with its top at `y`, scaled from zero up to the
+/// highest it's been
+fn draw_stats_graph(
+    history: &VecDeque<StatsSample>,
+    now: f64,
+    y: f32,
+    label: &str,
+    value: fn(&StatsSample) -> f32,
+    color: mq::Color,
+) {
+    let (size, x) = (STATS_GRAPH_SIZE, MINIMAP_MARGIN);
+    mq::draw_rectangle_lines(x, y, size.x, size.y, 1., mq::GRAY);
+    let top = history.iter().map(value).fold(f32::EPSILON, f32::max);
+    let points: Vec<_> = history
+        .iter()
+        .map(|sample| {
+            let age = ((now - sample.at) / STATS_HISTORY_SECONDS) as f32;
+            mq::vec2(
+                x + size.x * (1. - age),
+                y + size.y * (1. - value(sample) / top),
+            )
+        })
+        .collect();
+    for pair in points.windows(2) {
+        mq::draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1., color);
+    }
+    mq::draw_text(label, x, y - 4., 16., mq::WHITE);
+}
+
+/// Plot total, kinetic and potential energy over the last few seconds in the bottom corner,
+/// scaled to fit whatever range they cover
+fn draw_energy_graph(history: &VecDeque<EnergyReading>) {
+    let (width, height) = (200., 100.);
+    let origin = mq::vec2(
+        mq::screen_width() - width - 10.,
+        mq::screen_height() - height - 10.,
+    );
+    mq::draw_rectangle_lines(origin.x, origin.y, width, height, 1., mq::GRAY);
+    let readings =
+        |value: fn(&EnergyReading) -> Real| history.iter().map(|e| to_f32(value(e))).collect();
+    let series: [(Vec<f32>, mq::Color); 3] = [
+        (readings(|e| e.kinetic), mq::ORANGE),
+        (readings(|e| e.potential), mq::GREEN),
+        (readings(EnergyReading::total), mq::WHITE),
+    ];
+    let values = series.iter().flat_map(|(values, _)| values.iter());
+    let (lo, hi) = values.fold((f32::MAX, f32::MIN), |(lo, hi), v| (lo.min(*v), hi.max(*v)));
+    let range = (hi - lo).max(f32::EPSILON);
+    let point = |k: usize, v: f32| {
+        let x = origin.x + width * k as f32 / history.len().max(2) as f32;
+        mq::vec2(x, origin.y + height * (1. - (v - lo) / range))
+    };
+    for (values, color) in &series {
+        let points: Vec<_> = values
+            .iter()
+            .enumerate()
+            .map(|(k, v)| point(k, *v))
+            .collect();
+        for pair in points.windows(2) {
+            mq::draw_line(pair[0].x, pair[0].y, pair[1].x, pair[1].y, 1., *color);
+        }
+    }
+    mq::draw_text(
+        &format!(
+            "energy: {:.3e}",
+            history.back().map_or(0., EnergyReading::total)
+        ),
+        origin.x,
+        origin.y - 4.,
+        16.,
+        mq::WHITE,
+    );
+}
+
+/// Left/right swing gravity around, down switches it off and up restores the default
+fn handle_gravity_keys(simulation: &mut Simulation, default_gravity: Vec2) {
+    let mut gravity = simulation.get_gravity();
+    let turn = (GRAVITY_ROTATION_SPEED * mq::get_frame_time()) as Real;
+    if Action::SwingGravityLeft.is_down() {
+        gravity = Vec2::from_angle(-turn).rotate(gravity);
+    }
+    if Action::SwingGravityRight.is_down() {
+        gravity = Vec2::from_angle(turn).rotate(gravity);
+    }
+    if Action::GravityOff.is_pressed() {
+        gravity = Vec2::ZERO;
+    }
+    if Action::GravityOn.is_pressed() {
+        gravity = default_gravity;
+    }
+    simulation.set_gravity(gravity);
+}
+
+/// Everything the keyboard does, apart from the overlays, tools and presets, which keep
+/// their own keys. Going through these rather than naming keys directly is what keeps the
+/// help list up to date.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Help,
+    Menu,
+    Settings,
+    Pause,
+    Slower,
+    Faster,
+    NormalSpeed,
+    Rewind,
+    SwingGravityLeft,
+    SwingGravityRight,
+    GravityOff,
+    GravityOn,
+    Integrator,
+    EnergyGraph,
+    Electrostatics,
+    ChargedBall,
+    Block,
+    Triangle,
+    Crate,
+    Capsule,
+    SoftBall,
+    Balloon,
+    Emitter,
+    GravityWell,
+    StopBall,
+    Aim,
+    Laser,
+    Stream,
+    StreamSlower,
+    StreamFaster,
+    UndoWall,
+    Follow,
+    FitWindow,
+    Fullscreen,
+    ResetView,
+    Save,
+    Load,
+    Screenshot,
+    Record,
+    SpeedColors,
+    Glow,
+    Sprites,
+    DesignBall,
+}
+
+impl Action {
+    const ALL: [Action; 43] = [
+        Action::Help,
+        Action::Menu,
+        Action::Settings,
+        Action::Pause,
+        Action::Slower,
+        Action::Faster,
+        Action::NormalSpeed,
+        Action::Rewind,
+        Action::SwingGravityLeft,
+        Action::SwingGravityRight,
+        Action::GravityOff,
+        Action::GravityOn,
+        Action::Integrator,
+        Action::EnergyGraph,
+        Action::Electrostatics,
+        Action::ChargedBall,
+        Action::Block,
+        Action::Triangle,
+        Action::Crate,
+        Action::Capsule,
+        Action::SoftBall,
+        Action::Balloon,
+        Action::Emitter,
+        Action::GravityWell,
+        Action::StopBall,
+        Action::Aim,
+        Action::Laser,
+        Action::Stream,
+        Action::StreamSlower,
+        Action::StreamFaster,
+        Action::UndoWall,
+        Action::Follow,
+        Action::FitWindow,
+        Action::Fullscreen,
+        Action::ResetView,
+        Action::Save,
+        Action::Load,
+        Action::Screenshot,
+        Action::Record,
+        Action::SpeedColors,
+        Action::Glow,
+        Action::Sprites,
+        Action::DesignBall,
+    ];
+
+    const fn key(self) -> mq::KeyCode {
+        match self {
+            Action::Help => mq::KeyCode::Slash,
+            Action::Menu => mq::KeyCode::Escape,
+            Action::Settings => mq::KeyCode::Y,
+            Action::Pause => mq::KeyCode::Space,
+            Action::Slower => mq::KeyCode::Minus,
+            Action::Faster => mq::KeyCode::Equal,
+            Action::NormalSpeed => mq::KeyCode::Key0,
+            Action::Rewind => mq::KeyCode::R,
+            Action::SwingGravityLeft => mq::KeyCode::Left,
+            Action::SwingGravityRight => mq::KeyCode::Right,
+            Action::GravityOff => mq::KeyCode::Down,
+            Action::GravityOn => mq::KeyCode::Up,
+            Action::Integrator => mq::KeyCode::I,
+            Action::EnergyGraph => mq::KeyCode::H,
+            Action::Electrostatics => mq::KeyCode::E,
+            Action::ChargedBall => mq::KeyCode::Q,
+            Action::Block => mq::KeyCode::B,
+            Action::Triangle => mq::KeyCode::T,
+            Action::Crate => mq::KeyCode::C,
+            Action::Capsule => mq::KeyCode::P,
+            Action::SoftBall => mq::KeyCode::S,
+            Action::Balloon => mq::KeyCode::O,
+            Action::Emitter => mq::KeyCode::F,
+            Action::GravityWell => mq::KeyCode::G,
+            Action::StopBall => mq::KeyCode::X,
+            Action::Aim => mq::KeyCode::A,
+            Action::Laser => mq::KeyCode::L,
+            Action::Stream => mq::KeyCode::U,
+            Action::StreamSlower => mq::KeyCode::LeftBracket,
+            Action::StreamFaster => mq::KeyCode::RightBracket,
+            Action::UndoWall => mq::KeyCode::Backspace,
+            Action::Follow => mq::KeyCode::Tab,
+            Action::FitWindow => mq::KeyCode::W,
+            Action::Fullscreen => mq::KeyCode::Enter,
+            Action::ResetView => mq::KeyCode::Home,
+            Action::Save => mq::KeyCode::F6,
+            Action::Load => mq::KeyCode::F9,
+            Action::Screenshot => mq::KeyCode::F12,
+            Action::Record => mq::KeyCode::F8,
+            Action::SpeedColors => mq::KeyCode::V,
+            Action::Glow => mq::KeyCode::N,
+            Action::Sprites => mq::KeyCode::K,
+            Action::DesignBall => mq::KeyCode::Period,
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Action::Help => "this list",
+            Action::Menu => "the pause menu",
+            Action::Settings => "live settings",
+            Action::Pause => "pause or carry on",
+            Action::Slower => "slow time down",
+            Action::Faster => "speed time up",
+            Action::NormalSpeed => "time back to normal",
+            Action::Rewind => "hold to rewind",
+            Action::SwingGravityLeft => "hold to swing gravity left",
+            Action::SwingGravityRight => "hold to swing gravity right",
+            Action::GravityOff => "gravity off",
+            Action::GravityOn => "gravity back to normal",
+            Action::Integrator => "cycle the integrator",
+            Action::EnergyGraph => "energy graph",
+            Action::Electrostatics => "electrostatics on or off",
+            Action::ChargedBall => "a charged ball",
+            Action::Block => "a block",
+            Action::Triangle => "a triangle",
+            Action::Crate => "a box",
+            Action::Capsule => "a capsule",
+            Action::SoftBall => "a soft ball",
+            Action::Balloon => "a balloon",
+            Action::Emitter => "an emitter",
+            Action::GravityWell => "a gravity well",
+            Action::StopBall => "stop the ball under the cursor",
+            Action::Aim => "hold to aim emitters",
+            Action::Laser => "hold for a laser",
+            Action::Stream => "stream balls from the left button",
+            Action::StreamSlower => "stream fewer balls",
+            Action::StreamFaster => "stream more balls",
+            Action::UndoWall => "take back the last wall",
+            Action::Follow => "follow the ball under the cursor",
+            Action::FitWindow => "fit the walls to the window",
+            Action::Fullscreen => "fullscreen",
+            Action::ResetView => "reset the view",
+            Action::Save => "save",
+            Action::Load => "load the save",
+            Action::Screenshot => "screenshot, with Shift no HUD",
+            Action::Record => "start or stop recording",
+            Action::SpeedColors => "colour balls by speed",
+            Action::Glow => "glow",
+            Action::Sprites => "picture balls",
+            Action::DesignBall => "design the next ball",
+        }
+    }
+
+    /// Only fullscreen needs a modifier held, so Enter alone is left free
+    fn needs_alt(self) -> bool {
+        self == Action::Fullscreen
+    }
+
+    fn is_pressed(self) -> bool {
+        let alt = mq::is_key_down(mq::KeyCode::LeftAlt) || mq::is_key_down(mq::KeyCode::RightAlt);
+        mq::is_key_pressed(self.key()) && (alt || !self.needs_alt())
+    }
+
+    fn is_down(self) -> bool {
+        mq::is_key_down(self.key())
+    }
+
+    /// The key as the help list shows it
+    fn key_name(self) -> String {
+        let name = key_name(self.key());
+        if self.needs_alt() {
+            format!("Alt+{name}")
+        } else {
+            name
+        }
+    }
+}
+
+/// How a key is written down for people rather than as its `KeyCode`
+fn key_name(key: mq::KeyCode) -> String {
+    let name = match key {
+        mq::KeyCode::Minus => "-",
+        mq::KeyCode::Equal => "=",
+        mq::KeyCode::LeftBracket => "[",
+        mq::KeyCode::RightBracket => "]",
+        mq::KeyCode::Escape => "Esc",
+        mq::KeyCode::Period => ".",
+        mq::KeyCode::Slash => "/",
+        _ => return format!("{key:?}").trim_start_matches("Key").to_string(),
+    };
+    name.to_string()
+}
+
+/// The number keys, each dropping the matching one of `SPAWN_PRESETS` at the cursor
+const PRESET_KEYS: [mq::KeyCode; SPAWN_PRESETS.len()] = [
+    mq::KeyCode::Key1,
+    mq::KeyCode::Key2,
+    mq::KeyCode::Key3,
+    mq::KeyCode::Key4,
+    mq::KeyCode::Key5,
+    mq::KeyCode::Key6,
+    mq::KeyCode::Key7,
+    mq::KeyCode::Key8,
+    mq::KeyCode::Key9,
+];
+
+/// What the mouse buttons do besides the left one, which is the toolbar's
+const MOUSE_HELP: [&str; 3] = [
+    "Right click to pop a ball, or blast",
+    "Middle drag to pan",
+    "Scroll to zoom",
+];
+
+/// Every key and tool there is, in columns over a dark backdrop. It's all read off
+/// `Action`, `Overlay`, `Tool` and the presets, so nothing can be left out.
+fn draw_help() {
+    let mut lines: Vec<String> = Action::ALL
+        .into_iter()
+        .map(|action| format!("{} {}", action.key_name(), action.describe()))
+        .collect();
+    lines.extend(
+        PRESET_KEYS
+            .iter()
+            .zip(&SPAWN_PRESETS)
+            .map(|(&key, preset)| format!("{} a {} at the cursor", key_name(key), preset.name)),
+    );
+    lines.extend(Overlay::ALL.map(|overlay| format!("{} overlay", overlay.label())));
+    lines.extend(Tool::ALL.map(|tool| match tool.key() {
+        Some(key) => format!("{} the {} tool", key_name(key), tool.label()),
+        None => format!("Toolbar: the {} tool", tool.label()),
+    }));
+    lines.extend(MOUSE_HELP.map(String::from));
+    let (width, height) = (mq::screen_width(), mq::screen_height());
+    mq::draw_rectangle(0., 0., width, height, mq::Color::new(0., 0., 0., 0.85));
+    mq::draw_text("Keys and tools (H to close)", 10., 24., 20., mq::YELLOW);
+    let rows = lines.len().div_ceil(HELP_COLUMNS);
+    let column_width = (width - 20.) / HELP_COLUMNS as f32;
+    for (i, line) in lines.iter().enumerate() {
+        let x = 10. + column_width * (i / rows) as f32;
+        let y = 48. + 16. * (i % rows) as f32;
+        mq::draw_text(line, x, y, 16., mq::WHITE);
+    }
+}
+
+/// Debug views that can be switched on and off, each with its own function key
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Overlay {
+    Hud,
+    Velocities,
+    Colliders,
+    Contacts,
+    BroadPhase,
+    BallLabels,
+    Grid,
+    Fields,
+    Minimap,
+}
+
+impl Overlay {
+    const ALL: [Overlay; 9] = [
+        Overlay::Hud,
+        Overlay::Velocities,
+        Overlay::Colliders,
+        Overlay::Contacts,
+        Overlay::BroadPhase,
+        Overlay::BallLabels,
+        Overlay::Grid,
+        Overlay::Fields,
+        Overlay::Minimap,
+    ];
+
+    fn key(self) -> mq::KeyCode {
+        match self {
+            Overlay::Hud => mq::KeyCode::F1,
+            Overlay::Velocities => mq::KeyCode::F2,
+            Overlay::Colliders => mq::KeyCode::F3,
+            Overlay::Contacts => mq::KeyCode::F4,
+            Overlay::BroadPhase => mq::KeyCode::F5,
+            Overlay::BallLabels => mq::KeyCode::F7,
+            Overlay::Grid => mq::KeyCode::F10,
+            Overlay::Fields => mq::KeyCode::F11,
+            Overlay::Minimap => mq::KeyCode::M,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Overlay::Hud => "F1 text",
+            Overlay::Velocities => "F2 velocities",
+            Overlay::Colliders => "F3 colliders",
+            Overlay::Contacts => "F4 contacts",
+            Overlay::BroadPhase => "F5 broad phase",
+            Overlay::BallLabels => "F7 ball velocities",
+            Overlay::Grid => "F10 grid",
+            Overlay::Fields => "F11 force fields",
+            Overlay::Minimap => "M minimap",
+        }
+    }
+}
+
+/// Which overlays are showing. The text and velocity arrows start out on, as they used to
+/// be all the time, and so does the grid for judging distances by.
+struct Overlays {
+    on: [bool; Overlay::ALL.len()],
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        let mut overlays = Self {
+            on: [false; Overlay::ALL.len()],
+        };
+        overlays.on[Overlay::Hud as usize] = true;
+        overlays.on[Overlay::Velocities as usize] = true;
+        overlays
+    }
+}
+
+impl Overlays {
+    fn is_on(&self, overlay: Overlay) -> bool {
+        self.on[overlay as usize]
+    }
+
+    /// Flip each overlay whose key went down this frame
+    fn handle_keys(&mut self) {
+        for overlay in Overlay::ALL {
+            if mq::is_key_pressed(overlay.key()) {
+                self.on[overlay as usize] = !self.on[overlay as usize];
+            }
+        }
+    }
+
+    /// The keys, with whichever overlays are on marked
+    fn legend(&self) -> String {
+        let labels: Vec<_> = Overlay::ALL
+            .into_iter()
+            .map(|o| format!("{}{}", o.label(), if self.is_on(o) { "*" } else { "" }))
+            .collect();
+        format!("Overlays: {}", labels.join(", "))
+    }
+
+    /// Draw the overlays that go behind everything in the world
+    fn draw_under(&self, ctx: &mut DrawContext, simulation: &Simulation) {
+        if self.is_on(Overlay::Grid) {
+            draw_grid(ctx);
+        }
+        if self.is_on(Overlay::Fields) {
+            draw_field(ctx, simulation);
+        }
+    }
+
+    /// Draw the overlays that go over the world, rather than on the screen
+    fn draw(&self, ctx: &mut DrawContext, simulation: &Simulation) {
+        if self.is_on(Overlay::BroadPhase) {
+            let color = mq::Color::new(0.5, 0.5, 0.5, 0.5);
+            for cell in simulation.broad_phase_cells() {
+                let (min, size) = (to_mq(cell.min), to_mq(cell.max - cell.min));
+                ctx.draw_rectangle_lines(min.x, min.y, size.x, size.y, 0.025, color);
+            }
+        }
+        if self.is_on(Overlay::Colliders) {
+            for collider in simulation.statics() {
+                draw_outline(ctx, &collider.body(), mq::ORANGE);
+            }
+            let bodies = simulation.objects().filter_map(|(_, o)| o.body());
+            bodies.for_each(|body| draw_outline(ctx, body, mq::GREEN));
+        }
+        if self.is_on(Overlay::Velocities) {
+            let seconds = ctx.lead();
+            // blue when slow through to red as fast as balls get coloured for
+            let velocity = ArrowStyle {
+                thickness: 1.,
+                head_size: 6.,
+                color: ArrowColor::ByMagnitude {
+                    gradient: Gradient::heat(),
+                    max: SPEED_COLORING_TOP_SPEED,
+                },
+                length_per_unit: ARROW_LEN_MULTIPLIER,
+                ..Default::default()
+            };
+            for body in simulation.objects().filter_map(|(_, o)| o.body()) {
+                let pos = body.pos + lead(body, seconds);
+                velocity.draw(ctx, to_mq(pos), to_mq(body.velocity));
+            }
+        }
+        if self.is_on(Overlay::Contacts) {
+            for point in simulation.contacts().iter().copied().map(to_mq) {
+                ctx.draw_circle(point.x, point.y, 0.075, mq::RED);
+            }
+        }
+        if self.is_on(Overlay::BallLabels) {
+            let seconds = ctx.lead();
+            for (handle, _) in simulation.objects() {
+                let Some(ball) = simulation.get_as::<Ball>(handle) else {
+                    continue;
+                };
+                // just off the ball's upper right, in screen space so it stays readable
+                let body = &ball.body;
+                let corner = body.pos + lead(body, seconds) + vec2(1., -1.) * body.radius;
+                let at = ctx.camera().world_to_screen(to_mq(corner));
+                let text = format!("<{:.2}, {:.2}>", body.velocity.x, body.velocity.y);
+                ctx.screen().draw_text(&text, at.x + 2., at.y, 14., mq::RED);
+            }
+        }
+        if self.is_on(Overlay::Minimap) {
+            draw_minimap(ctx, simulation);
+        }
+    }
+}
+
+/// The whole world shrunk into the bottom left corner: scenery as lines, bodies as dots and
+/// the part the camera can see as a box, for finding your way once zoomed in
+fn draw_minimap(ctx: &mut DrawContext, simulation: &Simulation) {
+    let Some(extent) = simulation.extent() else {
+        return;
+    };
+    let camera = *ctx.camera();
+    let (min, max) = (to_mq(extent.min), to_mq(extent.max));
+    let size = (max - min).max(mq::Vec2::splat(f32::EPSILON));
+    let scale = (MINIMAP_SIZE / size).min_element();
+    let origin = mq::vec2(
+        MINIMAP_MARGIN,
+        camera.size().y - MINIMAP_MARGIN - size.y * scale,
+    );
+    let to_map = |point: mq::Vec2| origin + (point - min) * scale;
+    let screen = ctx.screen();
+    let (w, h) = (size.x * scale, size.y * scale);
+    screen.draw_rectangle(origin.x, origin.y, w, h, mq::Color::new(0., 0., 0., 0.6));
+    screen.draw_rectangle_lines(origin.x, origin.y, w, h, 1., mq::GRAY);
+    for collider in simulation.statics() {
+        match *collider {
+            StaticCollider::Segment(a, b)
+            | StaticCollider::Platform(a, b)
+            | StaticCollider::Conveyor(a, b, _) => {
+                let (a, b) = (to_map(to_mq(a)), to_map(to_mq(b)));
+                screen.draw_line(a.x, a.y, b.x, b.y, 1., mq::LIGHTGRAY);
+            }
+            StaticCollider::Box(aabb) => {
+                let (min, max) = (to_map(to_mq(aabb.min)), to_map(to_mq(aabb.max)));
+                screen.draw_rectangle(min.x, min.y, max.x - min.x, max.y - min.y, mq::LIGHTGRAY);
+            }
+        }
+    }
+    for body in simulation.objects().filter_map(|(_, o)| o.body()) {
+        let at = to_map(to_mq(body.pos));
+        let radius = to_f32(body.radius) * scale;
+        screen.draw_circle(at.x, at.y, radius.max(1.5), mq::WHITE);
+    }
+    let view_min = to_map(camera.screen_to_world(mq::Vec2::ZERO)).max(origin);
+    let view_max = to_map(camera.screen_to_world(camera.size())).min(origin + mq::vec2(w, h));
+    if view_min.cmplt(view_max).all() {
+        let view = view_max - view_min;
+        screen.draw_rectangle_lines(view_min.x, view_min.y, view.x, view.y, 1., mq::YELLOW);
+    }
+}
+
+/// Lines across the visible world at round numbers of metres, spaced out as the view zooms
+/// out so they never crowd together. Every few lines is brighter and labelled along the
+/// bottom and right edges of the screen.
+fn draw_grid(ctx: &mut DrawContext) {
+    let camera = *ctx.camera();
+    let scale = camera.scale();
+    let Some(spacing) = GRID_SPACINGS
+        .into_iter()
+        .find(|spacing| spacing * scale >= GRID_MIN_PIXELS)
+    else {
+        return;
+    };
+    let (width, height) = camera.size().into();
+    let min = camera.screen_to_world(mq::Vec2::ZERO);
+    let max = camera.screen_to_world(mq::vec2(width, height));
+    // a pixel wide however far in or out the view is
+    let thickness = 1. / scale;
+    let (minor, major) = (
+        mq::Color::new(1., 1., 1., 0.08),
+        mq::Color::new(1., 1., 1., 0.2),
+    );
+    let lines = |from: f32, to: f32| (from / spacing).floor() as i64..=(to / spacing).ceil() as i64;
+    for k in lines(min.x, max.x) {
+        let x = k as f32 * spacing;
+        let is_major = k % GRID_LABEL_EVERY == 0;
+        let color = if is_major { major } else { minor };
+        ctx.draw_line(x, min.y, x, max.y, thickness, color);
+        if is_major {
+            let at = camera.world_to_screen(mq::vec2(x, 0.));
+            let label = format!("{} m", x);
+            ctx.screen()
+                .draw_text(&label, at.x + 3., height - 5., 14., major);
+        }
+    }
+    for k in lines(min.y, max.y) {
+        let y = k as f32 * spacing;
+        let is_major = k % GRID_LABEL_EVERY == 0;
+        let color = if is_major { major } else { minor };
+        ctx.draw_line(min.x, y, max.x, y, thickness, color);
+        if is_major {
+            let at = camera.world_to_screen(mq::vec2(0., y));
+            let label = format!("{} m", y);
+            ctx.screen()
+                .draw_text(&label, width - 45., at.y - 3., 14., major);
+        }
+    }
+}
+
+/// Arrows across the visible world showing which way the wind, force fields and attractors
+/// push, each as long as the push is strong up to the gap between arrows. Gravity is shown on
+/// its dial instead.
+fn draw_field(ctx: &mut DrawContext, simulation: &Simulation) {
+    let camera = *ctx.camera();
+    let min = camera.screen_to_world(mq::Vec2::ZERO);
+    let max = camera.screen_to_world(camera.size());
+    // sparser when zoomed out, so the arrows keep to a readable size on screen
+    let spacing = (FIELD_ARROW_PIXELS / camera.scale()).max(FIELD_MIN_SPACING);
+    let samples =
+        |from: f32, to: f32| (from / spacing).floor() as i64..=(to / spacing).ceil() as i64;
+    let style = ArrowStyle {
+        thickness: 1.,
+        head_size: 6.,
+        color: ArrowColor::Fixed(color_with_alpha(mq::ORANGE, 0.6)),
+        ..Default::default()
+    };
+    for i in samples(min.x, max.x) {
+        for j in samples(min.y, max.y) {
+            let point = mq::vec2(i as f32, j as f32) * spacing;
+            let accel = to_mq(simulation.field_at(from_mq(point)));
+            let strength = accel.length();
+            if strength < FIELD_MIN_ACCEL {
+                continue;
+            }
+            // eases off towards full length, so a strong pull doesn't swamp everything else
+            let len = 0.8 * spacing * strength / (strength + FIELD_HALF_LENGTH_ACCEL);
+            style.draw(ctx, point, accel / strength * len);
+        }
+    }
+}
+
+/// The edge of a body's collision shape, as the narrow phase sees it
+fn draw_outline(ctx: &mut DrawContext, body: &RigidBody, color: mq::Color) {
+    const THICKNESS: f32 = 0.025;
+    match body.shape {
+        Shape::Circle => {
+            let (pos, radius) = (to_mq(body.pos), to_f32(body.radius));
+            ctx.draw_circle_lines(pos.x, pos.y, radius, THICKNESS, color)
+        }
+        Shape::Capsule { radius, .. } => {
+            let (a, b) = body.capsule_segment();
+            let (a, b, radius) = (to_mq(a), to_mq(b), to_f32(radius));
+            let side = (b - a).normalize_or_zero().perp() * radius;
+            for (p, q) in [(a + side, b + side), (a - side, b - side)] {
+                ctx.draw_line(p.x, p.y, q.x, q.y, THICKNESS, color);
+            }
+            for end in [a, b] {
+                ctx.draw_circle_lines(end.x, end.y, radius, THICKNESS, color);
+            }
+        }
+        Shape::Polygon(_) | Shape::Rect { .. } => {
+            let vertices: Vec<_> = body.world_vertices().into_iter().map(to_mq).collect();
+            for (p, q) in vertices.iter().zip(vertices.iter().cycle().skip(1)) {
+                ctx.draw_line(p.x, p.y, q.x, q.y, THICKNESS, color);
+            }
+        }
+    }
+}
+
+/// Details of the ball under the pointer, drawn next to it
+fn draw_inspector(simulation: &Simulation, cursor: Vec2, mouse: mq::Vec2) {
+    let Some(ball) = simulation
+        .object_at(cursor)
+        .and_then(|h| simulation.get_as::<Ball>(h))
+    else {
+        return;
+    };
+    let body = &ball.body;
+    let time_left = ball.expiry.time_left();
+    let lines = [
+        format!("speed {:.1} m/s", body.velocity.length()),
+        format!("radius {:.2} m, mass {:.2} kg", body.radius, body.mass()),
+        format!("charge {:.1e} C", body.charge),
+        if time_left.is_finite() {
+            format!("expires in {time_left:.1}s")
+        } else {
+            "never expires".to_owned()
+        },
+        "X to stop it".to_owned(),
+    ];
+    for (i, line) in lines.iter().enumerate() {
+        let y = mouse.y + 20. + i as f32 * 14.;
+        mq::draw_text(line, mouse.x + 12., y, 14., mq::WHITE);
+    }
+}
+
+/// What the selected ball's panel shows, a line each
+#[derive(Clone, Copy)]
+enum Field {
+    X,
+    Y,
+    VelocityX,
+    VelocityY,
+    Radius,
+    Restitution,
+    Age,
+}
+
+impl Field {
+    const ALL: [Field; 7] = [
+        Field::X,
+        Field::Y,
+        Field::VelocityX,
+        Field::VelocityY,
+        Field::Radius,
+        Field::Restitution,
+        Field::Age,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Field::X => "x (m)",
+            Field::Y => "y (m)",
+            Field::VelocityX => "vx (m/s)",
+            Field::VelocityY => "vy (m/s)",
+            Field::Radius => "radius (m)",
+            Field::Restitution => "restitution",
+            Field::Age => "age (s)",
+        }
+    }
+
+    /// How much each click of - or + changes it, if it can be changed
+    fn step(self) -> Option<Real> {
+        match self {
+            Field::X | Field::Y => Some(0.25),
+            Field::VelocityX | Field::VelocityY => Some(1.),
+            Field::Radius => Some(0.05),
+            Field::Restitution => Some(0.05),
+            Field::Age => None,
+        }
+    }
+
+    fn value(self, ball: &Ball) -> Real {
+        let body = &ball.body;
+        match self {
+            Field::X => body.pos.x,
+            Field::Y => body.pos.y,
+            Field::VelocityX => body.velocity.x,
+            Field::VelocityY => body.velocity.y,
+            Field::Radius => body.radius,
+            Field::Restitution => body.material.restitution,
+            Field::Age => ball.age as Real,
+        }
+    }
+
+    fn nudge(self, ball: &mut Ball, by: Real) {
+        let body = &mut ball.body;
+        match self {
+            Field::X => body.pos.x += by,
+            Field::Y => body.pos.y += by,
+            Field::VelocityX => body.velocity.x += by,
+            Field::VelocityY => body.velocity.y += by,
+            Field::Radius => body.radius = (body.radius + by).max(INSPECTOR_MIN_RADIUS),
+            Field::Restitution => {
+                body.material.restitution = (body.material.restitution + by).clamp(0., 1.)
+            }
+            Field::Age => {}
+        }
+    }
+}
+
+/// The ball picked out with the select tool, and a panel for seeing and changing it as it
+/// goes
+#[derive(Default)]
+struct Selection {
+    ball: Option<ObjectHandle>,
+}
+
+impl Selection {
+    const ROW_HEIGHT: f32 = 18.;
+    const WIDTH: f32 = 200.;
+    const BUTTON: f32 = 14.;
+
+    /// Where the panel goes, down the right of the screen under the dials
+    fn panel(&self) -> mq::Rect {
+        let height = Self::ROW_HEIGHT * (Field::ALL.len() + 1) as f32;
+        mq::Rect::new(
+            mq::screen_width() - Self::WIDTH - 10.,
+            110.,
+            Self::WIDTH,
+            height,
+        )
+    }
+
+    /// The - and + buttons on a line of the panel
+    fn buttons(&self, row: usize) -> (mq::Rect, mq::Rect) {
+        let panel = self.panel();
+        let y = panel.y + Self::ROW_HEIGHT * (row + 1) as f32 + 2.;
+        let plus = mq::Rect::new(
+            panel.right() - Self::BUTTON - 4.,
+            y,
+            Self::BUTTON,
+            Self::BUTTON,
+        );
+        let minus = mq::Rect {
+            x: plus.x - Self::BUTTON - 4.,
+            ..plus
+        };
+        (minus, plus)
+    }
+
+    /// Whether the panel is under `pointer`, so the left button is for that rather than
+    /// whichever tool's picked
+    fn wants_mouse(&self, pointer: mq::Vec2) -> bool {
+        self.ball.is_some() && self.panel().contains(pointer)
+    }
+
+    /// Work the panel's buttons, and pick out the ball under `cursor` if `picking`
+    fn handle_input(
+        &mut self,
+        simulation: &mut Simulation,
+        pointer: mq::Vec2,
+        cursor: Vec2,
+        picking: bool,
+    ) {
+        self.ball = self
+            .ball
+            .filter(|&ball| simulation.get_as::<Ball>(ball).is_some());
+        if !mq::is_mouse_button_pressed(mq::MouseButton::Left) {
+            return;
+        }
+        if let Some(handle) = self.ball.filter(|_| self.panel().contains(pointer)) {
+            let Some(ball) = simulation.get_as_mut::<Ball>(handle) else {
+                return;
+            };
+            for (row, field) in Field::ALL.into_iter().enumerate() {
+                let (minus, plus) = self.buttons(row);
+                let Some(step) = field.step() else {
+                    continue;
+                };
+                if minus.contains(pointer) {
+                    field.nudge(ball, -step);
+                } else if plus.contains(pointer) {
+                    field.nudge(ball, step);
+                }
+            }
+        } else if picking {
+            let hovered = simulation.object_at(cursor);
+            self.ball = hovered.filter(|&handle| simulation.get_as::<Ball>(handle).is_some());
+        }
+    }
+
+    /// A ring around the selected ball, in the world
+    fn draw_highlight(&self, ctx: &mut DrawContext, simulation: &Simulation) {
+        let Some(ball) = self.ball.and_then(|ball| simulation.get_as::<Ball>(ball)) else {
+            return;
+        };
+        let pos = to_mq(ball.body.pos + lead(&ball.body, ctx.lead()));
+        let radius = to_f32(ball.body.radius) + 0.1;
+        ctx.draw_circle_lines(pos.x, pos.y, radius, 0.05, mq::YELLOW);
+    }
+
+    fn draw_panel(&self, simulation: &Simulation) {
+        let Some(ball) = self.ball.and_then(|ball| simulation.get_as::<Ball>(ball)) else {
+            return;
+        };
+        let panel = self.panel();
+        mq::draw_rectangle(
+            panel.x,
+            panel.y,
+            panel.w,
+            panel.h,
+            mq::Color::new(0., 0., 0., 0.7),
+        );
+        mq::draw_rectangle_lines(panel.x, panel.y, panel.w, panel.h, 1., mq::YELLOW);
+        let title_y = panel.y + Self::ROW_HEIGHT - 4.;
+        mq::draw_text("Selected ball", panel.x + 6., title_y, 16., mq::YELLOW);
+        for (row, field) in Field::ALL.into_iter().enumerate() {
+            let y = panel.y + Self::ROW_HEIGHT * (row + 2) as f32 - 4.;
+            let text = format!("{}: {:.2}", field.label(), field.value(ball));
+            mq::draw_text(&text, panel.x + 6., y, 16., mq::WHITE);
+            if field.step().is_none() {
+                continue;
+            }
+            let (minus, plus) = self.buttons(row);
+            for (button, sign) in [(minus, "-"), (plus, "+")] {
+                mq::draw_rectangle_lines(button.x, button.y, button.w, button.h, 1., mq::GRAY);
+                mq::draw_text(sign, button.x + 4., button.y + 11., 16., mq::WHITE);
+            }
+        }
+    }
+}
+
+/// Sketching walls in by hand: with the wall tool picked, dragging with the left button lays
+/// a wall from where the button went down to where it came up, and Backspace takes the last
+/// one back out. Ends snap to the ends of other walls close by, or else to a grid.
+#[derive(Default)]
+struct WallTool {
+    /// Where the wall being dragged out starts
+    start: Option<Vec2>,
+    /// Where the walls drawn so far are in the simulation's scenery, oldest first
+    drawn: Vec<usize>,
+}
+
+impl WallTool {
+    /// Undo with Backspace whenever, and lay walls down with the left button if `drawing`
+    fn handle_input(&mut self, simulation: &mut Simulation, cursor: Vec2, drawing: bool) {
+        if Action::UndoWall.is_pressed() {
+            // the newest is always last in the scenery, so the others' places don't move
+            if let Some(index) = self.drawn.pop() {
+                simulation.remove_static(index);
+            }
+        }
+        if !drawing {
+            self.start = None;
+            return;
+        }
+        let point = snap_to_walls(simulation, cursor);
+        if mq::is_mouse_button_pressed(mq::MouseButton::Left) {
+            self.start = Some(point);
+        }
+        if !mq::is_mouse_button_released(mq::MouseButton::Left) {
+            return;
+        }
+        let Some(start) = self.start.take() else {
+            return;
+        };
+        if start.distance(point) >= WALL_MIN_LENGTH {
+            self.drawn.push(simulation.statics().len());
+            simulation.add_static(StaticCollider::Segment(start, point));
+        }
+    }
+
+    /// Where the next end would snap to, and the wall being dragged out
+    fn draw_preview(&self, ctx: &mut DrawContext, simulation: &Simulation, cursor: Vec2) {
+        let point = to_mq(snap_to_walls(simulation, cursor));
+        ctx.draw_circle_lines(point.x, point.y, 0.1, 0.025, mq::ORANGE);
+        if let Some(start) = self.start.map(to_mq) {
+            ctx.draw_line(start.x, start.y, point.x, point.y, 0.05, mq::ORANGE);
+        }
+    }
+}
+
+/// `point` moved onto the nearest end of a wall within snapping distance, so walls drawn by
+/// hand join up, or failing that onto the nearest grid point
+fn snap_to_walls(simulation: &Simulation, point: Vec2) -> Vec2 {
+    let ends = simulation
+        .statics()
+        .iter()
+        .flat_map(|collider| match *collider {
+            StaticCollider::Segment(a, b)
+            | StaticCollider::Platform(a, b)
+            | StaticCollider::Conveyor(a, b, _) => vec![a, b],
+            StaticCollider::Box(_) => vec![],
+        });
+    ends.filter(|end| end.distance(point) < WALL_SNAP_DISTANCE)
+        .min_by(|a, b| a.distance(point).total_cmp(&b.distance(point)))
+        .unwrap_or_else(|| (point / WALL_GRID).round() * WALL_GRID)
+}
+
+/// Sliders for tuning the running simulation, in a window Y opens, rather than editing the
+/// constants up top and building again. Gravity, damping and the tick length are read off
+/// the simulation each frame, so keys that change them show up here too. A new tick length
+/// only takes once it's applied, as each change throws away the rewind history.
+#[derive(Default)]
+struct Settings {
+    open: bool,
+    /// The tick length being dragged to, in milliseconds, until it's applied
+    pending_tick_ms: Option<f32>,
+}
+
+impl Settings {
+    /// Show the window if it's open, and apply whatever was changed in it
+    fn handle_input(&mut self, simulation: &mut Simulation, controls: &mut Controls) {
+        if Action::Settings.is_pressed() {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return;
+        }
+        let gravity = to_f32(simulation.get_gravity().length());
+        let mut strength = gravity;
+        let damping = to_f32(simulation.get_damping());
+        let mut new_damping = damping;
+        let current_tick_ms = (simulation.get_tick_len() * 1000.) as f32;
+        let mut tick_ms = self.pending_tick_ms.unwrap_or(current_tick_ms);
+        let mut apply_tick = false;
+        let mut cooldown = controls.cooldown as f32;
+        let position = mq::vec2(mq::screen_width() - SETTINGS_SIZE.x - 10., 10.);
+        self.open = widgets::Window::new(hash!(), position, SETTINGS_SIZE)
+            .label("Settings")
+            .close_button(true)
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, "World");
+                ui.slider(hash!(), "gravity, m/s²", 0.0..30., &mut strength);
+                ui.slider(hash!(), "damping, /s", 0.0..2., &mut new_damping);
+                ui.slider(hash!(), "tick, ms", 1.0..33., &mut tick_ms);
+                apply_tick = ui.button(None, "Apply tick");
+                ui.label(None, "Launching");
+                let (slowest, fastest) = STREAM_RATE_RANGE;
+                let rate = &mut controls.stream_rate;
+                ui.slider(hash!(), "stream, balls/s", slowest..fastest, rate);
+                ui.slider(hash!(), "cooldown, s", 0.0..1., &mut cooldown);
+            });
+        controls.cooldown = cooldown as f64;
+        if strength != gravity {
+            set_gravity_strength(simulation, strength as Real);
+        }
+        if new_damping != damping {
+            simulation.set_damping(new_damping as Real);
+        }
+        let tick_len = tick_ms as f64 / 1000.;
+        let changed = (tick_len - simulation.get_tick_len()).abs() > 1e-6;
+        if apply_tick {
+            if changed {
+                simulation.set_tick_len(tick_len);
+            }
+            self.pending_tick_ms = None;
+        } else if changed {
+            self.pending_tick_ms = Some(tick_ms);
+        }
+    }
+}
+
+/// What the next ball launched will be like, in a window of its own. Each setting can be
+/// pinned down, so a run of balls comes out alike and an experiment can be repeated, or
+/// left to chance: the radius within a range, the material and colour at random, and the
+/// launch speed and direction jittered. It starts out as random as balls always were.
+struct BallDesign {
+    open: bool,
+    min_radius: f32,
+    max_radius: f32,
+    /// One of rubber, steel and clay at random, rather than `density` and `restitution`
+    random_material: bool,
+    density: f32,
+    restitution: f32,
+    random_color: bool,
+    /// Red, green and blue, from nothing to full
+    color: [f32; 3],
+    /// What the launch speed is multiplied by
+    speed_scale: f32,
+    /// How far the launch speed can stray either way, as a fraction of it
+    speed_jitter: f32,
+    /// How far the launch direction can stray either way, in degrees
+    angle_jitter: f32,
+    expiry_seconds: f32,
+    /// Whether balls also go after `lifetime_seconds`, however lively they still are
+    limited_lifetime: bool,
+    lifetime_seconds: f32,
+}
+
+impl BallDesign {
+    fn new() -> Self {
+        Self {
+            open: false,
+            min_radius: 0.25,
+            max_radius: 0.75,
+            random_material: true,
+            density: to_f32(Material::RUBBER.density),
+            restitution: to_f32(Material::RUBBER.restitution),
+            random_color: true,
+            color: [1., 1., 1.],
+            speed_scale: 1.,
+            speed_jitter: 0.,
+            angle_jitter: 0.,
+            expiry_seconds: BALL_EXPIRY_TIME as f32,
+            limited_lifetime: false,
+            lifetime_seconds: BALL_LIFETIME as f32,
+        }
+    }
+
+    /// Show the window if it's open, which `Action::DesignBall` opens and closes
+    fn handle_input(&mut self) {
+        if Action::DesignBall.is_pressed() {
+            self.open = !self.open;
+        }
+        if !self.open {
+            return;
+        }
+        let position = mq::vec2((mq::screen_width() - BALL_DESIGN_SIZE.x) / 2., 10.);
+        self.open = widgets::Window::new(hash!(), position, BALL_DESIGN_SIZE)
+            .label("Next ball")
+            .close_button(true)
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, "Size");
+                ui.slider(hash!(), "min radius, m", 0.05..2., &mut self.min_radius);
+                ui.slider(hash!(), "max radius, m", 0.05..2., &mut self.max_radius);
+                ui.label(None, "Material");
+                ui.checkbox(hash!(), "random material", &mut self.random_material);
+                ui.slider(hash!(), "density, kg/m²", 0.1..10., &mut self.density);
+                ui.slider(hash!(), "restitution", 0.0..1., &mut self.restitution);
+                ui.label(None, "Colour");
+                ui.checkbox(hash!(), "random colour", &mut self.random_color);
+                let [red, green, blue] = &mut self.color;
+                ui.slider(hash!(), "red", 0.0..1., red);
+                ui.slider(hash!(), "green", 0.0..1., green);
+                ui.slider(hash!(), "blue", 0.0..1., blue);
+                ui.label(None, "Launch");
+                ui.slider(hash!(), "speed ×", 0.0..3., &mut self.speed_scale);
+                ui.slider(hash!(), "speed jitter", 0.0..1., &mut self.speed_jitter);
+                ui.slider(
+                    hash!(),
+                    "angle jitter, °",
+                    0.0..180.,
+                    &mut self.angle_jitter,
+                );
+                ui.label(None, "Lasting");
+                ui.slider(hash!(), "expiry, s", 0.1..10., &mut self.expiry_seconds);
+                ui.checkbox(hash!(), "limited lifetime", &mut self.limited_lifetime);
+                let lifetime = &mut self.lifetime_seconds;
+                ui.slider(hash!(), "lifetime, s", 1.0..120., lifetime);
+            });
+        self.max_radius = self.max_radius.max(self.min_radius);
+    }
+
+    /// A ball to this design, leaving `rng` to settle whatever's left to chance
+    fn ball(&self, rng: &mut Rng, pos: Vec2, velocity: Vec2) -> Ball {
+        let material = if self.random_material {
+            rand_material(rng)
+        } else {
+            Material {
+                density: self.density as Real,
+                restitution: self.restitution as Real,
+                ..Material::RUBBER
+            }
+        };
+        let color = if self.random_color {
+            mq::Color::from_rgba(
+                rng.gen_range(100, 255),
+                rng.gen_range(100, 255),
+                rng.gen_range(100, 255),
+                255,
+            )
+        } else {
+            let [r, g, b] = self.color;
+            mq::Color::new(r, g, b, 1.)
+        };
+        let jitter = self.speed_jitter as Real;
+        let speed = self.speed_scale as Real * (1. + rng.gen_range(-jitter, jitter));
+        let turn = (self.angle_jitter as Real).to_radians();
+        let velocity = Vec2::from_angle(rng.gen_range(-turn, turn)).rotate(velocity) * speed;
+        let expiry = ExpiryPolicy::on_floor(self.expiry_seconds as f64);
+        let expiry = if self.limited_lifetime {
+            expiry.or(ExpiryPolicy::lifetime(self.lifetime_seconds as f64))
+        } else {
+            expiry
+        };
+        Ball {
+            body: RigidBody {
+                pos,
+                velocity: velocity.clamp_length_max(MAX_SPEED),
+                radius: rng.gen_range(self.min_radius as Real, self.max_radius as Real),
+                material,
+                drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                ..Default::default()
+            },
+            color,
+            expiry,
+            trail: Trail::new(BALL_TRAIL_SECONDS),
+            sprite: None,
+            age: 0.,
+        }
+    }
+}
+
+/// The two things everyone reaches for in a demo, gravity and how bouncy everything is, as
+/// a strip of sliders along the bottom that's there whenever the HUD is
+fn draw_quick_sliders(simulation: &mut Simulation) {
+    let gravity = to_f32(simulation.get_gravity().length());
+    let restitution = to_f32(simulation.get_restitution_scale());
+    let (mut new_gravity, mut new_restitution) = (gravity, restitution);
+    let size = QUICK_SLIDERS_SIZE;
+    let position = mq::vec2(
+        (mq::screen_width() - size.x) / 2.,
+        mq::screen_height() - TIMING_GRAPH_SIZE.y - size.y - 34.,
+    );
+    root_ui().window(hash!(), position, size, |ui| {
+        ui.slider(hash!(), "gravity, m/s²", 0.0..30., &mut new_gravity);
+        ui.slider(hash!(), "bounce ×", 0.0..2., &mut new_restitution);
+    });
+    if new_gravity != gravity {
+        set_gravity_strength(simulation, new_gravity as Real);
+    }
+    if new_restitution != restitution {
+        simulation.set_restitution_scale(new_restitution as Real);
+    }
+}
+
+/// Make gravity `strength` strong, keeping whichever way it's been turned, or pointing it
+/// straight down if it's been off
+fn set_gravity_strength(simulation: &mut Simulation, strength: Real) {
+    let down = simulation.get_gravity().try_normalize().unwrap_or(Vec2::Y);
+    simulation.set_gravity(down * strength);
+}
+
+/// Whether the mouse is over one of the settings windows, and so not meant for the
+/// simulation
+fn ui_wants_mouse() -> bool {
+    root_ui().is_mouse_over(mq::mouse_position().into())
+}
+
+/// What the pause menu was asked to do
+#[derive(Clone, Copy)]
+enum MenuChoice {
+    Resume,
+    ClearBalls,
+    Restart,
+    Load,
+    Quit,
+}
+
+/// The menu Escape brings up, with every layer paused for as long as it's open
+#[derive(Default)]
+struct PauseMenu {
+    open: bool,
+    /// Whether things were paused already, to go back to on closing
+    was_paused: bool,
+}
+
+impl PauseMenu {
+    const CHOICES: [(MenuChoice, &'static str); 5] = [
+        (MenuChoice::Resume, "Resume"),
+        (MenuChoice::ClearBalls, "Clear all balls"),
+        (MenuChoice::Restart, "Restart scene"),
+        (MenuChoice::Load, "Load scene"),
+        (MenuChoice::Quit, "Quit"),
+    ];
+
+    /// Open or close the menu with Escape, and show it if it's open. Pressing Escape again
+    /// counts as resuming. Whatever's chosen, `close` puts things back afterwards.
+    fn handle_input(&mut self, scenes: &mut SceneStack, foreground: LayerId) -> Option<MenuChoice> {
+        if Action::Menu.is_pressed() {
+            if self.open {
+                return Some(MenuChoice::Resume);
+            }
+            self.open = true;
+            self.was_paused = scenes[foreground].is_paused();
+            scenes.iter_mut().for_each(|(_, s)| s.set_paused(true));
+        }
+        if !self.open {
+            return None;
+        }
+        let size = PAUSE_MENU_SIZE;
+        let position = (mq::vec2(mq::screen_width(), mq::screen_height()) - size) / 2.;
+        let mut choice = None;
+        widgets::Window::new(hash!(), position, size)
+            .label("Paused")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                for (option, label) in Self::CHOICES {
+                    if ui.button(None, label) {
+                        choice = Some(option);
+                    }
+                }
+            });
+        choice
+    }
+
+    fn close(&mut self, scenes: &mut SceneStack) {
+        self.open = false;
+        let paused = self.was_paused;
+        scenes.iter_mut().for_each(|(_, s)| s.set_paused(paused));
+    }
+}
+
+/// Shine a beam from the corner towards the cursor, stopping at the first body in the way
+fn draw_laser(renderer: &mut dyn Renderer, simulation: &Simulation, cursor: Vec2) {
+    let dir = (cursor - LASER_ORIGIN).normalize_or_zero();
+    let hit = simulation.raycast(LASER_ORIGIN, dir, LASER_RANGE);
+    let end = to_mq(hit.map_or(LASER_ORIGIN + dir * LASER_RANGE, |h| h.point));
+    let origin = to_mq(LASER_ORIGIN);
+    renderer.draw_line(origin.x, origin.y, end.x, end.y, 0.025, mq::RED);
+    if let Some(hit) = hit {
+        let (point, tip) = (to_mq(hit.point), to_mq(hit.point + hit.normal * 0.25));
+        renderer.draw_circle(point.x, point.y, 0.075, mq::RED);
+        renderer.draw_line(point.x, point.y, tip.x, tip.y, 0.025, mq::YELLOW);
+    }
+}
+
+/// Something the number keys drop at the cursor
+struct Preset {
+    /// For the key list on screen
+    name: &'static str,
+    /// Sides, for a block rather than a ball
+    sides: Option<usize>,
+    radius: Real,
+    material: Material,
+    color: mq::Color,
+    /// Fired off in a random direction this fast, in m/s
+    speed: Real,
+    gravity_scale: Real,
+    /// How many, packed in a clump around the cursor
+    count: usize,
+}
+
+impl Preset {
+    const fn ball(name: &'static str, radius: Real, material: Material, color: mq::Color) -> Self {
+        Self {
+            name,
+            sides: None,
+            radius,
+            material,
+            color,
+            speed: 0.,
+            gravity_scale: 1.,
+            count: 1,
+        }
+    }
+
+    const fn block(name: &'static str, sides: usize, radius: Real, color: mq::Color) -> Self {
+        Self {
+            sides: Some(sides),
+            ..Self::ball(name, radius, Material::CLAY, color)
+        }
+    }
+
+    const fn speed(self, speed: Real) -> Self {
+        Self { speed, ..self }
+    }
+
+    const fn gravity_scale(self, gravity_scale: Real) -> Self {
+        Self {
+            gravity_scale,
+            ..self
+        }
+    }
+
+    const fn count(self, count: usize) -> Self {
+        Self { count, ..self }
+    }
+
+    /// Drop this preset's objects around `at`
+    fn spawn(&self, simulation: &mut Simulation, at: Vec2) {
+        // a sunflower spiral, which packs a clump evenly without overlaps
+        const GOLDEN_ANGLE: Real = 2.4;
+        for k in 0..self.count {
+            let spread = 2.2 * self.radius * (k as Real).sqrt();
+            let pos = at + Vec2::from_angle(k as Real * GOLDEN_ANGLE) * spread;
+            let rng = simulation.rng();
+            let velocity = Vec2::from_angle(rng.gen_range(0., 2. * PI)) * self.speed;
+            let Some(sides) = self.sides else {
+                simulation.add_pooled(Ball {
+                    body: RigidBody {
+                        pos,
+                        velocity,
+                        radius: self.radius,
+                        material: self.material,
+                        drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                        gravity_scale: self.gravity_scale,
+                        ..Default::default()
+                    },
+                    color: self.color,
+                    expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+                    trail: Trail::new(BALL_TRAIL_SECONDS),
+                    sprite: None,
+                    age: 0.,
+                });
+                continue;
+            };
+            let mut block = Block::regular(pos, sides, self.radius, self.color);
+            block.body.velocity = velocity;
+            block.body.material = self.material;
+            block.body.gravity_scale = self.gravity_scale;
+            simulation.add_object(Box::from(block));
+        }
+    }
+}
+
+/// Take the ball under `cursor` out of the world in a puff of its own colour, if there's one
+/// there to pop
+fn pop_ball(simulation: &mut Simulation, cursor: Vec2) -> bool {
+    let hovered = simulation.object_at(cursor);
+    let Some(handle) = hovered.filter(|&h| simulation.get_as::<Ball>(h).is_some()) else {
+        return false;
+    };
+    let Some(ball) = simulation.remove_object(handle) else {
+        return false;
+    };
+    let Some(ball) = ball.as_any().downcast_ref::<Ball>() else {
+        return false;
+    };
+    for k in 0..POP_PARTICLES {
+        let rng = simulation.rng();
+        let angle = (k as Real + rng.gen_range(0., 1.)) / POP_PARTICLES as Real * 2. * PI;
+        let out = Vec2::from_angle(angle);
+        let speed = rng.gen_range(POP_SPEED.0, POP_SPEED.1);
+        simulation.spawn(particle(
+            ball.body.pos + out * ball.body.radius,
+            ball.body.velocity + out * speed,
+            ball.color,
+        ));
+    }
+    true
+}
+
+/// Dragging balls about: pressing the left button on one while `grabbing` hangs it from the
+/// cursor on a spring until the button's let go
+fn handle_grab(simulation: &mut Simulation, cursor: Vec2, grabbing: bool) {
+    if grabbing && mq::is_mouse_button_pressed(mq::MouseButton::Left) {
+        let hovered = simulation.object_at(cursor);
+        let ball = hovered.filter(|&handle| simulation.get_as::<Ball>(handle).is_some());
+        simulation.set_mouse_joint(ball.map(|body| MouseJoint {
+            body,
+            target: cursor,
+            stiffness: GRAB_STIFFNESS,
+            damping: GRAB_DAMPING,
+        }));
+    }
+    if !mq::is_mouse_button_down(mq::MouseButton::Left) {
+        simulation.set_mouse_joint(None);
+    }
+    if let Some(joint) = simulation.mouse_joint_mut() {
+        joint.target = cursor;
+    }
+}
+
+/// Knock everything within reach of `pos` away from it, with a flash to show where
+fn blast(simulation: &mut Simulation, pos: Vec2) {
+    simulation.apply_radial_impulse(pos, EXPLOSION_RADIUS, EXPLOSION_STRENGTH);
+    simulation.spawn(blast_ring(pos));
+}
+
+/// What the left button does
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+enum Tool {
+    /// Drop a ball where it's clicked
+    Spawn,
+    /// Pull back from where the button went down and let go to launch
+    #[default]
+    Slingshot,
+    Grab,
+    /// Pop the ball clicked on
+    Delete,
+    Explode,
+    Wall,
+    Select,
+}
+
+impl Tool {
+    const ALL: [Tool; 7] = [
+        Tool::Spawn,
+        Tool::Slingshot,
+        Tool::Grab,
+        Tool::Delete,
+        Tool::Explode,
+        Tool::Wall,
+        Tool::Select,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Tool::Spawn => "spawn",
+            Tool::Slingshot => "sling",
+            Tool::Grab => "grab",
+            Tool::Delete => "delete",
+            Tool::Explode => "blast",
+            Tool::Wall => "wall",
+            Tool::Select => "select",
+        }
+    }
+
+    /// The key that picks it without going to the toolbar, if it has one
+    fn key(self) -> Option<mq::KeyCode> {
+        match self {
+            Tool::Wall => Some(mq::KeyCode::J),
+            Tool::Select => Some(mq::KeyCode::Z),
+            _ => None,
+        }
+    }
+
+    fn color(self) -> mq::Color {
+        match self {
+            Tool::Spawn | Tool::Slingshot => mq::WHITE,
+            Tool::Grab => mq::LIGHTGRAY,
+            Tool::Delete => mq::PINK,
+            Tool::Explode => mq::ORANGE,
+            Tool::Wall => mq::GOLD,
+            Tool::Select => mq::YELLOW,
+        }
+    }
+}
+
+/// A row of buttons for picking the tool, under the HUD text
+#[derive(Default)]
+struct Toolbar {
+    tool: Tool,
+}
+
+impl Toolbar {
+    const ORIGIN: mq::Vec2 = mq::vec2(5., 252.);
+    const BUTTON: mq::Vec2 = mq::vec2(62., 22.);
+
+    fn button(&self, index: usize) -> mq::Rect {
+        let x = Self::ORIGIN.x + (Self::BUTTON.x + 4.) * index as f32;
+        mq::Rect::new(x, Self::ORIGIN.y, Self::BUTTON.x, Self::BUTTON.y)
+    }
+
+    fn wants_mouse(&self, pointer: mq::Vec2) -> bool {
+        (0..Tool::ALL.len()).any(|i| self.button(i).contains(pointer))
+    }
+
+    /// Pick a tool by clicking its button or pressing its key. Pressing the key of the tool
+    /// that's already picked goes back to the slingshot.
+    fn handle_input(&mut self, pointer: mq::Vec2) {
+        for (i, tool) in Tool::ALL.into_iter().enumerate() {
+            let clicked = mq::is_mouse_button_pressed(mq::MouseButton::Left)
+                && self.button(i).contains(pointer);
+            if clicked {
+                self.tool = tool;
+            }
+            if tool.key().is_some_and(mq::is_key_pressed) {
+                self.tool = if self.tool == tool {
+                    Tool::default()
+                } else {
+                    tool
+                };
+            }
+        }
+    }
+
+    fn draw(&self) {
+        for (i, tool) in Tool::ALL.into_iter().enumerate() {
+            let button = self.button(i);
+            let picked = tool == self.tool;
+            if picked {
+                let fill = mq::Color {
+                    a: 0.3,
+                    ..tool.color()
+                };
+                mq::draw_rectangle(button.x, button.y, button.w, button.h, fill);
+            }
+            let edge = if picked { tool.color() } else { mq::GRAY };
+            mq::draw_rectangle_lines(button.x, button.y, button.w, button.h, 1., edge);
+            let text = match tool.key() {
+                Some(key) => format!("{} {key:?}", tool.label()),
+                None => tool.label().to_string(),
+            };
+            mq::draw_text(&text, button.x + 4., button.y + 15., 16., mq::WHITE);
+        }
+    }
+
+    /// The picked tool's name by the pointer, so it's clear what a click will do
+    fn draw_cursor(&self, pointer: mq::Vec2) {
+        let tool = self.tool;
+        mq::draw_circle_lines(pointer.x, pointer.y, 6., 1., tool.color());
+        mq::draw_text(
+            tool.label(),
+            pointer.x + 10.,
+            pointer.y + 18.,
+            16.,
+            tool.color(),
+        );
+    }
+}
+
+/// What the player asked for this frame, whether with the mouse and keyboard, a gamepad or
+/// a touch screen
+#[derive(Default)]
+struct Commands {
+    /// Fire a ball off from a spot at a velocity
+    launch: Option<(mq::Vec2, mq::Vec2)>,
+    /// Where a ball would go from and how fast, while one's being lined up
+    aim: Option<(mq::Vec2, mq::Vec2)>,
+    /// Drop this one of `SPAWN_PRESETS` at the cursor
+    spawn: Option<usize>,
+    /// Pop whatever ball is here
+    pop: Option<mq::Vec2>,
+    toggle_pause: bool,
+}
+
+/// A finger on the screen, until it's clear whether it's a tap, a drag or a long press
+struct Press {
+    id: u64,
+    /// Where it went down, on screen and then in the world
+    start: mq::Vec2,
+    from: Option<mq::Vec2>,
+    since: f64,
+    /// Whether it's wandered far enough from `start` to be a drag
+    moved: bool,
+    /// Whether it's been held long enough to pop what's under it, which it only does once
+    popped: bool,
+}
+
+/// How two fingers moved apart and along since the frame before, for zooming and panning
+struct Pinch {
+    center: mq::Vec2,
+    zoom: f32,
+    pan: mq::Vec2,
+}
+
+/// The player's hands. Launching, dropping presets, pausing and pointing all come through
+/// here, so the sandbox doesn't need to know whether it was the mouse and keyboard, a gamepad
+/// or a touch screen: slingshot with the mouse or a finger, or aim with the left stick and
+/// fire on A, trigger for speed; 1 to 9 or the bumpers and X for presets; space or start to
+/// pause; and the mouse, a finger or the right stick to point. A tap drops a ball where it
+/// lands, a long press pops one, and two fingers pinch to zoom and drag to pan.
+struct Controls {
+    gamepad: Gamepad,
+    /// Where on screen the player's pointing: the mouse, unless the right stick has moved
+    /// it on since
+    pointer: mq::Vec2,
+    last_mouse: mq::Vec2,
+    /// Where the ball being pulled back with the mouse will launch from
+    launch_from: Option<mq::Vec2>,
+    /// Which way the left stick last pointed, so letting go of it doesn't lose the aim
+    aim: mq::Vec2,
+    /// The preset the gamepad drops
+    preset: usize,
+    press: Option<Press>,
+    /// Whether `press`'s finger came up this frame
+    press_ended: bool,
+    /// Where two fingers' midpoint was and how far apart they were last frame
+    last_pinch: Option<(mq::Vec2, f32)>,
+    /// What two fingers did this frame, for the camera
+    pinch: Option<Pinch>,
+    /// Whether holding the left button pours out a stream of balls, rather than pulling one
+    /// back to launch
+    streaming: bool,
+    /// Balls per second while streaming
+    stream_rate: f32,
+    /// How far towards the next ball in the stream
+    stream_due: f32,
+    /// Where the cursor was last frame, for flinging streamed balls along with it
+    last_cursor: mq::Vec2,
+    /// The shortest gap between any two balls, in seconds
+    cooldown: f64,
+    last_launch: f64,
+}
+
+impl Controls {
+    fn new() -> Self {
+        // touches are read as themselves, rather than as clicks of a mouse
+        mq::simulate_mouse_with_touch(false);
+        let mouse = mq::mouse_position().into();
+        Self {
+            gamepad: Gamepad::open(),
+            pointer: mouse,
+            last_mouse: mouse,
+            launch_from: None,
+            aim: mq::vec2(1., -1.).normalize(),
+            preset: 0,
+            press: None,
+            press_ended: false,
+            last_pinch: None,
+            pinch: None,
+            streaming: false,
+            stream_rate: STREAM_RATE,
+            stream_due: 0.,
+            last_cursor: mq::Vec2::ZERO,
+            cooldown: arg_after("--spawn-cooldown")
+                .and_then(|seconds| seconds.parse().ok())
+                .unwrap_or(SPAWN_COOLDOWN_SECONDS),
+            last_launch: f64::MIN,
+        }
+    }
+
+    /// Balls streamed from the cursor while the left button's held, moving as it moves
+    fn stream(&mut self, cursor: mq::Vec2, holding: bool) -> Option<(mq::Vec2, mq::Vec2)> {
+        if Action::Stream.is_pressed() {
+            self.streaming = !self.streaming;
+        }
+        if Action::StreamSlower.is_pressed() {
+            self.stream_rate = (self.stream_rate / 2.).max(STREAM_RATE_RANGE.0);
+        }
+        if Action::StreamFaster.is_pressed() {
+            self.stream_rate = (self.stream_rate * 2.).min(STREAM_RATE_RANGE.1);
+        }
+        let dt = mq::get_frame_time();
+        let velocity = (cursor - self.last_cursor) / dt.max(f32::EPSILON);
+        self.last_cursor = cursor;
+        if !self.streaming || holding || !mq::is_mouse_button_down(mq::MouseButton::Left) {
+            // so the first ball comes out as soon as the button goes down
+            self.stream_due = 1.;
+            return None;
+        }
+        // one a frame at most, so a slow frame doesn't dump a clump all at once
+        self.stream_due = (self.stream_due + self.stream_rate * dt).min(1.);
+        if self.stream_due < 1. {
+            return None;
+        }
+        self.stream_due -= 1.;
+        Some((cursor, velocity.clamp_length_max(to_f32(MAX_SPEED))))
+    }
+
+    fn update_touches(&mut self) {
+        self.pinch = None;
+        self.press_ended = false;
+        // the platform's order isn't stable from frame to frame; ids are, so the pinch
+        // always follows the same two fingers
+        let mut touches = mq::touches();
+        touches.sort_by_key(|touch| touch.id);
+        match touches.as_slice() {
+            [] => self.last_pinch = None,
+            [touch] => {
+                self.pointer = touch.position;
+                self.last_pinch = None;
+                if touch.phase == mq::TouchPhase::Started {
+                    self.press = Some(Press {
+                        id: touch.id,
+                        start: touch.position,
+                        from: None,
+                        since: mq::get_time(),
+                        moved: false,
+                        popped: false,
+                    });
+                }
+                let Some(press) = self.press.as_mut().filter(|p| p.id == touch.id) else {
+                    return;
+                };
+                press.moved |= touch.position.distance(press.start) > TAP_SLOP;
+                self.press_ended = matches!(
+                    touch.phase,
+                    mq::TouchPhase::Ended | mq::TouchPhase::Cancelled
+                );
+            }
+            [a, b, ..] => {
+                // a second finger makes it a pinch, whatever the first was up to
+                self.press = None;
+                let (center, span) = (
+                    (a.position + b.position) / 2.,
+                    a.position.distance(b.position),
+                );
+                if let Some((last_center, last_span)) = self.last_pinch {
+                    self.pinch = Some(Pinch {
+                        center,
+                        zoom: span / last_span.max(1.),
+                        pan: center - last_center,
+                    });
+                }
+                self.last_pinch = Some((center, span));
+            }
+        }
+    }
+
+    /// Catch up with the devices and move the pointer. Call once a frame, before asking for
+    /// `commands`.
+    fn update(&mut self) {
+        let mouse: mq::Vec2 = mq::mouse_position().into();
+        if mouse != self.last_mouse {
+            self.pointer = mouse;
+        }
+        self.last_mouse = mouse;
+        self.update_touches();
+        let pad = &mut self.gamepad;
+        pad.update();
+        if !pad.is_connected() {
+            return;
+        }
+        let nudge = pad.stick(gamepad::RIGHT_X, gamepad::RIGHT_Y);
+        let screen = mq::vec2(mq::screen_width(), mq::screen_height());
+        self.pointer = (self.pointer + nudge * GAMEPAD_POINTER_SPEED * mq::get_frame_time())
+            .clamp(mq::Vec2::ZERO, screen);
+        let stick = pad.stick(gamepad::LEFT_X, gamepad::LEFT_Y);
+        if stick != mq::Vec2::ZERO {
+            self.aim = stick.normalize();
+        }
+    }
+
+    /// What's been asked for, with the pointer over `cursor` in the world. `tool` is what
+    /// the left button's for, or nothing if it's busy with the panels and toolbar; only the
+    /// spawn tool and the slingshot launch anything with it.
+    fn commands(&mut self, cursor: mq::Vec2, tool: Option<Tool>) -> Commands {
+        let launching = matches!(tool, Some(Tool::Spawn | Tool::Slingshot));
+        let mut commands = Commands {
+            launch: self.stream(cursor, !launching),
+            ..Default::default()
+        };
+        let clicked = mq::is_mouse_button_pressed(mq::MouseButton::Left) && !self.streaming;
+        if clicked && tool == Some(Tool::Spawn) {
+            commands.launch = Some((cursor, mq::Vec2::ZERO));
+        }
+        if clicked && tool == Some(Tool::Slingshot) {
+            self.launch_from = Some(cursor);
+        }
+        if let Some(from) = self.launch_from {
+            commands.aim = Some((from, launch_velocity(from, cursor)));
+            if mq::is_mouse_button_released(mq::MouseButton::Left) {
+                commands.launch = commands.aim;
+                self.launch_from = None;
+            }
+        }
+        if let Some(press) = &mut self.press {
+            let from = *press.from.get_or_insert(cursor);
+            if press.moved {
+                commands.aim = Some((from, launch_velocity(from, cursor)));
+            } else if !press.popped && mq::get_time() - press.since >= LONG_PRESS_SECONDS {
+                commands.pop = Some(from);
+                press.popped = true;
+            }
+            if self.press_ended {
+                if press.moved {
+                    commands.launch = commands.aim;
+                } else if !press.popped {
+                    commands.launch = Some((from, mq::Vec2::ZERO));
+                }
+                self.press = None;
+            }
+        }
+        commands.spawn = PRESET_KEYS.iter().position(|&k| mq::is_key_pressed(k));
+        commands.toggle_pause = Action::Pause.is_pressed();
+        self.gamepad_commands(&mut commands, cursor);
+        // however they're asked for, balls come no closer together than the cooldown
+        let now = mq::get_time();
+        if commands.launch.is_some() {
+            if now - self.last_launch < self.cooldown {
+                commands.launch = None;
+            } else {
+                self.last_launch = now;
+            }
+        }
+        commands
+    }
+
+    fn gamepad_commands(&mut self, commands: &mut Commands, cursor: mq::Vec2) {
+        let pad = &self.gamepad;
+        if !pad.is_connected() {
+            return;
+        }
+        let presets = SPAWN_PRESETS.len();
+        if pad.is_button_pressed(gamepad::RIGHT_BUMPER) {
+            self.preset = (self.preset + 1) % presets;
+        }
+        if pad.is_button_pressed(gamepad::LEFT_BUMPER) {
+            self.preset = (self.preset + presets - 1) % presets;
+        }
+        if pad.is_button_pressed(gamepad::X) {
+            commands.spawn = Some(self.preset);
+        }
+        let speed = pad.trigger(gamepad::RIGHT_TRIGGER) * GAMEPAD_LAUNCH_SPEED;
+        if speed > 0. && commands.aim.is_none() {
+            commands.aim = Some((cursor, self.aim * speed));
+        }
+        if pad.is_button_pressed(gamepad::A) {
+            commands.launch = Some((cursor, self.aim * speed));
+        }
+        commands.toggle_pause |= pad.is_button_pressed(gamepad::START);
+    }
+
+    /// The preset the gamepad drops, while there's a gamepad to drop it with
+    fn gamepad_preset(&self) -> Option<&'static Preset> {
+        self.gamepad
+            .is_connected()
+            .then(|| &SPAWN_PRESETS[self.preset])
+    }
+}
+
+/// Away from the cursor, faster the further back it's been pulled
+fn launch_velocity(from: mq::Vec2, cursor: mq::Vec2) -> mq::Vec2 {
+    ((from - cursor) * LAUNCH_SPEED_PER_METER).clamp_length_max(to_f32(MAX_SPEED))
+}
+
+/// A slingshot's band pulled back from the launch spot, and an arrow the way the ball will go
+fn draw_launch_preview(ctx: &mut DrawContext, from: mq::Vec2, velocity: mq::Vec2) {
+    let band = from - velocity / LAUNCH_SPEED_PER_METER;
+    ctx.draw_line(from.x, from.y, band.x, band.y, 0.025, mq::GRAY);
+    let style = ArrowStyle {
+        dash: Some((6., 4.)),
+        color: ArrowColor::ByMagnitude {
+            gradient: Gradient::heat(),
+            max: SPEED_COLORING_TOP_SPEED,
+        },
+        length_per_unit: 1. / LAUNCH_SPEED_PER_METER,
+        ..Default::default()
+    };
+    style.draw(ctx, from, velocity);
+}
+
+/// The window, measured in metres from the world's origin
+fn window_bounds() -> Aabb {
+    Aabb {
+        min: Vec2::ZERO,
+        max: from_mq(mq::vec2(mq::screen_width(), mq::screen_height()) / PIXELS_PER_METER),
+    }
+}
+
+/// A closed loop around `bounds`, going down the left side first, the way the sandbox's
+/// walls are laid out
+fn box_corners(bounds: Aabb) -> [Vec2; 5] {
+    [
+        bounds.min,
+        vec2(bounds.min.x, bounds.max.y),
+        bounds.max,
+        vec2(bounds.max.x, bounds.min.y),
+        bounds.min,
+    ]
+}
 
-        pub trait Expire {
-            fn is_expired(&self) -> bool;
-        }
+/// Moves the walls added from `corners`'s chain, starting at static `first`, to run along
+/// `corners` instead
+fn set_walls(simulation: &mut Simulation, first: usize, corners: &[Vec2]) {
+    let walls = &mut simulation.statics_mut()[first..];
+    for (wall, moved) in walls.iter_mut().zip(StaticCollider::chain(corners)) {
+        *wall = moved;
+    }
+}
 
-        pub trait TickDrawExpire: Tick + Draw + Expire {}
+/// The ball under `cursor` to follow, or any ball at all if there isn't one there
+fn pick_followed(simulation: &Simulation, cursor: Vec2) -> Option<ObjectHandle> {
+    let is_ball = |handle: &ObjectHandle| simulation.get_as::<Ball>(*handle).is_some();
+    let hovered = simulation.object_at(cursor).filter(is_ball);
+    hovered.or_else(|| simulation.objects().map(|(handle, _)| handle).find(is_ball))
+}
 
-        pub struct Simulation {
-            seconds_per_tick: f64,
-            objects: Vec<Box<dyn TickDrawExpire>>,
-            tick_count: usize,
-        }
+/// Where a ball is drawn this frame, if it's still around
+fn drawn_ball_pos(simulation: &Simulation, ball: Option<ObjectHandle>) -> Option<mq::Vec2> {
+    let ball = simulation.get_as::<Ball>(ball?)?;
+    Some(to_mq(
+        ball.body.pos + lead(&ball.body, simulation.lead_seconds()),
+    ))
+}
 
-        impl Simulation {
-            pub fn new(seconds_per_tick: f64) -> Self {
-                Self {
-                    seconds_per_tick,
-                    objects: Vec::new(),
-                    tick_count: 0,
-                }
-            }
+/// The cameras to draw with: just `camera` over the whole window, or with a ball to follow,
+/// `camera` on the left and a closer one kept centred on the ball on the right
+fn split_views(camera: Camera, followed: Option<mq::Vec2>) -> Vec<Camera> {
+    let Some(followed) = followed else {
+        return vec![camera];
+    };
+    let (width, height) = (mq::screen_width() / 2., mq::screen_height());
+    let overview = Camera {
+        viewport: Some(mq::Rect::new(0., 0., width, height)),
+        ..camera
+    };
+    let mut close_up = Camera {
+        zoom: FOLLOW_ZOOM,
+        viewport: Some(mq::Rect::new(width, 0., width, height)),
+        ..Camera::with_pixels_per_meter(PIXELS_PER_METER)
+    };
+    close_up.center_on(followed);
+    vec![overview, close_up]
+}
 
-            pub fn get_tick_count(&self) -> usize {
-                self.tick_count
-            }
+/// Whatever follows `flag` on the command line, if it's there
+fn arg_after(flag: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let at = args.iter().position(|arg| arg == flag)?;
+    args.get(at + 1).cloned()
+}
 
-            pub fn get_object_count(&self) -> usize {
-                self.objects.len()
-            }
+/// The number after `--seed` on the command line, if there is one
+fn seed_arg() -> Option<u64> {
+    arg_after("--seed")?.parse().ok()
+}
 
-            pub fn do_tick(&mut self, time: f64) {
-                let expected_tick_count = (time / self.seconds_per_tick).floor() as usize;
-                let ticks_to_perform = expected_tick_count - self.tick_count;
-                for _ in 0..(ticks_to_perform + 1) {
-                    self.objects
-                        .iter_mut()
-                        .for_each(|o| o.on_tick(self.seconds_per_tick));
-                }
-                self.tick_count += ticks_to_perform;
-            }
+/// A path in `dir` for a new `name` file, named for when it was made, making `dir` first if
+/// it isn't there yet
+fn timestamped_path(dir: &str, name: &str, extension: &str) -> io::Result<String> {
+    std::fs::create_dir_all(dir)?;
+    // miniquad's clock rather than `SystemTime`, which panics in the browser
+    let millis = (macroquad::miniquad::date::now() * 1000.) as u64;
+    Ok(format!("{dir}/{name}-{millis}.{extension}"))
+}
 
-            pub fn do_draw(&self) {
-                self.objects.iter().for_each(|o| o.on_draw())
-            }
+/// Write what's on screen so far this frame to a PNG in `dir`
+fn save_screenshot(dir: &str) -> io::Result<String> {
+    let path = timestamped_path(dir, "screenshot", "png")?;
+    let screen = mq::get_screen_data();
+    let (width, height) = (screen.width as u32, screen.height as u32);
+    // the screen comes bottom row first
+    let rows: Vec<u8> = screen
+        .bytes
+        .chunks_exact(width as usize * 4)
+        .rev()
+        .flatten()
+        .copied()
+        .collect();
+    let mut out = io::BufWriter::new(std::fs::File::create(&path)?);
+    record::write_png(&mut out, width, height, &rows)?;
+    out.flush()?;
+    Ok(path)
+}
 
-            pub fn do_handle_expiry(&mut self) {
-                self.objects.retain(|o| !o.is_expired());
+/// Encode a finished recording into `dir` off the main thread, so the sandbox carries on
+/// while it works, and say on `toasts` once it's done
+fn save_recording(recorder: Recorder, dir: &str, format: RecordFormat, toasts: Toasts) {
+    let path = match timestamped_path(dir, "recording", format.extension()) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("couldn't save a recording to {dir}: {err}");
+            toasts.push(format!("Couldn't save the recording: {err}"));
+            return;
+        }
+    };
+    let frames = recorder.into_frames();
+    println!("encoding {} frames to {path}", frames.len());
+    std::thread::spawn(
+        move || match record::encode(&frames, RECORD_FPS, format, &path) {
+            Ok(()) => {
+                println!("saved {path}");
+                toasts.push("Recording saved");
             }
-
-            pub fn add_object(&mut self, boxed: Box<dyn TickDrawExpire>) {
-                self.objects.push(boxed);
+            Err(err) => {
+                eprintln!("couldn't save {path}: {err}");
+                toasts.push(format!("Couldn't save the recording: {err}"));
             }
+        },
+    );
+}
+
+/// Load the save into the sandbox, saying how it went on `toasts`
+fn load_sandbox(simulation: &mut Simulation, toasts: &Toasts) {
+    match simulation.load(SAVE_PATH) {
+        Ok(()) => toasts.push("Scene loaded"),
+        Err(err) => {
+            eprintln!("couldn't load {SAVE_PATH}: {err}");
+            toasts.push(format!("Couldn't load: {err}"));
         }
     }
+}
 
-    pub mod physics {
-        pub const EARTH_ACCELERATION_M_PER_S: f64 = 9.8;
+/// Hang a chain of small balls off a fixed point, laid out sideways so it swings
+fn add_rope(simulation: &mut Simulation, anchor: Vec2, links: usize, link_len: Real) {
+    let mut prev = ObjectAnchor::Point(anchor);
+    for k in 0..links {
+        let ball = Ball {
+            body: RigidBody {
+                pos: anchor + vec2(link_len * (k + 1) as Real, 0.),
+                velocity: Vec2::ZERO,
+                radius: 0.2,
+                material: Material::STEEL,
+                drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                ..Default::default()
+            },
+            color: mq::LIGHTGRAY,
+            expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+            trail: Trail::new(BALL_TRAIL_SECONDS),
+            sprite: None,
+            age: 0.,
+        };
+        let id = simulation.add_object(Box::from(ball));
+        let next = ObjectAnchor::Object(id);
+        simulation.add_constraint(DistanceConstraint {
+            a: prev,
+            b: next,
+            length: link_len,
+        });
+        prev = next;
     }
 }
 
-struct Ball {
-    pos: mq::Vec2,
-    velocity: mq::Vec2,
-    radius: f32,
-    color: mq::Color,
-    time_on_floor: f64,
+/// Build a squashy ball out of a ring of small nodes held together by springs and pressure
+fn add_soft_ball(simulation: &mut Simulation, center: Vec2, radius: Real, color: mq::Color) {
+    let step = consts::TAU / SOFT_BALL_NODES as Real;
+    let nodes = (0..SOFT_BALL_NODES)
+        .map(|k| {
+            let node = SoftNode {
+                body: RigidBody {
+                    pos: center + Vec2::from_angle(step * k as Real) * radius,
+                    radius: 0.1,
+                    material: Material::RUBBER,
+                    ..Default::default()
+                },
+                expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+            };
+            simulation.add_object(Box::from(node))
+        })
+        .collect();
+    let rest_area = PI * radius * radius;
+    // the gas keeps the skin under tension (pressure times radius, for a hoop), so shorten the
+    // springs by however much that stretches them for the ball to settle at `radius`
+    let edge = 2. * radius * (step / 2.).sin();
+    let stretch = SOFT_BALL_PRESSURE * radius / SOFT_BALL_STIFFNESS;
+    simulation.add_soft_ring(SoftRing {
+        nodes,
+        rest_length: edge - stretch,
+        stiffness: SOFT_BALL_STIFFNESS,
+        damping: SOFT_BALL_DAMPING,
+        gas: SOFT_BALL_PRESSURE * rest_area,
+        color,
+    });
 }
 
-impl Tick for Ball {
-    fn on_tick(&mut self, tick_len_seconds: f64) {
-        // update velocity
-        self.velocity.y +=
-            (tick_len_seconds * EARTH_ACCELERATION_M_PER_S * GRAVITY_MULTIPLIER) as f32;
-        self.pos += self.velocity * tick_len_seconds as f32;
-        if self.pos.y > FLOOR_Y {
-            self.pos.y = FLOOR_Y;
-            self.velocity.y *= -DAMPENING_MULTIPLIER;
-            self.time_on_floor += tick_len_seconds;
-        }
+/// Lets F6 and F9 save and load every kind of object in the sandbox worth keeping
+struct SandboxSaves;
 
-        if self.pos.x > 500. || self.pos.x < 200. {
-            self.pos.x = self.pos.x.clamp(200., 500.);
-            self.velocity.x *= -DAMPENING_MULTIPLIER;
-        }
+impl Plugin for SandboxSaves {
+    fn build(&self, sim: &mut SimulationBuilder) {
+        sim.register_loader::<Ball>(Ball::SAVE_TAG)
+            .register_loader::<Block>(Block::SAVE_TAG)
+            .register_loader::<BoxBody>(BoxBody::SAVE_TAG)
+            .register_loader::<Capsule>(Capsule::SAVE_TAG)
+            .register_loader::<GravityWell>(GravityWell::SAVE_TAG)
+            .register_loader::<Emitter>(Emitter::SAVE_TAG)
+            .register_loader::<SoftNode>(SoftNode::SAVE_TAG);
     }
 }
 
-fn color_with_alpha(color: mq::Color, a: f32) -> mq::Color {
-    mq::Color {
-        r: color.r,
-        g: color.g,
-        b: color.b,
-        a,
+/// The fastest anything has gone so far
+struct TopSpeed(Real);
+
+struct TrackTopSpeed;
+
+impl System for TrackTopSpeed {
+    fn on_step(
+        &mut self,
+        bodies: &mut [(ObjectHandle, &mut RigidBody)],
+        _components: &mut Components,
+        resources: &mut Resources,
+        _step_len: f64,
+    ) {
+        let Some(TopSpeed(top)) = resources.get_mut() else {
+            return;
+        };
+        for (_, body) in bodies.iter() {
+            *top = top.max(body.velocity.length());
+        }
     }
 }
 
-impl Ball {
-    fn get_alpha(&self) -> f32 {
-        ((BALL_EXPIRY_TIME - self.time_on_floor) / BALL_EXPIRY_TIME) as f32
+/// Keeps a record of the top speed for the HUD
+struct SpeedRecord;
+
+/// Whether balls are drawn in a colour for how fast they're going rather than their own,
+/// which shows where the energy is in a crowd at a glance
+struct SpeedColoring {
+    on: bool,
+    gradient: Gradient,
+    /// Speed that gets the far end of the gradient, in metres per second
+    top_speed: f32,
+}
+
+impl Default for SpeedColoring {
+    fn default() -> Self {
+        Self {
+            on: false,
+            gradient: Gradient::heat(),
+            top_speed: SPEED_COLORING_TOP_SPEED,
+        }
     }
 }
-impl Draw for Ball {
-    fn on_draw(&self) {
-        let alpha = self.get_alpha();
-        mq::draw_circle(
-            self.pos.x,
-            self.pos.y,
-            self.radius,
-            color_with_alpha(self.color, alpha),
-        );
-        let circle_center = self.pos;
-        let scaled_velocity = self.velocity * ARROW_LEN_MULTIPLIER;
-        draw_arrow(
-            circle_center.x,
-            circle_center.y,
-            circle_center.x + scaled_velocity.x,
-            circle_center.y + scaled_velocity.y,
-            1.,
-            color_with_alpha(mq::BLUE, alpha),
-            0.2,
-        );
 
-        mq::draw_text(
-            &format!("v: <{:.2},{:.2}>", self.velocity.x, self.velocity.y),
-            10.,
-            50.,
-            15.,
-            mq::RED,
-        );
+impl SpeedColoring {
+    fn color_for(&self, speed: f32) -> mq::Color {
+        self.gradient.at(speed / self.top_speed)
     }
 }
 
-impl Expire for Ball {
-    fn is_expired(&self) -> bool {
-        self.time_on_floor >= BALL_EXPIRY_TIME
+impl Plugin for SpeedRecord {
+    fn build(&self, sim: &mut SimulationBuilder) {
+        sim.insert_resource(TopSpeed(0.))
+            .add_system(Phase::PostTick, Box::new(TrackTopSpeed));
     }
 }
 
-impl TickDrawExpire for Ball {}
+/// Paint a round picture, asking `shade` for the colour at each point of the unit disc with
+/// y pointing down. Everything outside the disc is left clear.
+fn bake_sprite(shade: impl Fn(mq::Vec2) -> mq::Color) -> mq::Texture2D {
+    let mut image = mq::Image::gen_image_color(SPRITE_SIZE, SPRITE_SIZE, mq::BLANK);
+    let size = SPRITE_SIZE as u32;
+    for y in 0..size {
+        for x in 0..size {
+            let p = (mq::vec2(x as f32, y as f32) + 0.5) / size as f32 * 2. - 1.;
+            if p.length() <= 1. {
+                image.set_pixel(x, y, shade(p));
+            }
+        }
+    }
+    mq::Texture2D::from_image(&image)
+}
 
-fn draw_dbg_text(time: f64, ticks_so_far: usize, frames_so_far: usize, object_count: usize) {
-    mq::draw_text(
-            &format!("Time elapsed {:.2}\nTPS: {:.2} (expected {:.2})\nTicks: {}\nFPS: {:.2} (expected {:.2})\nFrames: {}\nObjects: {}",
-                time,
-                ticks_so_far as f64/time,
-                1. / TICK_LEN_SECONDS,
-                ticks_so_far,
-                frames_so_far as f64 / time,
-                mq::get_fps(),
-                frames_so_far,
-            object_count),
-            5.,
-            20.,
-            16.,
-            mq::WHITE,
-        );
+/// A football, a banded planet and a smiley face, made once and shared by every ball wearing
+/// one
+fn bake_sprites() -> Vec<mq::Texture2D> {
+    let football = bake_sprite(|p| {
+        use std::f32::consts::PI;
+        let patches = (0..5).map(|k| mq::Vec2::from_angle(k as f32 * 0.4 * PI - 0.5 * PI) * 0.85);
+        let on_patch = p.length() < 0.3 || patches.into_iter().any(|c| p.distance(c) < 0.28);
+        if on_patch {
+            mq::BLACK
+        } else {
+            mq::WHITE
+        }
+    });
+    let planet = bake_sprite(|p| {
+        let band = ((p.y * 9. + (p.x * 3.).sin()).sin() + 1.) / 2.;
+        // darker towards the rim, which still looks right however it's turned
+        let limb = (1. - p.length_squared()).sqrt() * 0.5 + 0.5;
+        let (light, dark) = (mq::vec3(0.95, 0.7, 0.4), mq::vec3(0.6, 0.35, 0.2));
+        let rgb = dark.lerp(light, band) * limb;
+        mq::Color::new(rgb.x, rgb.y, rgb.z, 1.)
+    });
+    let smiley = bake_sprite(|p| {
+        let eyes = [mq::vec2(-0.35, -0.3), mq::vec2(0.35, -0.3)];
+        let on_eye = eyes.into_iter().any(|e| p.distance(e) < 0.12);
+        let on_mouth = (p.length() - 0.55).abs() < 0.08 && p.y > 0.15;
+        if on_eye || on_mouth || p.length() > 0.93 {
+            mq::BLACK
+        } else {
+            mq::YELLOW
+        }
+    });
+    vec![football, planet, smiley]
 }
 
-fn handle_click<T: FnMut()>(mut callback: T) {
-    if mq::is_mouse_button_down(mq::MouseButton::Left) {
-        callback();
-    }
+fn rand_material(rng: &mut Rng) -> Material {
+    [Material::RUBBER, Material::STEEL, Material::CLAY][rng.gen_range(0, 3)]
 }
 
-fn rand_vec2(xlow: f32, xhigh: f32, ylow: f32, yhigh: f32) -> mq::Vec2 {
-    mq::vec2(
-        mq::rand::gen_range(xlow, xhigh),
-        mq::rand::gen_range(ylow, yhigh),
-    )
+/// How the window starts out, from the command line: `--title`, `--window-size`,
+/// `--fullscreen` and `--no-vsync`
+fn window_conf() -> mq::Conf {
+    let flag = |name: &str| std::env::args().any(|arg| arg == name);
+    let (window_width, window_height) = arg_after("--window-size")
+        .and_then(|size| {
+            let (width, height) = size.split_once('x')?;
+            Some((width.parse().ok()?, height.parse().ok()?))
+        })
+        .unwrap_or(WINDOW_SIZE);
+    mq::Conf {
+        window_title: arg_after("--title").unwrap_or(WINDOW_TITLE.to_string()),
+        window_width,
+        window_height,
+        fullscreen: flag("--fullscreen"),
+        platform: macroquad::miniquad::conf::Platform {
+            swap_interval: Some(if flag("--no-vsync") { 0 } else { 1 }),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
 }
 
-#[macroquad::main("Bouncing Balls")]
-async fn main() {
+/// The sandbox as it starts out, along with where its walls begin among its scenery. It's
+/// built afresh to restart it.
+fn build_sandbox(bounds: Aabb, gravity: Vec2, seed: u64) -> (Simulation, usize) {
     let ball = Ball {
-        pos: mq::Vec2 { x: 400., y: 100. },
-        velocity: mq::Vec2::X * 80.,
-        radius: 15.0,
+        body: RigidBody {
+            pos: Vec2 { x: 10., y: 2.5 },
+            velocity: Vec2::X * 2.,
+            radius: 0.375,
+            material: Material::RUBBER,
+            drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+            ..Default::default()
+        },
         color: mq::WHITE,
-        time_on_floor: 0.,
+        expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+        trail: Trail::new(BALL_TRAIL_SECONDS),
+        sprite: None,
+        age: 0.,
     };
-    let mut simulation = Simulation::new(TICK_LEN_SECONDS);
+    let mut simulation = Simulation::builder()
+        .tick_len(TICK_LEN_SECONDS)
+        .gravity(gravity)
+        .bounds(bounds)
+        .seed(seed)
+        .max_speed(MAX_SPEED)
+        .object_cap(ObjectCap {
+            max: MAX_OBJECTS,
+            eviction: Eviction::Oldest,
+        })
+        .rewind_seconds(REWIND_SECONDS)
+        .threads(std::thread::available_parallelism().map_or(1, |n| n.get()))
+        // gusts that swing back and forth every ten seconds or so
+        .wind(|t| vec2((t * 0.6).sin() as Real, 0.))
+        .add_plugin(&SandboxSaves)
+        .add_plugin(&SpeedRecord)
+        .insert_resource(SpeedColoring::default())
+        .build();
     simulation.add_object(Box::from(ball));
+    // a funnel that steers everything towards a gap in the middle
+    let funnel_left = [vec2(5., 9.5), vec2(7.75, 11.)];
+    let funnel_right = [vec2(12.5, 9.5), vec2(9.75, 11.)];
+    simulation.add_static(StaticCollider::Platform(vec2(5.5, 7.5), vec2(7.5, 7.5)));
+    simulation.add_fluid(FluidRegion {
+        bounds: Aabb {
+            min: vec2(5., 11.25),
+            max: vec2(12.5, FLOOR_Y),
+        },
+        density: WATER_DENSITY,
+    });
+    // carries things left to drop them into the funnel
+    simulation.add_static(StaticCollider::Conveyor(
+        vec2(12., 8.25),
+        vec2(8.5, 8.25),
+        2.5,
+    ));
+    let first_wall = simulation.statics().len();
+    for collider in StaticCollider::chain(&SANDBOX_WALLS)
+        .chain(StaticCollider::chain(&funnel_left))
+        .chain(StaticCollider::chain(&funnel_right))
+    {
+        simulation.add_static(collider);
+    }
+    add_rope(&mut simulation, vec2(6.5, 1.5), 4, 0.625);
+    // a paddle on an axle that balls can set spinning
+    let paddle_center = vec2(10.5, 6.25);
+    let paddle = Block::plank(paddle_center, vec2(1., 0.125), mq::BROWN);
+    let paddle = simulation.add_object(Box::from(paddle));
+    simulation.add_hinge(HingeJoint {
+        body: paddle,
+        pivot: Vec2::ZERO,
+        anchor: HingeAnchor::World(paddle_center),
+    });
+    (simulation, first_wall)
+}
+
+#[macroquad::main(window_conf)]
+async fn main() {
+    let world_bounds = window_bounds();
+    let default_gravity = Vec2::Y * EARTH_ACCELERATION_M_PER_S as Real;
+    let seed = seed_arg();
+    // miniquad's clock, as `SystemTime` panics in the browser, where there are no arguments
+    let seed = seed.unwrap_or_else(|| (macroquad::miniquad::date::now() * 1e9) as u64);
+    let (simulation, first_wall) = build_sandbox(world_bounds, default_gravity, seed);
+
+    let mut background = Simulation::builder()
+        .tick_len(BACKGROUND_TICK_LEN_SECONDS)
+        .seed(seed)
+        .build();
+    StaticCollider::chain(&box_corners(world_bounds)).for_each(|wall| background.add_static(wall));
+    for _ in 0..BACKGROUND_MOTES {
+        let rng = background.rng();
+        let mote = Mote {
+            body: RigidBody {
+                pos: rng.rand_vec2(0., world_bounds.max.x, 0., world_bounds.max.y),
+                velocity: rng.rand_vec2(-0.5, 0.5, -0.5, 0.5),
+                radius: rng.gen_range(0.05, 0.15),
+                material: Material::RUBBER,
+                ..Default::default()
+            },
+        };
+        background.add_object(Box::from(mote));
+    }
+    let mut scenes = SceneStack::new();
+    let backdrop = scenes.push_layer(background);
+    let foreground = scenes.push_layer(simulation);
+    // anything in the sandbox can put a message up through its resources
+    let toasts = Toasts::new();
+    scenes[foreground].insert_resource(toasts.clone());
 
     let mut frames_so_far = 0;
+    let mut timings = VecDeque::new();
+    let mut stats_history = VecDeque::new();
+    let mut integrator_index = 0;
+    let mut show_energy = false;
+    let mut show_help = false;
+    let sprites = bake_sprites();
+    let mut overlays = Overlays::default();
+    let screenshot_dir = arg_after("--screenshot-dir").unwrap_or(SCREENSHOT_DIR.to_string());
+    let record_format = arg_after("--record-format")
+        .and_then(|name| RecordFormat::parse(&name))
+        .unwrap_or(RecordFormat::Gif);
+    let mut recorder: Option<Recorder> = None;
+    let mut sprite_balls = false;
+    let mut glow = Glow::new(GLOW_DOWNSCALE)
+        .map_err(|err| eprintln!("glow is off, as its shaders didn't load: {err}"))
+        .ok();
+    let mut glow_on = false;
+    let mut fullscreen = std::env::args().any(|arg| arg == "--fullscreen");
+    let mut fit_window = std::env::args().any(|arg| arg == "--fit-window");
+    let mut window_size = mq::Vec2::ZERO;
+    let mut camera = Camera::with_pixels_per_meter(PIXELS_PER_METER);
+    let mut controls = Controls::new();
+    let mut selection = Selection::default();
+    let mut settings = Settings::default();
+    let mut ball_design = BallDesign::new();
+    let mut wall_tool = WallTool::default();
+    let mut pause_menu = PauseMenu::default();
+    let mut toolbar = Toolbar::default();
+    // the ball the right half follows while the window is split
+    let mut followed: Option<ObjectHandle> = None;
+    let mut last_mouse: mq::Vec2 = mq::mouse_position().into();
 
     loop {
-        // Handle Inputs
-        handle_click(|| {
-            let b = Ball {
-                pos: rand_vec2(200., 400., 200., 400.),
-                velocity: rand_vec2(5., 50., 0., 0.),
-                radius: mq::rand::gen_range(10., 30.),
-                color: mq::Color::from_rgba(
-                    mq::rand::gen_range(100, 255),
-                    mq::rand::gen_range(100, 255),
-                    mq::rand::gen_range(100, 255),
-                    255,
-                ),
-                time_on_floor: 0.,
+        // Handle Inputs, which only ever reach the foreground
+        let mouse: mq::Vec2 = mq::mouse_position().into();
+        controls.update();
+        let pointer = controls.pointer;
+        if let Some(pinch) = &controls.pinch {
+            camera.pan(pinch.pan);
+            camera.zoom_about(pinch.center, pinch.zoom);
+        }
+        let (_, wheel) = mq::mouse_wheel();
+        if wheel != 0. {
+            camera.zoom_about(mouse, ZOOM_STEP.powf(wheel.signum()));
+        }
+        if mq::is_mouse_button_down(mq::MouseButton::Middle) {
+            camera.pan(mouse - last_mouse);
+        }
+        if Action::ResetView.is_pressed() {
+            camera = Camera::with_pixels_per_meter(PIXELS_PER_METER);
+        }
+        if Action::Fullscreen.is_pressed() {
+            fullscreen = !fullscreen;
+            mq::set_fullscreen(fullscreen);
+        }
+        if let Some(choice) = pause_menu.handle_input(&mut scenes, foreground) {
+            match choice {
+                MenuChoice::Resume => {}
+                MenuChoice::ClearBalls => {
+                    scenes[foreground].clear();
+                    toasts.push("Cleared");
+                }
+                MenuChoice::Restart => {
+                    let (sandbox, _) = build_sandbox(world_bounds, default_gravity, seed);
+                    scenes.replace_layer(foreground, sandbox);
+                    scenes[foreground].insert_resource(toasts.clone());
+                    toasts.push("Scene restarted");
+                    wall_tool = WallTool::default();
+                    selection = Selection::default();
+                    followed = None;
+                    // so the new walls get fitted to the window if they're meant to be
+                    window_size = mq::Vec2::ZERO;
+                }
+                MenuChoice::Load => load_sandbox(&mut scenes[foreground], &toasts),
+                MenuChoice::Quit => break,
+            }
+            pause_menu.close(&mut scenes);
+        }
+        let refit = Action::FitWindow.is_pressed();
+        fit_window ^= refit;
+        let resized = window_size != mq::vec2(mq::screen_width(), mq::screen_height());
+        if resized || refit {
+            window_size = mq::vec2(mq::screen_width(), mq::screen_height());
+            let bounds = window_bounds();
+            scenes[backdrop].set_bounds(bounds);
+            set_walls(&mut scenes[backdrop], 0, &box_corners(bounds));
+            if fit_window {
+                scenes[foreground].set_bounds(bounds);
+                set_walls(&mut scenes[foreground], first_wall, &box_corners(bounds));
+            } else if refit {
+                scenes[foreground].set_bounds(world_bounds);
+                set_walls(&mut scenes[foreground], first_wall, &SANDBOX_WALLS);
+            }
+        }
+        if Action::Follow.is_pressed() {
+            followed = match followed {
+                Some(_) => None,
+                None => {
+                    let cursor = from_mq(camera.screen_to_world(pointer));
+                    pick_followed(&scenes[foreground], cursor)
+                }
             };
-            simulation.add_object(Box::from(b));
+        }
+        // back to one view once the ball's gone
+        followed = followed.filter(|&ball| scenes[foreground].get_as::<Ball>(ball).is_some());
+        last_mouse = mouse;
+        // whichever view the pointer is over says where in the world it's pointing
+        let views = split_views(camera, drawn_ball_pos(&scenes[foreground], followed));
+        let view = views
+            .iter()
+            .rfind(|view| view.viewport.is_none_or(|rect| rect.contains(pointer)))
+            .unwrap_or(&camera);
+        let cursor = from_mq(view.screen_to_world(pointer - view.origin()));
+        let buttons = [
+            mq::MouseButton::Left,
+            mq::MouseButton::Right,
+            mq::MouseButton::Middle,
+        ];
+        scenes[foreground].set_inputs(Inputs {
+            cursor,
+            keys_down: OBJECT_KEYS
+                .into_iter()
+                .filter(|&k| mq::is_key_down(k))
+                .collect(),
+            mouse_buttons_down: buttons
+                .into_iter()
+                .filter(|&b| mq::is_mouse_button_down(b))
+                .collect(),
         });
+        let simulation = &mut scenes[foreground];
+        if Action::StopBall.is_pressed() {
+            let hovered = simulation.object_at(cursor);
+            if let Some(ball) = hovered.and_then(|h| simulation.get_as_mut::<Ball>(h)) {
+                ball.body.velocity = Vec2::ZERO;
+                ball.body.angular_velocity = 0.;
+            }
+        }
+        settings.handle_input(simulation, &mut controls);
+        ball_design.handle_input();
+        if overlays.is_on(Overlay::Hud) {
+            draw_quick_sliders(simulation);
+        }
+        // the left button goes to whichever tool's picked, unless it's over something on
+        // screen that wants it
+        let over_panels = pause_menu.open
+            || ui_wants_mouse()
+            || toolbar.wants_mouse(pointer)
+            || selection.wants_mouse(pointer);
+        toolbar.handle_input(pointer);
+        let tool = (!over_panels).then_some(toolbar.tool);
+        let clicked = mq::is_mouse_button_pressed(mq::MouseButton::Left);
+        selection.handle_input(simulation, pointer, cursor, tool == Some(Tool::Select));
+        wall_tool.handle_input(simulation, cursor, tool == Some(Tool::Wall));
+        handle_grab(simulation, cursor, tool == Some(Tool::Grab));
+        if clicked && tool == Some(Tool::Delete) {
+            pop_ball(simulation, cursor);
+        }
+        if clicked && tool == Some(Tool::Explode) {
+            blast(simulation, cursor);
+        }
+        let commands = controls.commands(to_mq(cursor), tool);
+        if let Some((pos, velocity)) = commands.launch {
+            let rng = simulation.rng();
+            let mut b = ball_design.ball(rng, from_mq(pos), from_mq(velocity));
+            b.sprite = sprite_balls.then(|| sprites[rng.gen_range(0, sprites.len())].clone());
+            simulation.add_pooled(b);
+        }
+        for (action, sides) in [(Action::Block, 4), (Action::Triangle, 3)] {
+            if action.is_pressed() {
+                let mut block = Block::regular(
+                    cursor,
+                    sides,
+                    simulation.rng().gen_range(0.375, 0.75),
+                    mq::ORANGE,
+                );
+                block.body.angle = simulation.rng().gen_range(-0.5, 0.5);
+                simulation.add_object(Box::from(block));
+            }
+        }
+        if let Some(preset) = commands.spawn {
+            SPAWN_PRESETS[preset].spawn(simulation, cursor);
+        }
+        if let Some(at) = commands.pop {
+            pop_ball(simulation, from_mq(at));
+        }
+        overlays.handle_keys();
+        // F12 takes the frame as it is, shift-F12 without the text and readouts over it
+        let screenshot = Action::Screenshot.is_pressed();
+        let shifted =
+            mq::is_key_down(mq::KeyCode::LeftShift) || mq::is_key_down(mq::KeyCode::RightShift);
+        let hide_hud = screenshot && shifted;
+        let toggle_recording = Action::Record.is_pressed();
+        if Action::SpeedColors.is_pressed() {
+            if let Some(coloring) = simulation.resource_mut::<SpeedColoring>() {
+                coloring.on = !coloring.on;
+            }
+        }
+        if Action::Glow.is_pressed() {
+            glow_on = !glow_on;
+        }
+        if Action::Sprites.is_pressed() {
+            sprite_balls = !sprite_balls;
+        }
+        if Action::Crate.is_pressed() {
+            let half_extents = simulation.rng().rand_vec2(0.25, 0.75, 0.25, 0.75);
+            simulation.add_object(Box::from(BoxBody::new(cursor, half_extents, Vec2::ZERO)));
+        }
+        if Action::Capsule.is_pressed() {
+            let rng = simulation.rng();
+            let capsule = Capsule::new(
+                cursor,
+                rng.gen_range(0.25, 0.625),
+                rng.gen_range(0.15, 0.3),
+                rng.gen_range(-1., 1.),
+            );
+            simulation.add_object(Box::from(capsule));
+        }
+        handle_gravity_keys(simulation, default_gravity);
+        if Action::Integrator.is_pressed() {
+            integrator_index = (integrator_index + 1) % 4;
+            simulation.set_integrator(match integrator_index {
+                0 => Box::new(SemiImplicitEuler),
+                1 => Box::new(VelocityVerlet),
+                2 => Box::new(Rk4),
+                _ => Box::new(ExplicitEuler),
+            });
+        }
+        if Action::ChargedBall.is_pressed() {
+            let positive = simulation.rng().gen_range(0, 2) == 0;
+            simulation.add_object(Box::from(Ball {
+                body: RigidBody {
+                    pos: cursor,
+                    radius: 0.3,
+                    material: Material::RUBBER,
+                    drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                    charge: if positive { BALL_CHARGE } else { -BALL_CHARGE },
+                    ..Default::default()
+                },
+                color: if positive { mq::PINK } else { mq::SKYBLUE },
+                expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+                trail: Trail::new(BALL_TRAIL_SECONDS),
+                sprite: None,
+                age: 0.,
+            }));
+        }
+        // right-clicking a ball pops it, and anywhere else sets off a blast
+        if mq::is_mouse_button_pressed(mq::MouseButton::Right) && !pop_ball(simulation, cursor) {
+            blast(simulation, cursor);
+        }
+        if Action::SoftBall.is_pressed() {
+            add_soft_ball(simulation, cursor, 0.625, mq::LIME);
+        }
+        if Action::Balloon.is_pressed() {
+            simulation.add_object(Box::from(Ball {
+                body: RigidBody {
+                    pos: cursor,
+                    radius: 0.45,
+                    material: Material::RUBBER,
+                    drag: Drag::Quadratic(SPHERE_DRAG_COEFFICIENT),
+                    gravity_scale: BALLOON_GRAVITY_SCALE,
+                    ..Default::default()
+                },
+                color: mq::MAGENTA,
+                expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+                trail: Trail::new(BALL_TRAIL_SECONDS),
+                sprite: None,
+                age: 0.,
+            }));
+        }
+        if Action::Slower.is_pressed() {
+            let scale = simulation.get_time_scale();
+            let slower = TIME_SCALES.iter().rev().find(|&&s| s < scale - 1e-9);
+            simulation.set_time_scale(slower.copied().unwrap_or(scale));
+        }
+        if Action::Faster.is_pressed() {
+            let scale = simulation.get_time_scale();
+            let faster = TIME_SCALES.iter().find(|&&s| s > scale + 1e-9);
+            simulation.set_time_scale(faster.copied().unwrap_or(scale));
+        }
+        if Action::NormalSpeed.is_pressed() {
+            simulation.set_time_scale(1.);
+        }
+        if Action::Electrostatics.is_pressed() {
+            simulation.set_electrostatics(!simulation.get_electrostatics());
+        }
+        if Action::Emitter.is_pressed() {
+            simulation.add_object(Box::from(Emitter {
+                pos: cursor,
+                rate: 8.,
+                burst: 2,
+                direction: -consts::FRAC_PI_2,
+                spread: 0.25,
+                speed: (6.25, 8.75),
+                ball_lifetime: 3.,
+                due: 0.,
+                age: 0.,
+            }));
+        }
+        if Action::GravityWell.is_pressed() {
+            simulation.add_object(Box::from(GravityWell {
+                pos: cursor,
+                strength: GRAVITY_WELL_STRENGTH,
+                age: 0.,
+            }));
+        }
+        if Action::Save.is_pressed() {
+            match simulation.save(SAVE_PATH) {
+                Ok(()) => toasts.push("Scene saved"),
+                Err(err) => {
+                    eprintln!("couldn't save to {SAVE_PATH}: {err}");
+                    toasts.push(format!("Couldn't save: {err}"));
+                }
+            }
+        }
+        if Action::Load.is_pressed() {
+            load_sandbox(simulation, &toasts);
+        }
         // Handle Ticks
+        // the menu has things paused until it's closed
+        if commands.toggle_pause && !pause_menu.open {
+            let paused = !scenes[foreground].is_paused();
+            scenes.iter_mut().for_each(|(_, s)| s.set_paused(paused));
+        }
         let time = mq::get_time();
-        simulation.do_tick(time);
+        let rewinding = Action::Rewind.is_down();
+        // `Instant` isn't there in the browser, miniquad's clock is
+        let ticking = macroquad::miniquad::date::now();
+        for (layer, simulation) in scenes.iter_mut() {
+            if rewinding && layer == foreground {
+                simulation.do_rewind(time);
+            } else {
+                simulation.do_tick(time);
+            }
+        }
+        timings.push_back(FrameTiming {
+            at: time,
+            frame_seconds: mq::get_frame_time(),
+            tick_seconds: (macroquad::miniquad::date::now() - ticking) as f32,
+        });
+        while timings
+            .front()
+            .is_some_and(|t| time - t.at > TIMING_HISTORY_SECONDS)
+        {
+            timings.pop_front();
+        }
+        let simulation = &mut scenes[foreground];
+        let hard_hits: Vec<_> = simulation
+            .drain_events()
+            .filter_map(|event| match event {
+                Event::Collision(hit) if hit.impulse > SPARK_IMPULSE_THRESHOLD => Some(hit.point),
+                _ => None,
+            })
+            .collect();
+        for pos in hard_hits {
+            simulation.spawn(spark(pos));
+        }
 
         // Handle Expiry
-        simulation.do_handle_expiry();
+        scenes.do_handle_expiry();
 
         // Handle Drawing
         mq::clear_background(mq::BLACK);
-        draw_dbg_text(
-            time,
-            simulation.get_tick_count(),
-            frames_so_far,
-            simulation.get_object_count(),
+        let views = split_views(camera, drawn_ball_pos(&scenes[foreground], followed));
+        let split = views.len() > 1;
+        for &view in &views {
+            let mut screen = MacroquadRenderer;
+            overlays.draw_under(
+                &mut DrawContext::new(&mut screen, view, 0., 0.),
+                &scenes[foreground],
+            );
+            scenes.do_draw(&mut MacroquadRenderer, view);
+            // laid over the whole window, so it's left off while the window is split
+            if let Some(glow) = glow.as_mut().filter(|_| glow_on && !split) {
+                let simulation = &scenes[foreground];
+                glow.draw_sources(|| simulation.do_draw_glow(&mut MacroquadRenderer, view));
+                glow.composite(GLOW_STRENGTH);
+            }
+            let simulation = &scenes[foreground];
+            let mut screen = MacroquadRenderer;
+            let (tick_fraction, lead) = (simulation.tick_fraction(), simulation.lead_seconds());
+            let mut ctx = DrawContext::new(&mut screen, view, tick_fraction, lead);
+            overlays.draw(&mut ctx, simulation);
+            if Action::Laser.is_down() {
+                draw_laser(&mut ctx, simulation, cursor);
+            }
+            if let Some((from, velocity)) = commands.aim {
+                draw_launch_preview(&mut ctx, from, velocity);
+            }
+            selection.draw_highlight(&mut ctx, simulation);
+            if toolbar.tool == Tool::Wall {
+                wall_tool.draw_preview(&mut ctx, simulation, cursor);
+            }
+            let held = simulation.mouse_joint().and_then(|joint| {
+                Some((
+                    drawn_ball_pos(simulation, Some(joint.body))?,
+                    to_mq(joint.target),
+                ))
+            });
+            if let Some((from, to)) = held {
+                ctx.draw_line(from.x, from.y, to.x, to.y, 0.025, mq::LIGHTGRAY);
+            }
+        }
+        if split {
+            let middle = mq::screen_width() / 2.;
+            mq::draw_line(middle, 0., middle, mq::screen_height(), 2., mq::GRAY);
+        }
+        let simulation = &scenes[foreground];
+        let stats = simulation.stats();
+        stats_history.push_back(StatsSample {
+            at: time,
+            object_count: stats.object_count,
+            kinetic_energy: to_f32(stats.kinetic_energy),
+        });
+        while stats_history
+            .front()
+            .is_some_and(|s| time - s.at > STATS_HISTORY_SECONDS)
+        {
+            stats_history.pop_front();
+        }
+        if overlays.is_on(Overlay::Hud) && !hide_hud {
+            draw_dbg_text(time, simulation.get_tick_count(), frames_so_far, &stats);
+            draw_timing_graph(&timings, time);
+            draw_stats_graphs(&stats_history, time);
+            mq::draw_text(
+                &format!(
+                    "Integrator: {} (I to cycle, H for an energy graph, / for every key)",
+                    simulation.get_integrator_name()
+                ),
+                5.,
+                36.,
+                16.,
+                mq::WHITE,
+            );
+            mq::draw_text(&overlays.legend(), 5., 50., 16., mq::WHITE);
+            mq::draw_text(
+                &format!(
+                    "Electrostatics: {} (E to toggle, Q for a charged ball)",
+                    if simulation.get_electrostatics() {
+                        "on"
+                    } else {
+                        "off"
+                    }
+                ),
+                5.,
+                66.,
+                16.,
+                mq::WHITE,
+            );
+            mq::draw_text(
+            &format!(
+                "Time scale: {:.2}x{} (- and = to change, 0 to reset, hold R to rewind, space to pause)",
+                simulation.get_time_scale(),
+                if simulation.is_paused() { ", paused" } else { "" }
+            ),
+            5.,
+            82.,
+            16.,
+            mq::WHITE,
         );
-        simulation.do_draw();
+            mq::draw_text(
+                &format!(
+                    "F6 to save, F9 to load, F12 for a screenshot, F8 to record, hold A to aim emitters, K for picture balls ({})",
+                    if sprite_balls { "on" } else { "off" }
+                ),
+                5.,
+                98.,
+                16.,
+                mq::WHITE,
+            );
+            mq::draw_text(
+                "Scroll to zoom, drag with the middle button to pan, Home to reset the view",
+                5.,
+                114.,
+                16.,
+                mq::WHITE,
+            );
+            if let Some(TopSpeed(top)) = simulation.resource() {
+                mq::draw_text(
+                    &format!("Top speed: {top:.1} m/s"),
+                    5.,
+                    130.,
+                    16.,
+                    mq::WHITE,
+                );
+            }
+            if let Some(coloring) = simulation.resource::<SpeedColoring>() {
+                let text = if coloring.on {
+                    format!("Colours: by speed, red at {:.0} m/s", coloring.top_speed)
+                } else {
+                    "Colours: each ball's own".to_string()
+                };
+                let glow = if glow_on { "on" } else { "off" };
+                let text = format!("{text} (V to toggle), glow {glow} (N)");
+                mq::draw_text(&text, 5., 146., 16., mq::WHITE);
+            }
+            mq::draw_text(
+                &format!(
+                    "Alt+Enter for fullscreen, W to fit the walls to the window ({}), Tab to follow the ball under the cursor",
+                    if fit_window { "on" } else { "off" }
+                ),
+                5.,
+                162.,
+                16.,
+                mq::WHITE,
+            );
+            let presets: Vec<_> = SPAWN_PRESETS.iter().map(|p| p.name).collect();
+            let mut text = format!("1-9 at the cursor: {}", presets.join(", "));
+            if let Some(preset) = controls.gamepad_preset() {
+                text += &format!(" (gamepad: {}, bumpers to change)", preset.name);
+            }
+            mq::draw_text(&text, 5., 178., 16., mq::WHITE);
+            let text = "Pick what the left button does below: Z to select a ball and change it";
+            mq::draw_text(text, 5., 210., 16., mq::WHITE);
+            let text = "J to draw walls, snapping to ends and a grid, Backspace to undo";
+            mq::draw_text(text, 5., 226., 16., mq::WHITE);
+            let text = "Y for live settings, . to design the next ball, Esc for the menu";
+            mq::draw_text(text, 5., 242., 16., mq::WHITE);
+            let streaming = if controls.streaming { "on" } else { "off" };
+            let text = format!(
+                "U to stream balls while the left button's held ({streaming}, {}/s, [ and ] to change)",
+                controls.stream_rate
+            );
+            mq::draw_text(&text, 5., 194., 16., mq::WHITE);
+            draw_vector_indicator(
+                0,
+                "wind",
+                to_mq(simulation.get_wind()),
+                // dashed, as wind is only felt by what's out in it
+                &ArrowStyle {
+                    dash: Some((4., 3.)),
+                    color: ArrowColor::Fixed(mq::SKYBLUE),
+                    length_per_unit: WIND_ARROW_LEN_MULTIPLIER,
+                    max_length: 30.,
+                    ..Default::default()
+                },
+            );
+            draw_vector_indicator(
+                1,
+                "gravity",
+                to_mq(simulation.get_gravity()),
+                &ArrowStyle {
+                    color: ArrowColor::Fixed(mq::GREEN),
+                    length_per_unit: GRAVITY_ARROW_LEN_MULTIPLIER,
+                    max_length: 30.,
+                    ..Default::default()
+                },
+            );
+        }
+        if Action::EnergyGraph.is_pressed() {
+            show_energy = !show_energy;
+        }
+        if Action::Help.is_pressed() {
+            show_help = !show_help;
+        }
+        if !hide_hud {
+            if show_energy {
+                draw_energy_graph(simulation.get_energy_history());
+            }
+            draw_inspector(simulation, cursor, pointer);
+            selection.draw_panel(simulation);
+            toolbar.draw();
+            toolbar.draw_cursor(pointer);
+            if show_help {
+                draw_help();
+            }
+        }
+        if screenshot {
+            match save_screenshot(&screenshot_dir) {
+                Ok(path) => {
+                    println!("saved {path}");
+                    toasts.push("Screenshot saved");
+                }
+                Err(err) => {
+                    eprintln!("couldn't save a screenshot to {screenshot_dir}: {err}");
+                    toasts.push(format!("Couldn't save a screenshot: {err}"));
+                }
+            }
+        }
+        let tick = scenes[foreground].get_tick_count();
+        let full = recorder.as_mut().is_some_and(|r| !r.capture(tick));
+        if toggle_recording || full {
+            match recorder.take() {
+                Some(finished) => {
+                    toasts.push("Recording stopped, saving it");
+                    save_recording(finished, &screenshot_dir, record_format, toasts.clone());
+                }
+                None => {
+                    toasts.push("Recording started");
+                    let tick_len = scenes[foreground].get_tick_len();
+                    let ticks_per_frame = (1. / (tick_len * RECORD_FPS as f64)).round().max(1.);
+                    let max_frames = (RECORD_MAX_SECONDS * RECORD_FPS) as usize;
+                    let downscale = RECORD_DOWNSCALE;
+                    recorder = Some(Recorder::new(
+                        ticks_per_frame as usize,
+                        downscale,
+                        max_frames,
+                    ));
+                }
+            }
+        }
+        // after capturing, so it stays out of the recording
+        if let Some(recorder) = &recorder {
+            let seconds = recorder.frame_count() as f32 / RECORD_FPS as f32;
+            let text = format!("REC {seconds:.1}s (F8 to stop)");
+            mq::draw_circle(mq::screen_width() - 200., 80., 6., mq::RED);
+            mq::draw_text(&text, mq::screen_width() - 188., 85., 16., mq::RED);
+        }
+        // last of all, so they're in neither screenshots nor recordings, and clear of the
+        // graph and sliders along the bottom when those are up
+        let clearance = if overlays.is_on(Overlay::Hud) && !hide_hud {
+            TIMING_GRAPH_SIZE.y + QUICK_SLIDERS_SIZE.y + 44.
+        } else {
+            10.
+        };
+        let bottom = mq::vec2(mq::screen_width() / 2., mq::screen_height() - clearance);
+        toasts.draw(&mut MacroquadRenderer, time, bottom);
 
         frames_so_far += 1;
         mq::next_frame().await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::render::NullRenderer;
+
+    const TICK: f64 = 1. / 120.;
+
+    fn ball(pos: Vec2, velocity: Vec2) -> Box<dyn TickDrawExpire> {
+        Box::new(Ball {
+            body: RigidBody {
+                pos,
+                prev_pos: pos,
+                velocity,
+                radius: 0.25,
+                ..Default::default()
+            },
+            color: mq::WHITE,
+            expiry: ExpiryPolicy::on_floor(BALL_EXPIRY_TIME),
+            trail: Trail::new(BALL_TRAIL_SECONDS),
+            sprite: None,
+            age: 0.,
+        })
+    }
+
+    fn state(simulation: &Simulation) -> Vec<(ObjectHandle, Vec2, Vec2)> {
+        let bodies = simulation.bodies();
+        bodies.map(|(h, b)| (h, b.pos, b.velocity)).collect()
+    }
+
+    #[test]
+    fn ball_falls_along_the_analytic_path() {
+        let gravity = vec2(0., 9.81);
+        // exact for constant acceleration, so only rounding is left to disagree
+        let mut simulation = Simulation::builder()
+            .tick_len(TICK)
+            .gravity(gravity)
+            .integrator(Box::new(VelocityVerlet))
+            .build();
+        let (start, launch) = (vec2(1., 2.), vec2(3., -4.));
+        let handle = simulation.add_object(ball(start, launch));
+        simulation.run_for(120);
+        assert_eq!(simulation.get_tick_count(), 120);
+        let t = simulation.get_time() as Real;
+        let body = simulation.get_body(handle).expect("nothing to expire it");
+        let expected = start + launch * t + gravity * (0.5 * t * t);
+        assert!(
+            body.pos.distance(expected) < 1e-3,
+            "at {}, expected {expected}",
+            body.pos
+        );
+        assert!(body.velocity.distance(launch + gravity * t) < 1e-3);
+    }
+
+    #[test]
+    fn seeded_rng_repeats_itself() {
+        let draws = |seed| {
+            let mut rng = Rng::new(seed);
+            let ints = (0..64).map(|_| rng.next_u32()).collect::<Vec<_>>();
+            let floats = (0..64)
+                .map(|_| rng.gen_range(-1., 1.))
+                .collect::<Vec<Real>>();
+            (ints, floats)
+        };
+        assert_eq!(draws(42), draws(42));
+        assert_ne!(draws(42), draws(43));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let build = || {
+            Simulation::builder()
+                .tick_len(TICK)
+                .gravity(vec2(0., 9.81))
+                .seed(7)
+                .add_plugin(&SandboxSaves)
+                .build()
+        };
+        let mut saved = build();
+        saved.add_object(ball(vec2(1., 1.), vec2(2., 0.)));
+        saved.add_object(ball(vec2(1.5, 1.), vec2(-2., 1.)));
+        saved.run_for(30);
+        let path = std::env::temp_dir().join(format!("bouncing-ball-{}.save", std::process::id()));
+        saved.save(&path).expect("temp dir is writable");
+        let mut loaded = build();
+        let result = loaded.load(&path);
+        std::fs::remove_file(&path).ok();
+        result.expect("reads back what it wrote");
+        assert_eq!(loaded.get_tick_count(), saved.get_tick_count());
+        assert_eq!(state(&loaded), state(&saved));
+        // and carries on the same way, so nothing that matters was left out
+        saved.run_for(60);
+        loaded.run_for(60);
+        assert_eq!(state(&loaded), state(&saved));
+    }
+
+    #[test]
+    fn effects_never_evict_objects_at_the_cap() {
+        let mut simulation = Simulation::builder()
+            .tick_len(TICK)
+            .object_cap(ObjectCap {
+                max: 2,
+                eviction: Eviction::Oldest,
+            })
+            .build();
+        let first = simulation.add_object(ball(Vec2::ZERO, Vec2::ZERO));
+        let second = simulation.add_object(ball(Vec2::X, Vec2::ZERO));
+        for _ in 0..5 {
+            simulation.spawn(spark(Vec2::ZERO));
+        }
+        assert!(simulation.get(first).is_some() && simulation.get(second).is_some());
+        assert_eq!(simulation.get_object_count(), 2);
+        assert_eq!(simulation.get_entity_count(), 5);
+        // while a real object still makes way for another
+        simulation.add_object(ball(Vec2::Y, Vec2::ZERO));
+        assert!(simulation.get(first).is_none() && simulation.get(second).is_some());
+    }
+
+    #[test]
+    fn entities_move_bounce_and_expire_by_their_components() {
+        let mut simulation = Simulation::builder()
+            .tick_len(TICK)
+            .rewind_seconds(1.)
+            .build();
+        simulation.add_static(StaticCollider::Segment(vec2(-5., 1.), vec2(5., 1.)));
+        let drifting = simulation.spawn(
+            Entity::new()
+                .with(Transform::at(Vec2::ZERO))
+                .with(Velocity::linear(vec2(1., 0.)))
+                .with(Lifetime::new(0.5)),
+        );
+        // heading down into the floor
+        let falling = simulation.spawn(particle(Vec2::ZERO, vec2(0., 4.), mq::WHITE));
+        let pos = |sim: &Simulation, handle| sim.components().get::<Transform>(handle).unwrap().pos;
+        simulation.run_for(30);
+        assert!((pos(&simulation, drifting).x - 0.25).abs() < 1e-4);
+        assert!(pos(&simulation, falling).y < 1.);
+        let bounced = simulation.components().get::<Velocity>(falling).unwrap();
+        assert!(bounced.linear.y < 0.);
+        // systems see them too, and anything can have another component attached
+        simulation.components_mut().insert(drifting, mq::WHITE);
+        assert_eq!(simulation.components().iter::<mq::Color>().count(), 1);
+        simulation.run_for(60);
+        assert_eq!(simulation.get_entity_count(), 0);
+        assert!(simulation.components().get::<Transform>(drifting).is_none());
+        assert!(simulation
+            .components_mut()
+            .insert(drifting, mq::BLACK)
+            .is_none());
+        assert_eq!(simulation.components().iter::<mq::Color>().count(), 0);
+        // and rewinding brings them back as they were
+        while simulation.do_rewind(0.) && simulation.get_tick_count() > 30 {}
+        assert_eq!(simulation.get_entity_count(), 2);
+        assert!((pos(&simulation, drifting).x - 0.25).abs() < 1e-4);
+    }
+
+    #[test]
+    fn png_is_well_formed() {
+        let (width, height) = (3, 2);
+        let rgba: Vec<u8> = (0..width * height * 4).map(|i| (i * 10) as u8).collect();
+        let mut png = Vec::new();
+        record::write_png(&mut png, width, height, &rgba).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        // IEND holds nothing, so its checksum is always the same
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+    }
+
+    #[test]
+    fn gif_is_well_formed() {
+        let frame = record::Frame {
+            width: 3,
+            height: 2,
+            rgb: (0..3 * 2 * 3).map(|i| (i * 14) as u8).collect(),
+        };
+        let mut gif = Vec::new();
+        record::write_gif(&mut gif, &[frame.clone(), frame], 30).unwrap();
+        assert!(gif.starts_with(b"GIF89a\x03\x00\x02\x00"));
+        let frames = gif.windows(2).filter(|w| w == b"\x21\xf9").count();
+        assert_eq!(frames, 2);
+        assert_eq!(gif.last(), Some(&0x3b));
+    }
+
+    fn body(pos: Vec2, shape: Shape, radius: Real) -> RigidBody {
+        RigidBody {
+            pos,
+            prev_pos: pos,
+            radius,
+            shape,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn polygons_collide_along_the_shallowest_axis() {
+        use engine::physics::{polygon_circle, polygon_polygon};
+        let square = [vec2(-1., -1.), vec2(1., -1.), vec2(1., 1.), vec2(-1., 1.)];
+        let moved = |by: Vec2| square.map(|v| v + by);
+        let contact = polygon_polygon(&square, &moved(vec2(1.8, 0.5))).expect("they overlap");
+        assert!(contact.normal.distance(Vec2::X) < 1e-5);
+        assert!((contact.depth - 0.2).abs() < 1e-5);
+        assert!(polygon_polygon(&square, &moved(vec2(2.1, 0.))).is_none());
+        let contact = polygon_circle(&square, vec2(0., 1.2), 0.5).expect("the circle dips in");
+        assert!(contact.normal.distance(Vec2::Y) < 1e-5);
+        assert!((contact.depth - 0.3).abs() < 1e-5);
+        // past a corner the push is out from the corner, not either face
+        let contact = polygon_circle(&square, vec2(1.3, 1.3), 0.5).expect("close to the corner");
+        assert!(contact.normal.distance(Vec2::ONE.normalize()) < 1e-5);
+        assert!(polygon_circle(&square, vec2(1.4, 1.4), 0.5).is_none());
+    }
+
+    #[test]
+    fn capsules_collide_along_their_length_and_round_ends() {
+        use engine::physics::collide;
+        let shape = Shape::Capsule {
+            half_length: 1.,
+            radius: 0.25,
+        };
+        let capsule = body(Vec2::ZERO, shape, 1.25);
+        let ball = |pos| body(pos, Shape::Circle, 0.25);
+        let contact = collide(&capsule, &ball(vec2(0.9, 0.4))).expect("touching its side");
+        assert!(contact.normal.distance(Vec2::Y) < 1e-5);
+        assert!((contact.depth - 0.1).abs() < 1e-5);
+        let contact = collide(&ball(vec2(1.4, 0.)), &capsule).expect("touching its end");
+        assert!(contact.normal.distance(-Vec2::X) < 1e-5);
+        assert!(collide(&capsule, &ball(vec2(1.6, 0.))).is_none());
+        // turned upright, the same ball misses it
+        let upright = RigidBody {
+            angle: consts::FRAC_PI_2,
+            ..capsule
+        };
+        assert!(collide(&upright, &ball(vec2(0.9, 0.4))).is_none());
+    }
+
+    #[test]
+    fn fast_balls_dont_tunnel_through_thin_walls() {
+        let mut simulation = Simulation::builder().tick_len(TICK).build();
+        simulation.add_static(StaticCollider::Segment(vec2(-5., 5.), vec2(5., 5.)));
+        // a couple of metres short of the wall and clear past it in a single tick
+        let handle = simulation.add_object(ball(vec2(0., 3.), vec2(0., 600.)));
+        simulation.run_for(1);
+        let body = simulation.get_body(handle).expect("nothing to expire it");
+        assert!(body.pos.y < 5., "went through to {}", body.pos.y);
+        assert!(body.velocity.y <= 0.);
+        assert!(engine::physics::sweep_circle_segment(
+            vec2(0., 3.),
+            vec2(0., 5.),
+            0.25,
+            vec2(-5., 5.),
+            vec2(5., 5.),
+        )
+        .is_some_and(|t| (t - 0.35).abs() < 1e-5));
+    }
+
+    #[test]
+    fn constraints_hold_their_bodies_in_place() {
+        let mut simulation = Simulation::builder()
+            .tick_len(TICK)
+            .gravity(vec2(0., 9.81))
+            .build();
+        let pivot = vec2(0., 0.);
+        let bob = simulation.add_object(ball(vec2(1., 0.), Vec2::ZERO));
+        simulation.add_constraint(DistanceConstraint {
+            a: ObjectAnchor::Point(pivot),
+            b: ObjectAnchor::Object(bob),
+            length: 1.,
+        });
+        let axle = vec2(4., 0.);
+        let wheel = simulation.add_object(ball(axle, vec2(2., 0.)));
+        simulation.add_hinge(HingeJoint {
+            body: wheel,
+            pivot: Vec2::ZERO,
+            anchor: HingeAnchor::World(axle),
+        });
+        simulation.run_for(120);
+        let swinging = simulation.get_body(bob).expect("nothing to expire it");
+        assert!((swinging.pos.distance(pivot) - 1.).abs() < 0.01);
+        // well away from where it was let go, half a swing on
+        assert!(swinging.pos.distance(vec2(1., 0.)) > 1.);
+        let pinned = simulation.get_body(wheel).expect("nothing to expire it");
+        assert!(pinned.pos.distance(axle) < 0.01);
+    }
+
+    #[test]
+    fn queries_find_the_bodies_in_a_region() {
+        let mut simulation = Simulation::builder().tick_len(TICK).build();
+        let near = simulation.add_object(ball(Vec2::ZERO, Vec2::ZERO));
+        let middle = simulation.add_object(ball(vec2(3., 0.), Vec2::ZERO));
+        let far = simulation.add_object(ball(vec2(6., 0.), Vec2::ZERO));
+        simulation.run_for(1);
+        let mut found = simulation.query_aabb(Aabb {
+            min: vec2(-1., -1.),
+            max: vec2(3., 1.),
+        });
+        found.sort_by_key(|h| simulation.get_body(*h).map(|b| b.pos.x as i32));
+        assert_eq!(found, [near, middle]);
+        assert_eq!(simulation.query_circle(vec2(6.5, 0.), 0.3), [far]);
+        assert!(simulation.query_circle(vec2(1.5, 0.), 1.).is_empty());
+    }
+
+    #[test]
+    fn expiry_policies_combine() {
+        let mut either = ExpiryPolicy::lifetime(1.).or(ExpiryPolicy::lifetime(3.));
+        let mut both = ExpiryPolicy::lifetime(1.).and(ExpiryPolicy::lifetime(3.));
+        let mut forever = ExpiryPolicy::lifetime(1.).and(ExpiryPolicy::Never);
+        assert_eq!(either.time_left(), 1.);
+        assert_eq!(both.time_left(), 3.);
+        assert_eq!(forever.time_left(), f64::INFINITY);
+        for policy in [&mut either, &mut both, &mut forever] {
+            policy.on_tick(None, 1.5);
+        }
+        assert!(either.is_expired(None));
+        assert!(!both.is_expired(None));
+        assert_eq!(both.time_left(), 1.5);
+        assert!(!forever.is_expired(None));
+        // there's no body for the floor timer to see resting, so it never runs
+        let mut floored = ExpiryPolicy::on_floor(1.).or(ExpiryPolicy::Never);
+        floored.on_tick(None, 5.);
+        assert!(!floored.is_expired(None));
+        both.on_tick(None, 1.5);
+        assert!(both.is_expired(None));
+    }
+
+    #[test]
+    fn eviction_policies_pick_their_victims() {
+        let run = |eviction| {
+            let mut simulation = Simulation::builder()
+                .tick_len(TICK)
+                .object_cap(ObjectCap { max: 3, eviction })
+                .build();
+            let existing =
+                [3., 1., 2.].map(|speed| simulation.add_object(ball(Vec2::ZERO, Vec2::X * speed)));
+            let newest = simulation.add_object(ball(Vec2::ZERO, Vec2::ZERO));
+            let survivors = existing.map(|h| simulation.get(h).is_some());
+            (survivors, simulation.get(newest).is_some())
+        };
+        assert_eq!(run(Eviction::Reject), ([true, true, true], false));
+        assert_eq!(run(Eviction::Oldest), ([false, true, true], true));
+        assert_eq!(run(Eviction::Slowest), ([true, false, true], true));
+    }
+
+    #[test]
+    fn rewind_goes_back_to_the_last_snapshot() {
+        let mut simulation = Simulation::builder()
+            .tick_len(TICK)
+            .gravity(vec2(0., 9.81))
+            .rewind_seconds(1.)
+            .build();
+        simulation.add_object(ball(Vec2::ZERO, vec2(1., 0.)));
+        // snapshots are taken every few ticks, and thirty is a multiple of any few
+        simulation.run_for(30);
+        let (tick, snapshot) = (simulation.get_tick_count(), state(&simulation));
+        simulation.run_for(1);
+        let late = simulation.add_object(ball(Vec2::X, Vec2::ZERO));
+        assert!(simulation.do_rewind(0.));
+        assert_eq!(simulation.get_tick_count(), tick);
+        assert_eq!(state(&simulation), snapshot);
+        assert!(simulation.get(late).is_none());
+        // a second of history at most, so it runs out
+        let rewinds = std::iter::from_fn(|| simulation.do_rewind(0.).then_some(())).count();
+        assert!(rewinds < 30);
+        assert!(!simulation.do_rewind(0.));
+    }
+
+    #[test]
+    fn threads_give_the_same_results_as_one() {
+        // enough bodies that integration really is split into several runs
+        let bodies = 2 * 1024 + 100;
+        let side = (bodies as Real).sqrt().ceil() as usize;
+        let run = |threads| {
+            let mut simulation = Simulation::builder()
+                .tick_len(TICK)
+                .gravity(vec2(0., 9.81))
+                .bounds(Aabb {
+                    min: Vec2::ZERO,
+                    max: Vec2::splat(side as Real),
+                })
+                .threads(threads)
+                .build();
+            simulation.add_static(StaticCollider::Segment(
+                vec2(0., side as Real),
+                vec2(side as Real, side as Real),
+            ));
+            for i in 0..bodies {
+                // close enough to bump into each other as they fall
+                let pos = vec2((i % side) as Real, (i / side) as Real * 0.6);
+                simulation.add_object(ball(pos, vec2((i % 7) as Real - 3., 0.)));
+            }
+            simulation.run_for(5);
+            state(&simulation)
+        };
+        assert_eq!(run(3), run(1));
+    }
+
+    #[test]
+    fn same_seed_runs_are_bit_identical() {
+        let run = |seed| {
+            let mut simulation = Simulation::builder()
+                .tick_len(TICK)
+                .gravity(vec2(0., 9.81))
+                .seed(seed)
+                .threads(2)
+                .build();
+            simulation.add_static(StaticCollider::Segment(vec2(0., 8.), vec2(8., 8.)));
+            for _ in 0..40 {
+                let pos = simulation.rng().rand_vec2(0.5, 7.5, 0.5, 6.);
+                let velocity = simulation.rng().rand_vec2(-4., 4., -4., 4.);
+                simulation.add_object(ball(pos, velocity));
+            }
+            simulation.run_for(240);
+            let bits = |v: Vec2| (v.x.to_bits(), v.y.to_bits());
+            state(&simulation)
+                .into_iter()
+                .map(|(h, pos, velocity)| (h, bits(pos), bits(velocity)))
+                .collect::<Vec<_>>()
+        };
+        // equal floats can still differ in their bits, like 0 and -0, so compare those
+        assert_eq!(run(11), run(11));
+        assert_ne!(run(11), run(12));
+    }
+
+    /// Not a check, just numbers for whether spreading integration over threads pays:
+    /// `cargo test --release -- --ignored --nocapture` prints how long a tick of a big,
+    /// scattered scene takes on one thread and on every core
+    #[test]
+    #[ignore]
+    fn threads_benchmark() {
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get());
+        for bodies in [400, 2_000, 4_000] {
+            for threads in [1, cores] {
+                let side = (bodies as Real).sqrt().ceil() as usize;
+                let bounds = Aabb {
+                    min: Vec2::ZERO,
+                    max: Vec2::splat(2. * side as Real),
+                };
+                let mut simulation = Simulation::builder()
+                    .tick_len(TICK)
+                    .gravity(vec2(0., 9.81))
+                    .bounds(bounds)
+                    .threads(threads)
+                    .build();
+                for i in 0..bodies {
+                    let pos = 2. * vec2((i % side) as Real, (i / side) as Real);
+                    simulation.add_object(ball(pos, Vec2::ZERO));
+                }
+                let start = std::time::Instant::now();
+                simulation.run_for(60);
+                let per_tick = start.elapsed().as_secs_f64() * 1000. / 60.;
+                eprintln!("{bodies} bodies on {threads} threads: {per_tick:.2} ms a tick");
+            }
+        }
+    }
+
+    #[test]
+    fn draws_without_a_window() {
+        let mut simulation = Simulation::builder().tick_len(TICK).build();
+        simulation.add_object(ball(Vec2::ZERO, Vec2::X));
+        simulation.run_for(10);
+        simulation.do_draw(&mut NullRenderer, Camera::default());
+    }
+}